@@ -0,0 +1,171 @@
+use std::path::{Path, PathBuf};
+use std::fs;
+
+use crate::notes;
+use crate::{error, warn};
+
+
+// generic constants
+pub const ATTACHMENTS_SUFFIX: &str = ".attachments";
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "bmp", "webp", "svg", "tiff"];
+
+
+/**
+Get the attachments directory of a note on the default path.
+# Parameters:
+* `note_title`: The title of the note without extension.
+# Return:
+The path to the note's attachments directory, which may not exist.
+ */
+pub fn attachments_dir(note_title: &str) -> PathBuf {
+    notes::notes_dir().join(format!("{note_title}{ATTACHMENTS_SUFFIX}"))
+}
+
+
+/**
+List the attachments of a note.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+A vector with the path of every attachment sorted by name, empty if there are none.
+ */
+pub fn list_attachments(note_title: &str) -> Vec<PathBuf> {
+    let dir = attachments_dir(note_title);
+    if !dir.exists() {
+        return Vec::new();
+    }
+
+    let dir_iterator = match fs::read_dir(&dir) {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            error!("Error reading directory {dir:?}: {e}");
+            return Vec::new();
+        }
+    };
+
+    let mut result: Vec<PathBuf> = Vec::new();
+    for entry in dir_iterator {
+        match entry {
+            Ok(entry) if entry.path().is_file() => result.push(entry.path()),
+            Ok(_) => continue,
+            Err(e) => error!("Error reading file entry: {e}"),
+        }
+    }
+
+    result.sort();
+    result
+}
+
+
+/**
+Copy a file into a note's attachments directory.
+If an attachment with the same name exists a number is added to the new one's name.
+# Parameters:
+* `note_title`: The title of the note.
+* `source`: The path of the file to attach.
+# Return:
+The path of the stored attachment or `None` if it could not be copied.
+ */
+pub fn add_attachment(note_title: &str, source: &Path) -> Option<PathBuf> {
+    if !notes::note_exists(note_title) {
+        warn!("Note {note_title} does not exist");
+        return None;
+    }
+
+    let file_name = match source.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => {
+            warn!("Path {source:?} is not a file");
+            return None;
+        }
+    };
+
+    let dir = attachments_dir(note_title);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Failed to create directory {dir:?}: {e}");
+        return None;
+    }
+
+    let target = unique_path(&dir, &file_name);
+    if let Err(e) = fs::copy(source, &target) {
+        error!("Error copying {source:?} to {target:?}: {e}");
+        return None;
+    }
+
+    Some(target)
+}
+
+
+/**
+Remove a single attachment from a note.
+# Parameters:
+* `note_title`: The title of the note.
+* `file_name`: The attachment's file name.
+# Return:
+Boolean stating if the attachment was removed.
+ */
+pub fn remove_attachment(note_title: &str, file_name: &str) -> bool {
+    let path = attachments_dir(note_title).join(file_name);
+    if let Err(e) = fs::remove_file(&path) {
+        error!("Error deleting attachment at path {path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Delete a note's attachments directory with everything in it, if there is one.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+Boolean stating if the attachments are gone.
+ */
+pub fn delete_attachments(note_title: &str) -> bool {
+    let dir = attachments_dir(note_title);
+    if !dir.exists() {
+        return true;
+    }
+
+    if let Err(e) = fs::remove_dir_all(&dir) {
+        error!("Error deleting directory {dir:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Check if an attachment is an image by its extension.
+ */
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .is_some_and(|extension| IMAGE_EXTENSIONS.contains(&extension.as_str()))
+}
+
+
+/**
+Get a path inside a directory that doesn't exist yet, adding " (n)" to the file stem if needed.
+ */
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let as_path = Path::new(file_name);
+    let stem = as_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = as_path.extension().map(|extension| format!(".{}", extension.to_string_lossy()));
+
+    let mut counter = 1;
+    loop {
+        let candidate = dir.join(format!("{stem} ({counter}){}", extension.as_deref().unwrap_or("")));
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
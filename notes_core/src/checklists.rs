@@ -0,0 +1,231 @@
+use crate::notes;
+use crate::warn;
+
+
+// Task line markers, e.g. "- [ ] buy milk" or "- [x] buy milk"
+const TASK_PREFIX: &str = "- [";
+const TASK_SUFFIX: &str = "]";
+const OPEN_MARK: char = ' ';
+const DONE_MARK: char = 'x';
+
+
+/**
+A task line found in a note.
+ */
+#[derive(Clone)]
+pub struct TaskItem {
+    /// Zero based line number of the task inside the note.
+    pub line: usize,
+    /// The task text without its marker.
+    pub text: String,
+    /// Whether the task is checked.
+    pub done: bool,
+}
+
+
+/**
+An open task together with the note it belongs to.
+ */
+pub struct OpenTask {
+    pub note_title: String,
+    pub task: TaskItem,
+}
+
+
+/**
+Parse a single line as a task.
+# Parameters:
+* `line`: A line of a note.
+# Return:
+A tuple with the checked state and the task text, or `None` if the line is not a task.
+ */
+fn parse_task_line(line: &str) -> Option<(bool, String)> {
+    let rest = line.trim_start().strip_prefix(TASK_PREFIX)?;
+
+    let mut chars = rest.chars();
+    let done = match chars.next()? {
+        OPEN_MARK => false,
+        'x' | 'X' => true,
+        _ => return None,
+    };
+    let text = chars.as_str().strip_prefix(TASK_SUFFIX)?;
+
+    Some((done, text.trim().to_string()))
+}
+
+
+/**
+Check if a line of a note is a task line.
+ */
+pub fn is_task_line(line: &str) -> bool {
+    parse_task_line(line).is_some()
+}
+
+
+/**
+Parse all task lines of a note's contents.
+# Parameters:
+* `contents`: The note contents.
+# Return:
+A vector with every task found, in order.
+ */
+pub fn parse_tasks(contents: &str) -> Vec<TaskItem> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            parse_task_line(text).map(|(done, text)| TaskItem { line, text, done })
+        })
+        .collect()
+}
+
+
+/**
+Remove task lines from a note's contents.
+# Parameters:
+* `contents`: The note contents.
+# Return:
+The contents with only the lines that are not tasks.
+ */
+pub fn strip_tasks(contents: &str) -> String {
+    contents
+        .lines()
+        .filter(|line| !is_task_line(line))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+
+/**
+Load the tasks of a note from the default path.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+A vector with the tasks of the note, empty if it has none or could not be read.
+ */
+pub fn load_tasks(note_title: &str) -> Vec<TaskItem> {
+    match notes::read_note(note_title) {
+        Some(contents) => parse_tasks(&contents),
+        None => Vec::new(),
+    }
+}
+
+
+/**
+Check or uncheck a task and write the change back to the note.
+# Parameters:
+* `note_title`: The title of the note with the task.
+* `line_index`: The zero based line number of the task.
+* `done`: The new checked state.
+# Return:
+Boolean stating if the note was updated.
+ */
+pub fn set_task_done(note_title: &str, line_index: usize, done: bool) -> bool {
+    let contents = match notes::read_note(note_title) {
+        Some(contents) => contents,
+        None => return false,
+    };
+
+    let mut updated = String::with_capacity(contents.len());
+    let mut found = false;
+
+    // split_inclusive keeps the original line endings untouched.
+    for (index, line) in contents.split_inclusive('\n').enumerate() {
+        let mark_index = line.find(TASK_PREFIX).map(|start| start + TASK_PREFIX.len());
+        match mark_index {
+            Some(mark_index) if index == line_index && is_task_line(line) => {
+                updated.push_str(&line[..mark_index]);
+                updated.push(if done { DONE_MARK } else { OPEN_MARK });
+                // Marks are single byte characters.
+                updated.push_str(&line[mark_index + 1..]);
+                found = true;
+            }
+            _ => updated.push_str(line),
+        }
+    }
+
+    if !found {
+        warn!("Line {line_index} of note {note_title} is not a task");
+        return false;
+    }
+
+    notes::overwrite_note(note_title, &updated)
+}
+
+
+/**
+Get the completion progress of a note's tasks.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+A tuple with the amount of done tasks and the total of tasks, or `None` if the note has no tasks.
+ */
+pub fn task_progress(note_title: &str) -> Option<(usize, usize)> {
+    let tasks = load_tasks(note_title);
+    if tasks.is_empty() {
+        return None;
+    }
+
+    let done = tasks.iter().filter(|task| task.done).count();
+    Some((done, tasks.len()))
+}
+
+
+/**
+Collect the unchecked tasks of every note on the default path.
+# Return:
+A vector with the open tasks, grouped by note.
+ */
+pub fn open_tasks() -> Vec<OpenTask> {
+    let mut result: Vec<OpenTask> = Vec::new();
+
+    for note_title in notes::list_note_titles() {
+        for task in load_tasks(&note_title) {
+            if task.done {
+                continue;
+            }
+
+            result.push(OpenTask { note_title: note_title.clone(), task });
+        }
+    }
+
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    #[test]
+    fn parses_task_lines() {
+        assert_eq!(parse_task_line("- [ ] buy milk"), Some((false, "buy milk".to_string())));
+        assert_eq!(parse_task_line("  - [x] call mum "), Some((true, "call mum".to_string())));
+        assert_eq!(parse_task_line("- [X] shout"), Some((true, "shout".to_string())));
+        assert_eq!(parse_task_line("- [] no mark"), None);
+        assert_eq!(parse_task_line("- [?] unknown mark"), None);
+        assert_eq!(parse_task_line("- plain item"), None);
+        assert_eq!(parse_task_line("text - [ ] later"), None);
+    }
+
+    #[test]
+    fn set_task_done_keeps_crlf() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Tasks", "title\r\n- [ ] one\r\n- [ ] two\r\n"));
+
+        assert!(set_task_done("Tasks", 2, true));
+        assert_eq!(notes::read_note("Tasks").as_deref(), Some("title\r\n- [ ] one\r\n- [x] two\r\n"));
+        assert!(!set_task_done("Tasks", 0, true));
+    }
+
+    #[test]
+    fn set_task_done_without_trailing_newline() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Tasks", "- [x] one\n- [x] two"));
+
+        assert!(set_task_done("Tasks", 1, false));
+        assert_eq!(notes::read_note("Tasks").as_deref(), Some("- [x] one\n- [ ] two"));
+        assert_eq!(task_progress("Tasks"), Some((1, 2)));
+    }
+}
@@ -0,0 +1,141 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::metadata::{self, Metadata};
+use crate::{error, warn};
+
+
+// generic constants
+/// Directory name used for the app under the user's config directory.
+pub const APP_DIR_NAME: &str = "rust_gtk_notes_app";
+const CONFIG_FILE_NAME: &str = "settings.conf";
+
+/// Settings stored as `key=value` lines, the same format as note metadata.
+pub type Config = Metadata;
+
+
+/**
+Get the user's config directory for the app, following the XDG base directory spec.
+# Return:
+`$XDG_CONFIG_HOME/<app>` or `$HOME/.config/<app>`, or `None` if neither variable is set.
+ */
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(base.join(APP_DIR_NAME))
+}
+
+
+/**
+Get the user's state directory for the app, where logs and other data that isn't worth backing up go.
+# Return:
+`$XDG_STATE_HOME/<app>` or `$HOME/.local/state/<app>`, or `None` if neither variable is set.
+ */
+pub fn state_dir() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local").join("state"),
+    };
+
+    Some(base.join(APP_DIR_NAME))
+}
+
+
+/**
+Get the path of the settings file.
+# Return:
+The path, which may not exist, or `None` if there is no config directory.
+ */
+pub fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join(CONFIG_FILE_NAME))
+}
+
+
+/**
+Load every setting from the settings file.
+# Return:
+The settings, empty if the file doesn't exist or could not be read.
+ */
+pub fn load_config() -> Config {
+    let path = match config_path() {
+        Some(path) => path,
+        None => return Config::new(),
+    };
+    if !path.exists() {
+        return Config::new();
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => metadata::parse_metadata(&contents),
+        Err(e) => {
+            error!("Error reading file at path {path:?}: {e}");
+            Config::new()
+        }
+    }
+}
+
+
+/**
+Write every setting to the settings file, creating its directory if needed.
+# Parameters:
+* `config`: The settings to write.
+# Return:
+Boolean stating if the file was written successfully.
+ */
+pub fn save_config(config: &Config) -> bool {
+    let (dir, path) = match (config_dir(), config_path()) {
+        (Some(dir), Some(path)) => (dir, path),
+        _ => {
+            warn!("There is no config directory, set $XDG_CONFIG_HOME or $HOME");
+            return false;
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Error creating directory {dir:?}: {e}");
+        return false;
+    }
+
+    if let Err(e) = fs::write(&path, metadata::format_metadata(config)) {
+        error!("Error writing file at path {path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Get a single setting.
+# Parameters:
+* `key`: The name of the setting.
+# Return:
+The value or `None` if it isn't set or is empty.
+ */
+pub fn get_setting(key: &str) -> Option<String> {
+    load_config().remove(key).filter(|value| !value.is_empty())
+}
+
+
+/**
+Change a single setting, or remove it if the value is empty.
+# Parameters:
+* `key`: The name of the setting.
+* `value`: The new value.
+# Return:
+Boolean stating if the setting was saved.
+ */
+pub fn set_setting(key: &str, value: &str) -> bool {
+    let mut config = load_config();
+    if value.is_empty() {
+        config.remove(key);
+    } else {
+        config.insert(key.to_string(), value.to_string());
+    }
+
+    save_config(&config)
+}
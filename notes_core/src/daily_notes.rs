@@ -0,0 +1,147 @@
+use chrono::{Local, NaiveDate, NaiveTime};
+
+use crate::notes;
+use crate::config;
+use crate::templates::{self, DATE_FORMAT};
+use crate::error;
+
+
+// Settings
+/// Name of the template used for new daily notes, `DAILY_TEMPLATE` if not set.
+pub const DAILY_TEMPLATE_SETTING: &str = "daily_template";
+
+// generic constants
+/// Template used for new daily notes, falls back to `DEFAULT_DAILY_CONTENTS` if it doesn't exist.
+pub const DAILY_TEMPLATE: &str = "daily";
+const DEFAULT_DAILY_CONTENTS: &str = "Journal for {{date}}\n\n{{cursor}}";
+
+
+/**
+Get the title of the daily note for a date.
+# Parameters:
+* `date`: The date of the daily note.
+# Return:
+The note title, formatted as `DATE_FORMAT`.
+ */
+pub fn daily_note_title(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+
+/**
+Get the name of the template new daily notes are created from, from the settings or `DAILY_TEMPLATE`.
+ */
+pub fn daily_template() -> String {
+    config::get_setting(DAILY_TEMPLATE_SETTING)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or(DAILY_TEMPLATE.to_string())
+}
+
+
+/**
+Get today's date in local time.
+ */
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+
+/**
+Open the daily note for a date, creating it from the daily template if it doesn't exist.
+# Parameters:
+* `date`: The date of the daily note.
+# Return:
+The title of the daily note or `None` if it had to be created and that failed.
+ */
+pub fn open_or_create_daily_note(date: NaiveDate) -> Option<String> {
+    let title = daily_note_title(date);
+    if notes::note_exists(&title) {
+        return Some(title);
+    }
+
+    // The daily note is rendered as if it was written at the start of its day.
+    let moment = date.and_time(NaiveTime::MIN);
+    let template = daily_template();
+    let has_template = templates::load_templates().contains(&template);
+    let rendered = if has_template {
+        templates::render_template_at(&template, &title, moment)?
+    } else {
+        templates::fill_placeholders(DEFAULT_DAILY_CONTENTS, &title, moment)
+    };
+
+    if !notes::create_a_note(&title, &rendered.contents) {
+        error!("Failed to create daily note {title}");
+        return None;
+    }
+
+    Some(title)
+}
+
+
+/**
+Get the dates that have a daily note.
+# Return:
+A vector with the date of every daily note stored.
+ */
+pub fn daily_note_dates() -> Vec<NaiveDate> {
+    notes::list_note_titles()
+        .iter()
+        .filter_map(|title| NaiveDate::parse_from_str(title, DATE_FORMAT).ok())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::test_utils::TestDirs;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 9).expect("a valid date")
+    }
+
+    #[test]
+    fn titles_daily_notes_by_date() {
+        assert_eq!(daily_note_title(date()), "2024-03-09");
+        assert_eq!(daily_note_title(NaiveDate::from_ymd_opt(2023, 12, 31).expect("a valid date")), "2023-12-31");
+    }
+
+    #[test]
+    fn creates_the_daily_note_once() {
+        let _dirs = TestDirs::new();
+
+        assert_eq!(open_or_create_daily_note(date()).as_deref(), Some("2024-03-09"));
+        assert_eq!(notes::read_note("2024-03-09").as_deref(), Some("Journal for 2024-03-09\n\n"));
+
+        assert!(notes::overwrite_note("2024-03-09", "written\n"));
+        assert_eq!(open_or_create_daily_note(date()).as_deref(), Some("2024-03-09"));
+        assert_eq!(notes::read_note("2024-03-09").as_deref(), Some("written\n"));
+    }
+
+    #[test]
+    fn uses_the_configured_template() {
+        let _dirs = TestDirs::new();
+        assert_eq!(daily_template(), DAILY_TEMPLATE);
+
+        assert!(config::set_setting(DAILY_TEMPLATE_SETTING, "journal"));
+        assert_eq!(daily_template(), "journal");
+        let templates_dir = templates::templates_dir().expect("a config directory");
+        fs::create_dir_all(&templates_dir).expect("create the templates directory");
+        fs::write(templates_dir.join("journal.txt"), "# {{title}}\n").expect("write the template");
+
+        assert_eq!(open_or_create_daily_note(date()).as_deref(), Some("2024-03-09"));
+        assert_eq!(notes::read_note("2024-03-09").as_deref(), Some("# 2024-03-09\n"));
+    }
+
+    #[test]
+    fn finds_daily_note_dates() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("2024-03-09", "day\n"));
+        assert!(notes::create_a_note("Groceries", "milk\n"));
+        assert!(notes::create_a_note("2024-13-40", "not a date\n"));
+
+        assert_eq!(daily_note_dates(), vec![date()]);
+    }
+}
@@ -0,0 +1,203 @@
+use std::env;
+use std::path::Path;
+
+use crate::config;
+
+
+// Settings
+/// Editor command, e.g. `code --wait {path}`. Falls back to `$VISUAL` and then `$EDITOR`.
+pub const EDITOR_SETTING: &str = "editor";
+/// `true` or `false` to override whether the editor runs inside a terminal.
+pub const EDITOR_TERMINAL_SETTING: &str = "editor.terminal";
+/// Terminal command used for terminal editors, the editor command is appended to it.
+pub const TERMINAL_SETTING: &str = "terminal";
+
+// generic constants
+const PATH_PLACEHOLDER: &str = "{path}";
+const DEFAULT_TERMINAL: &str = "x-terminal-emulator -e";
+/// Editors that need a terminal to run in.
+const TERMINAL_EDITORS: [&str; 13] = [
+    "vi", "vim", "nvim", "vim.basic", "nano", "pico", "micro", "hx", "helix", "kak", "ne", "joe", "mcedit",
+];
+
+
+/**
+An external editor command.
+ */
+pub struct EditorCommand {
+    /// The program and its arguments, `{path}` marks where the file goes.
+    pub args: Vec<String>,
+    /// Whether the editor has to run inside a terminal.
+    pub terminal: bool,
+}
+
+
+impl EditorCommand {
+    /**
+    Parse an editor command like `code --wait {path}`.
+    Known terminal editors are marked as such.
+    # Parameters:
+    * `command`: The command, quoted like in a shell.
+    # Return:
+    The editor or `None` if the command is empty.
+     */
+    pub fn parse(command: &str) -> Option<EditorCommand> {
+        let args = split_command(command);
+        let program = args.first()?;
+        let program_name = Path::new(program).file_name()?.to_string_lossy().to_string();
+
+        Some(EditorCommand {
+            terminal: TERMINAL_EDITORS.contains(&program_name.as_str()),
+            args,
+        })
+    }
+
+    /**
+    Get the editor's program name, without its directory.
+     */
+    pub fn name(&self) -> String {
+        self.args
+            .first()
+            .and_then(|program| Path::new(program).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /**
+    Build the command line that opens a file in the editor.
+    The path replaces `{path}` or is added at the end, and terminal editors are wrapped in the terminal command.
+    # Parameters:
+    * `file_path`: The file to open.
+    # Return:
+    The program followed by its arguments.
+     */
+    pub fn command_line(&self, file_path: &Path) -> Vec<String> {
+        let file_path = file_path.to_string_lossy();
+        let mut result: Vec<String> = Vec::new();
+
+        if self.terminal {
+            let terminal = config::get_setting(TERMINAL_SETTING).unwrap_or(DEFAULT_TERMINAL.to_string());
+            result.extend(split_command(&terminal));
+        }
+
+        result.extend(self.args.iter().map(|arg| arg.replace(PATH_PLACEHOLDER, &file_path)));
+        if !self.args.iter().any(|arg| arg.contains(PATH_PLACEHOLDER)) {
+            result.push(file_path.to_string());
+        }
+
+        result
+    }
+}
+
+
+/**
+Find the editor chosen by the user: the `editor` setting, then `$VISUAL` and then `$EDITOR`.
+# Return:
+The editor or `None` if none of them is set, in which case the desktop default should be used.
+ */
+pub fn configured_editor() -> Option<EditorCommand> {
+    let command = config::get_setting(EDITOR_SETTING)
+        .or_else(|| env::var("VISUAL").ok())
+        .or_else(|| env::var("EDITOR").ok())?;
+
+    let mut editor = EditorCommand::parse(&command)?;
+    match config::get_setting(EDITOR_TERMINAL_SETTING).as_deref() {
+        Some("true") => editor.terminal = true,
+        Some("false") => editor.terminal = false,
+        _ => {}
+    }
+
+    Some(editor)
+}
+
+
+/**
+Split a command into arguments like a shell would, honoring quotes and backslashes.
+# Parameters:
+* `command`: The command to split.
+# Return:
+The arguments, without the quotes.
+ */
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('\'')) => current.push(c),
+            ('\\', _) => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_argument = true;
+            }
+            (c, Some(open)) if c == open => quote = None,
+            (c, Some(_)) => current.push(c),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_argument = true;
+            }
+            (c, None) if c.is_whitespace() => {
+                if in_argument {
+                    result.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            (c, None) => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+
+    if in_argument {
+        result.push(current);
+    }
+
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    #[test]
+    fn splits_quoted_commands() {
+        assert_eq!(split_command("code --wait {path}"), vec!["code", "--wait", "{path}"]);
+        assert_eq!(split_command("  \"/opt/My Editor/edit\"  -n "), vec!["/opt/My Editor/edit", "-n"]);
+        assert_eq!(split_command("gvim -c 'set tw=80' \"a \\\"b\\\"\""), vec!["gvim", "-c", "set tw=80", "a \"b\""]);
+        assert_eq!(split_command("edit 'C:\\notes' two\\ words ''"), vec!["edit", "C:\\notes", "two words", ""]);
+        assert!(split_command("   ").is_empty());
+    }
+
+    #[test]
+    fn substitutes_the_path() {
+        let editor = EditorCommand::parse("code --goto {path}:1 --wait").expect("parse the editor");
+        assert!(!editor.terminal);
+        assert_eq!(
+            editor.command_line(Path::new("/notes/My note.txt")),
+            vec!["code", "--goto", "/notes/My note.txt:1", "--wait"]
+        );
+
+        let editor = EditorCommand::parse("gedit --new-window").expect("parse the editor");
+        assert_eq!(editor.command_line(Path::new("/notes/a.txt")), vec!["gedit", "--new-window", "/notes/a.txt"]);
+    }
+
+    #[test]
+    fn wraps_terminal_editors() {
+        let _dirs = TestDirs::new();
+        let editor = EditorCommand::parse("/usr/bin/vim").expect("parse the editor");
+        assert!(editor.terminal);
+        assert_eq!(editor.name(), "vim");
+        assert_eq!(
+            editor.command_line(Path::new("/notes/a.txt")),
+            vec!["x-terminal-emulator", "-e", "/usr/bin/vim", "/notes/a.txt"]
+        );
+        assert!(EditorCommand::parse("").is_none());
+    }
+}
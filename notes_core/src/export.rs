@@ -0,0 +1,380 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+use crate::notes;
+use crate::metadata;
+use crate::attachments;
+use crate::checklists;
+use crate::error;
+
+
+// Names used inside exported files
+const HTML_TITLE: &str = "Exported notes";
+const BUNDLE_ATTACHMENTS_DIR: &str = "attachments";
+/// Web stylesheet embedded in HTML exports, the app's own is GTK CSS that browsers can't use.
+const EXPORT_CSS: &str = include_str!("export.css");
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+
+/**
+Escape text so it can be placed inside HTML.
+ */
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+/**
+Quote text as a YAML double quoted string, so front matter stays valid whatever the text holds.
+ */
+fn yaml_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character if character.is_control() => quoted.push_str(&format!("\\u{:04x}", character as u32)),
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+
+/**
+Get the MIME type of an image attachment from its lowercase extension.
+ */
+fn image_mime(extension: &str) -> String {
+    match extension {
+        "svg" => "image/svg+xml".to_string(),
+        "jpg" | "jpeg" => "image/jpeg".to_string(),
+        _ => format!("image/{extension}"),
+    }
+}
+
+
+/**
+Encode bytes as standard base64 with padding, used to embed images in HTML.
+ */
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let block = match chunk.len() {
+            1 => (chunk[0] as u32) << 16,
+            2 => (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8,
+            _ => (chunk[0] as u32) << 16 | (chunk[1] as u32) << 8 | chunk[2] as u32,
+        };
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (block >> (18 - index * 6)) & 0x3f;
+                result.push(BASE64_ALPHABET[sextet as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+
+/**
+Render a single note as an HTML fragment.
+Task lines become checkboxes and image attachments are embedded.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+The HTML fragment or `None` if the note could not be read.
+ */
+pub fn note_to_html(note_title: &str) -> Option<String> {
+    let contents = notes::read_note(note_title)?;
+    let mut html = format!("<article class=\"note\">\n<h2>{}</h2>\n<p class=\"note_text\">", escape_html(note_title));
+
+    for line in contents.lines() {
+        if checklists::is_task_line(line) {
+            continue;
+        }
+        html.push_str(&escape_html(line));
+        html.push_str("<br>\n");
+    }
+    html.push_str("</p>\n");
+
+    for task in checklists::parse_tasks(&contents) {
+        let checked = if task.done { " checked" } else { "" };
+        html.push_str(&format!(
+            "<label class=\"task_check\"><input type=\"checkbox\" disabled{checked}> {}</label><br>\n",
+            escape_html(&task.text)
+        ));
+    }
+
+    for path in attachments::list_attachments(note_title) {
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+
+        // Images are embedded so the file stays standalone, other attachments are only named.
+        match fs::read(&path) {
+            Ok(bytes) if attachments::is_image(&path) => {
+                let mime = image_mime(&extension);
+                html.push_str(&format!(
+                    "<img class=\"attachment\" alt=\"{}\" src=\"data:{mime};base64,{}\">\n",
+                    escape_html(&file_name),
+                    base64_encode(&bytes)
+                ));
+            }
+            Ok(_) => html.push_str(&format!("<p class=\"attachment\">Attachment: {}</p>\n", escape_html(&file_name))),
+            Err(e) => error!("Error reading attachment at path {path:?}: {e}"),
+        }
+    }
+
+    html.push_str("</article>\n");
+    Some(html)
+}
+
+
+/**
+Export notes to a single standalone HTML file, styled by `EXPORT_CSS`.
+# Parameters:
+* `note_titles`: The titles of the notes to export.
+* `output`: Path of the HTML file to write.
+# Return:
+Boolean stating if the file was written.
+ */
+pub fn export_html(note_titles: &[String], output: &Path) -> bool {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width\">\n<title>{HTML_TITLE}</title>\n<style>\n{EXPORT_CSS}</style>\n</head>\n<body>\n<main>\n"
+    );
+
+    for note_title in note_titles {
+        match note_to_html(note_title) {
+            Some(fragment) => html.push_str(&fragment),
+            None => {
+                error!("Could not export note {note_title}");
+                return false;
+            }
+        }
+    }
+    html.push_str("</main>\n</body>\n</html>\n");
+
+    if let Err(e) = fs::write(output, html) {
+        error!("Error writing file at path {output:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Render a note as Markdown, with its metadata as front matter.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+The Markdown document or `None` if the note could not be read.
+ */
+pub fn note_to_markdown(note_title: &str) -> Option<String> {
+    let contents = notes::read_note(note_title)?;
+
+    let mut markdown = format!("---\ntitle: {}\n", yaml_string(note_title));
+    for (key, value) in metadata::load_metadata(note_title) {
+        // Task reminder keys hold the task's text, so they may need quoting too.
+        let plain_key = key.chars().all(|character| character.is_alphanumeric() || "_-.".contains(character));
+        let key = if plain_key { key } else { yaml_string(&key) };
+        markdown.push_str(&format!("{key}: {}\n", yaml_string(&value)));
+    }
+    markdown.push_str(&format!("---\n\n# {note_title}\n\n{contents}\n"));
+
+    let note_attachments = attachments::list_attachments(note_title);
+    if !note_attachments.is_empty() {
+        markdown.push_str("\n## Attachments\n\n");
+    }
+
+    for path in note_attachments {
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let link = format!("{BUNDLE_ATTACHMENTS_DIR}/{note_title}/{file_name}").replace(' ', "%20");
+        let image_mark = if attachments::is_image(&path) { "!" } else { "" };
+        markdown.push_str(&format!("- {image_mark}[{file_name}]({link})\n"));
+    }
+
+    Some(markdown)
+}
+
+
+/**
+Export notes to a zip file with one Markdown document per note and their attachments.
+# Parameters:
+* `note_titles`: The titles of the notes to export.
+* `output`: Path of the zip file to write.
+# Return:
+Boolean stating if the bundle was written.
+ */
+pub fn export_markdown_bundle(note_titles: &[String], output: &Path) -> bool {
+    let file = match fs::File::create(output) {
+        Ok(file) => file,
+        Err(e) => {
+            error!("Error creating file at path {output:?}: {e}");
+            return false;
+        }
+    };
+
+    let mut bundle = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    for note_title in note_titles {
+        let markdown = match note_to_markdown(note_title) {
+            Some(markdown) => markdown,
+            None => {
+                error!("Could not export note {note_title}");
+                return false;
+            }
+        };
+
+        let mut entries = vec![(format!("{note_title}.md"), markdown.into_bytes())];
+        for path in attachments::list_attachments(note_title) {
+            let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            match fs::read(&path) {
+                Ok(bytes) => entries.push((format!("{BUNDLE_ATTACHMENTS_DIR}/{note_title}/{file_name}"), bytes)),
+                Err(e) => error!("Error reading attachment at path {path:?}: {e}"),
+            }
+        }
+
+        for (name, bytes) in entries {
+            if let Err(e) = bundle.start_file(name.as_str(), options) {
+                error!("Error adding {name} to bundle: {e}");
+                return false;
+            }
+            if let Err(e) = bundle.write_all(&bytes) {
+                error!("Error writing {name} to bundle: {e}");
+                return false;
+            }
+        }
+    }
+
+    if let Err(e) = bundle.finish() {
+        error!("Error finishing bundle at path {output:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn html_escapes_text_and_renders_tasks() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Plan", "<b>bold</b> & co\n- [x] done\n- [ ] open\n"));
+
+        let html = note_to_html("Plan").expect("render the note");
+        assert!(html.contains("&lt;b&gt;bold&lt;/b&gt; &amp; co<br>"));
+        assert!(html.contains("<input type=\"checkbox\" disabled checked> done"));
+        assert!(html.contains("<input type=\"checkbox\" disabled> open"));
+        assert!(note_to_html("Missing").is_none());
+    }
+
+    #[test]
+    fn exports_html_file() {
+        let dirs = TestDirs::new();
+        assert!(notes::create_a_note("First", "one\n"));
+        assert!(notes::create_a_note("Second", "two\n"));
+        let output = dirs.root.join("export.html");
+
+        let note_titles = vec!["First".to_string(), "Second".to_string()];
+        assert!(export_html(&note_titles, &output));
+        let html = fs::read_to_string(&output).expect("read the export");
+        assert!(html.contains(EXPORT_CSS));
+        assert!(!html.contains("@define-color"));
+        assert!(html.find("<h2>First</h2>") < html.find("<h2>Second</h2>"));
+
+        assert!(!export_html(&["Missing".to_string()], &output));
+    }
+
+    #[test]
+    fn markdown_has_front_matter() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Trip", "pack\n"));
+        let mut note_metadata = metadata::Metadata::new();
+        note_metadata.insert("tags".to_string(), "travel".to_string());
+        assert!(metadata::save_metadata("Trip", &note_metadata));
+
+        let markdown = note_to_markdown("Trip").expect("render the note");
+        assert!(markdown.starts_with("---\ntitle: \"Trip\"\ntags: \"travel\"\n---\n\n# Trip\n\npack\n"));
+    }
+
+    #[test]
+    fn markdown_front_matter_is_quoted() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Plan: \"B\"", "text\n"));
+        let mut note_metadata = metadata::Metadata::new();
+        note_metadata.insert("description".to_string(), "two\nlines with a \\".to_string());
+        note_metadata.insert("reminder.task: call".to_string(), "2024-05-01 09:30".to_string());
+        assert!(metadata::save_metadata("Plan: \"B\"", &note_metadata));
+
+        let markdown = note_to_markdown("Plan: \"B\"").expect("render the note");
+        assert!(markdown.contains("title: \"Plan: \\\"B\\\"\"\n"));
+        assert!(markdown.contains("description: \"two\\nlines with a \\\\\"\n"));
+        assert!(markdown.contains("\"reminder.task: call\": \"2024-05-01 09:30\"\n"));
+    }
+
+    #[test]
+    fn html_embeds_jpeg_images() {
+        let dirs = TestDirs::new();
+        assert!(notes::create_a_note("Trip", "pack\n"));
+        let photo = dirs.root.join("photo.JPG");
+        fs::write(&photo, b"jpeg").expect("write the photo");
+        assert!(attachments::add_attachment("Trip", &photo).is_some());
+
+        let html = note_to_html("Trip").expect("render the note");
+        assert!(html.contains(&format!("src=\"data:image/jpeg;base64,{}\"", base64_encode(b"jpeg"))));
+    }
+
+    /**
+    Read a file of a zip bundle as text.
+     */
+    fn read_bundle_file(bundle: &mut zip::ZipArchive<fs::File>, name: &str) -> String {
+        let mut contents = String::new();
+        let mut file = bundle.by_name(name).expect("find the file in the bundle");
+        std::io::Read::read_to_string(&mut file, &mut contents).expect("read the file from the bundle");
+        contents
+    }
+
+    #[test]
+    fn exports_markdown_bundle() {
+        let dirs = TestDirs::new();
+        assert!(notes::create_a_note("Trip", "pack\n"));
+        let ticket = dirs.root.join("ticket.txt");
+        fs::write(&ticket, "seat 12A").expect("write the attachment");
+        assert!(attachments::add_attachment("Trip", &ticket).is_some());
+        let output = dirs.root.join("export.zip");
+
+        assert!(export_markdown_bundle(&["Trip".to_string()], &output));
+        let mut bundle = zip::ZipArchive::new(fs::File::open(&output).expect("open the bundle")).expect("read the bundle");
+        assert_eq!(bundle.len(), 2);
+        assert_eq!(read_bundle_file(&mut bundle, "Trip.md"), note_to_markdown("Trip").expect("render the note"));
+        assert_eq!(read_bundle_file(&mut bundle, "attachments/Trip/ticket.txt"), "seat 12A");
+    }
+}
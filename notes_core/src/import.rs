@@ -0,0 +1,924 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_json::Value;
+
+use crate::notes;
+use crate::metadata::{self, Metadata};
+use crate::{error, warn};
+
+
+// generic constants
+const TEXT_EXTENSIONS: [&str; 4] = ["txt", "text", "md", "markdown"];
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+// Dates, imported ones are stored in local time
+const IMPORTED_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+const ENEX_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+// Metadata keys set by importers
+const SOURCE_KEY: &str = "source";
+const CREATED_KEY: &str = "created";
+const UPDATED_KEY: &str = "updated";
+const TAGS_KEY: &str = "tags";
+const NOTEBOOK_KEY: &str = "notebook";
+const COLOR_KEY: &str = "color";
+const ARCHIVED_KEY: &str = "archived";
+const PINNED_KEY: &str = "pinned";
+
+// Joplin item types
+const JOPLIN_NOTE: i64 = 1;
+const JOPLIN_FOLDER: i64 = 2;
+const JOPLIN_TAG: i64 = 5;
+const JOPLIN_NOTE_TAG: i64 = 6;
+
+
+/**
+Where imported notes come from.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImportSource {
+    /// A folder of Markdown or plain text files.
+    TextFolder,
+    /// The `notes.json` file of a Simplenote export.
+    Simplenote,
+    /// The Keep folder of a Google Takeout, or a single note file from it.
+    GoogleKeep,
+    /// An Evernote `.enex` export.
+    Evernote,
+    /// A folder exported from Joplin as JSON.
+    Joplin,
+}
+
+
+impl ImportSource {
+    pub const ALL: [ImportSource; 5] = [
+        ImportSource::TextFolder,
+        ImportSource::Simplenote,
+        ImportSource::GoogleKeep,
+        ImportSource::Evernote,
+        ImportSource::Joplin,
+    ];
+
+    /**
+    Name of the source shown to the user.
+     */
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportSource::TextFolder => "Markdown/text folder",
+            ImportSource::Simplenote => "Simplenote (JSON)",
+            ImportSource::GoogleKeep => "Google Keep Takeout",
+            ImportSource::Evernote => "Evernote (.enex)",
+            ImportSource::Joplin => "Joplin (JSON export)",
+        }
+    }
+
+    /**
+    Whether the source is read from a folder instead of a single file.
+     */
+    pub fn expects_folder(&self) -> bool {
+        matches!(self, ImportSource::TextFolder | ImportSource::GoogleKeep | ImportSource::Joplin)
+    }
+
+    /**
+    Read the notes of an export without storing them.
+    # Parameters:
+    * `path`: The exported file or folder.
+    # Return:
+    The notes found, or a message explaining why the export could not be read.
+     */
+    pub fn parse(&self, path: &Path) -> Result<Vec<ImportedNote>, String> {
+        match self {
+            ImportSource::TextFolder => parse_text_folder(path),
+            ImportSource::Simplenote => parse_simplenote(path),
+            ImportSource::GoogleKeep => parse_google_keep(path),
+            ImportSource::Evernote => parse_evernote(path),
+            ImportSource::Joplin => parse_joplin(path),
+        }
+    }
+}
+
+
+/**
+What to do when an imported note has the title of an existing one.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum CollisionPolicy {
+    Skip,
+    Rename,
+    Overwrite,
+}
+
+
+impl CollisionPolicy {
+    pub const ALL: [CollisionPolicy; 3] = [CollisionPolicy::Skip, CollisionPolicy::Rename, CollisionPolicy::Overwrite];
+
+    /**
+    Name of the policy shown to the user.
+     */
+    pub fn label(&self) -> &'static str {
+        match self {
+            CollisionPolicy::Skip => "Skip existing notes",
+            CollisionPolicy::Rename => "Rename imported notes",
+            CollisionPolicy::Overwrite => "Overwrite existing notes",
+        }
+    }
+}
+
+
+/**
+A note read from an export, before being stored.
+ */
+pub struct ImportedNote {
+    pub title: String,
+    pub contents: String,
+    pub metadata: Metadata,
+}
+
+
+/**
+Outcome of an import.
+ */
+#[derive(Default)]
+pub struct ImportReport {
+    pub imported: Vec<String>,
+    /// Pairs of original and final titles.
+    pub renamed: Vec<(String, String)>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+
+impl ImportReport {
+    /**
+    One line summary with the amount of notes on each outcome.
+     */
+    pub fn summary(&self) -> String {
+        format!(
+            "Imported: {}, renamed: {}, overwritten: {}, skipped: {}, errors: {}",
+            self.imported.len(),
+            self.renamed.len(),
+            self.overwritten.len(),
+            self.skipped.len(),
+            self.errors.len()
+        )
+    }
+
+    /**
+    Titles of the notes that were created by the import.
+     */
+    pub fn new_titles(&self) -> Vec<String> {
+        self.imported
+            .iter()
+            .cloned()
+            .chain(self.renamed.iter().map(|(_, new_title)| new_title.clone()))
+            .collect()
+    }
+}
+
+
+/**
+Store imported notes on the default path.
+# Parameters:
+* `imported`: The notes to store.
+* `policy`: What to do with titles that already exist.
+# Return:
+A report with what happened to each note.
+ */
+pub fn import_notes(imported: Vec<ImportedNote>, policy: CollisionPolicy) -> ImportReport {
+    let mut report = ImportReport::default();
+
+    // Makes sure the notes directory exists.
+    notes::list_note_titles();
+
+    for note in imported {
+        let title = notes::sanitize_title(&note.title);
+
+        if !notes::note_exists(&title) {
+            if store_note(&title, &note, &mut report, false) {
+                report.imported.push(title);
+            }
+            continue;
+        }
+
+        match policy {
+            CollisionPolicy::Skip => report.skipped.push(title),
+            CollisionPolicy::Overwrite => {
+                if store_note(&title, &note, &mut report, true) {
+                    report.overwritten.push(title);
+                }
+            }
+            CollisionPolicy::Rename => {
+                let new_title = unique_title(&title);
+                if store_note(&new_title, &note, &mut report, false) {
+                    report.renamed.push((title, new_title));
+                }
+            }
+        }
+    }
+
+    report
+}
+
+
+/**
+Write an imported note and its metadata, recording failures on the report.
+An overwritten note keeps the metadata keys the import doesn't set, e.g. its reminders.
+ */
+fn store_note(title: &str, note: &ImportedNote, report: &mut ImportReport, overwrite: bool) -> bool {
+    let written = if overwrite {
+        notes::overwrite_note(title, &note.contents)
+    } else {
+        notes::create_a_note(title, &note.contents)
+    };
+
+    if !written {
+        report.errors.push(format!("Could not write note \"{title}\""));
+        return false;
+    }
+
+    let mut note_metadata = if overwrite { metadata::load_metadata(title) } else { Metadata::new() };
+    note_metadata.extend(note.metadata.clone());
+
+    if !metadata::save_metadata(title, &note_metadata) {
+        report.errors.push(format!("Could not write metadata of note \"{title}\""));
+    }
+
+    true
+}
+
+
+/**
+Get a title based on `title` that no note uses yet, adding " (n)" to it.
+ */
+fn unique_title(title: &str) -> String {
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{title} ({counter})");
+        if !notes::note_exists(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+
+/**
+Format an UTC date as an imported metadata date.
+ */
+fn format_date(date: DateTime<Utc>) -> String {
+    date.with_timezone(&Local).format(IMPORTED_DATE_FORMAT).to_string()
+}
+
+
+/**
+Parse an RFC 3339 date, as used by Simplenote and some Joplin exports.
+ */
+fn parse_rfc3339(text: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|date| format_date(date.with_timezone(&Utc)))
+}
+
+
+/**
+Split a text into its first non empty line and the rest of it.
+ */
+fn split_first_line(text: &str) -> (String, String) {
+    let text = text.trim_start();
+    match text.split_once('\n') {
+        Some((first, rest)) => (first.trim().to_string(), rest.trim_start_matches(['\r', '\n']).to_string()),
+        None => (text.trim().to_string(), String::new()),
+    }
+}
+
+
+/**
+Read a JSON file.
+ */
+fn read_json(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Error reading {path:?}: {e}"))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid JSON in {path:?}: {e}"))
+}
+
+
+/**
+Get the files with one of the given extensions in a folder, or the path itself if it's a file.
+ */
+fn files_with_extensions(path: &Path, extensions: &[&str]) -> Result<Vec<PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let dir_iterator = fs::read_dir(path).map_err(|e| format!("Error reading directory {path:?}: {e}"))?;
+    let mut result: Vec<PathBuf> = dir_iterator
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.is_file())
+        .filter(|file| {
+            file.extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .is_some_and(|extension| extensions.contains(&extension.as_str()))
+        })
+        .collect();
+
+    result.sort();
+    Ok(result)
+}
+
+
+/**
+Split YAML-like front matter (`key: value` lines between `---`) from a Markdown document.
+ */
+fn split_front_matter(contents: &str) -> (Metadata, String) {
+    let mut front_matter = Metadata::new();
+
+    let rest = match contents.strip_prefix(FRONT_MATTER_DELIMITER) {
+        Some(rest) if rest.starts_with(['\n', '\r']) => rest,
+        _ => return (front_matter, contents.to_string()),
+    };
+
+    let end = match rest.find(&format!("\n{FRONT_MATTER_DELIMITER}")) {
+        Some(end) => end,
+        None => return (front_matter, contents.to_string()),
+    };
+
+    for line in rest[..end].lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            front_matter.insert(key.trim().to_string(), value.trim().trim_matches('"').to_string());
+        }
+    }
+
+    let body = &rest[end + 1 + FRONT_MATTER_DELIMITER.len()..];
+    (front_matter, body.trim_start_matches(['\r', '\n']).to_string())
+}
+
+
+/**
+Read a folder of Markdown or plain text files, one note per file titled after the file name.
+ */
+fn parse_text_folder(path: &Path) -> Result<Vec<ImportedNote>, String> {
+    let mut result: Vec<ImportedNote> = Vec::new();
+
+    for file in files_with_extensions(path, &TEXT_EXTENSIONS)? {
+        let contents = fs::read_to_string(&file).map_err(|e| format!("Error reading {file:?}: {e}"))?;
+        let (mut note_metadata, body) = split_front_matter(&contents);
+
+        let title = note_metadata
+            .remove("title")
+            .or_else(|| file.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .unwrap_or_default();
+
+        if let Ok(modified) = fs::metadata(&file).and_then(|file_metadata| file_metadata.modified()) {
+            note_metadata.entry(UPDATED_KEY.to_string()).or_insert_with(|| format_date(modified.into()));
+        }
+        note_metadata.insert(SOURCE_KEY.to_string(), "folder".to_string());
+
+        result.push(ImportedNote { title, contents: body, metadata: note_metadata });
+    }
+
+    Ok(result)
+}
+
+
+/**
+Read a Simplenote `notes.json` export, the first line of each note is its title.
+Trashed notes are not imported.
+ */
+fn parse_simplenote(path: &Path) -> Result<Vec<ImportedNote>, String> {
+    let root = read_json(path)?;
+    let active_notes = root["activeNotes"]
+        .as_array()
+        .ok_or_else(|| format!("{path:?} is not a Simplenote export"))?;
+
+    let mut result: Vec<ImportedNote> = Vec::new();
+    for note in active_notes {
+        let (title, contents) = split_first_line(note["content"].as_str().unwrap_or_default());
+
+        let mut note_metadata = Metadata::new();
+        note_metadata.insert(SOURCE_KEY.to_string(), "simplenote".to_string());
+        if let Some(created) = note["creationDate"].as_str().and_then(parse_rfc3339) {
+            note_metadata.insert(CREATED_KEY.to_string(), created);
+        }
+        if let Some(updated) = note["lastModified"].as_str().and_then(parse_rfc3339) {
+            note_metadata.insert(UPDATED_KEY.to_string(), updated);
+        }
+        if let Some(tags) = join_strings(&note["tags"], |tag| tag.as_str()) {
+            note_metadata.insert(TAGS_KEY.to_string(), tags);
+        }
+        if note["pinned"].as_bool() == Some(true) {
+            note_metadata.insert(PINNED_KEY.to_string(), "true".to_string());
+        }
+
+        result.push(ImportedNote { title, contents, metadata: note_metadata });
+    }
+
+    Ok(result)
+}
+
+
+/**
+Read Google Keep notes from a Takeout folder, checklists become task lines.
+Trashed notes are not imported.
+ */
+fn parse_google_keep(path: &Path) -> Result<Vec<ImportedNote>, String> {
+    let mut result: Vec<ImportedNote> = Vec::new();
+
+    for file in files_with_extensions(path, &["json"])? {
+        let note = read_json(&file)?;
+        if note["isTrashed"].as_bool() == Some(true) {
+            continue;
+        }
+
+        let mut contents = note["textContent"].as_str().unwrap_or_default().to_string();
+        if let Some(items) = note["listContent"].as_array() {
+            for item in items {
+                let mark = if item["isChecked"].as_bool() == Some(true) { 'x' } else { ' ' };
+                contents.push_str(&format!("- [{mark}] {}\n", item["text"].as_str().unwrap_or_default()));
+            }
+        }
+
+        let title = match note["title"].as_str() {
+            Some(title) if !title.trim().is_empty() => title.to_string(),
+            _ => split_first_line(&contents).0,
+        };
+
+        let mut note_metadata = Metadata::new();
+        note_metadata.insert(SOURCE_KEY.to_string(), "keep".to_string());
+        if let Some(created) = note["createdTimestampUsec"].as_i64().and_then(DateTime::from_timestamp_micros) {
+            note_metadata.insert(CREATED_KEY.to_string(), format_date(created));
+        }
+        if let Some(updated) = note["userEditedTimestampUsec"].as_i64().and_then(DateTime::from_timestamp_micros) {
+            note_metadata.insert(UPDATED_KEY.to_string(), format_date(updated));
+        }
+        if let Some(tags) = join_strings(&note["labels"], |label| label["name"].as_str()) {
+            note_metadata.insert(TAGS_KEY.to_string(), tags);
+        }
+        if let Some(color) = note["color"].as_str().filter(|color| *color != "DEFAULT") {
+            note_metadata.insert(COLOR_KEY.to_string(), color.to_lowercase());
+        }
+        if note["isArchived"].as_bool() == Some(true) {
+            note_metadata.insert(ARCHIVED_KEY.to_string(), "true".to_string());
+        }
+        if note["isPinned"].as_bool() == Some(true) {
+            note_metadata.insert(PINNED_KEY.to_string(), "true".to_string());
+        }
+
+        result.push(ImportedNote { title, contents, metadata: note_metadata });
+    }
+
+    Ok(result)
+}
+
+
+/**
+Read an Evernote `.enex` export, converting each note's ENML content to plain text.
+Resources, the files embedded in notes, are not imported.
+ */
+fn parse_evernote(path: &Path) -> Result<Vec<ImportedNote>, String> {
+    let xml = fs::read_to_string(path).map_err(|e| format!("Error reading {path:?}: {e}"))?;
+    let mut reader = Reader::from_str(&xml);
+
+    let mut result: Vec<ImportedNote> = Vec::new();
+    let mut current: Option<ImportedNote> = None;
+    let mut field = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut resources = 0;
+
+    loop {
+        let event = reader.read_event().map_err(|e| format!("Invalid XML in {path:?}: {e}"))?;
+        match event {
+            Event::Start(element) => {
+                field = String::from_utf8_lossy(element.name().as_ref()).to_string();
+                if field == "note" {
+                    let mut note_metadata = Metadata::new();
+                    note_metadata.insert(SOURCE_KEY.to_string(), "evernote".to_string());
+                    current = Some(ImportedNote { title: String::new(), contents: String::new(), metadata: note_metadata });
+                    tags.clear();
+                    resources = 0;
+                } else if field == "resource" {
+                    resources += 1;
+                }
+            }
+            Event::End(element) => {
+                if element.name().as_ref() == b"note" {
+                    if let Some(mut note) = current.take() {
+                        if !tags.is_empty() {
+                            note.metadata.insert(TAGS_KEY.to_string(), tags.join(", "));
+                        }
+                        if resources > 0 {
+                            warn!("Skipped {resources} attached files of Evernote note {}", note.title);
+                        }
+                        result.push(note);
+                    }
+                }
+                field.clear();
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map(|text| text.to_string()).unwrap_or_default();
+                if let Some(note) = current.as_mut() {
+                    set_evernote_field(note, &mut tags, &field, &text);
+                }
+            }
+            Event::CData(data) => {
+                let text = String::from_utf8_lossy(&data.into_inner()).to_string();
+                if let Some(note) = current.as_mut() {
+                    set_evernote_field(note, &mut tags, &field, &text);
+                }
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    Ok(result)
+}
+
+
+/**
+Store the text of an Evernote note's field on the imported note.
+ */
+fn set_evernote_field(note: &mut ImportedNote, tags: &mut Vec<String>, field: &str, text: &str) {
+    match field {
+        "title" => note.title.push_str(text),
+        "content" => note.contents.push_str(&enml_to_text(text)),
+        "tag" => tags.push(text.trim().to_string()),
+        "created" | "updated" => {
+            let key = if field == "created" { CREATED_KEY } else { UPDATED_KEY };
+            if let Ok(date) = chrono::NaiveDateTime::parse_from_str(text.trim(), ENEX_DATE_FORMAT) {
+                note.metadata.insert(key.to_string(), format_date(date.and_utc()));
+            }
+        }
+        _ => (),
+    }
+}
+
+
+/**
+Convert Evernote's ENML markup to plain text, keeping line breaks and to-dos as task lines.
+ */
+fn enml_to_text(enml: &str) -> String {
+    let mut reader = Reader::from_str(enml);
+    let mut text = String::new();
+
+    loop {
+        let event = match reader.read_event() {
+            Ok(event) => event,
+            Err(e) => {
+                error!("Invalid note content, keeping what was read: {e}");
+                break;
+            }
+        };
+
+        match event {
+            Event::Start(element) | Event::Empty(element) => match element.name().as_ref() {
+                b"en-todo" => {
+                    let checked = element
+                        .try_get_attribute("checked")
+                        .ok()
+                        .flatten()
+                        .is_some_and(|attribute| attribute.value.as_ref() == b"true");
+                    text.push_str(if checked { "- [x] " } else { "- [ ] " });
+                }
+                b"br" => text.push('\n'),
+                b"li" => text.push_str("- "),
+                _ => (),
+            },
+            Event::End(element) => {
+                if matches!(element.name().as_ref(), b"div" | b"p" | b"li" | b"h1" | b"h2" | b"h3" | b"tr") {
+                    text.push('\n');
+                }
+            }
+            Event::Text(content) => {
+                // ENML allows HTML entities that plain XML doesn't know about.
+                let unescaped = content
+                    .unescape_with(|entity| match entity {
+                        "nbsp" => Some(" "),
+                        _ => None,
+                    })
+                    .map(|unescaped| unescaped.to_string())
+                    .unwrap_or_else(|_| String::from_utf8_lossy(&content).to_string());
+                text.push_str(&unescaped);
+            }
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+
+    // Nested blocks produce runs of empty lines.
+    let mut result = String::with_capacity(text.len());
+    for line in text.lines() {
+        if line.trim().is_empty() && result.ends_with("\n\n") {
+            continue;
+        }
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+
+    result.trim().to_string()
+}
+
+
+/**
+Read a Joplin JSON export folder, mapping notebooks and tags to metadata.
+ */
+fn parse_joplin(path: &Path) -> Result<Vec<ImportedNote>, String> {
+    let items: Vec<Value> = files_with_extensions(path, &["json"])?
+        .iter()
+        .map(|file| read_json(file))
+        .collect::<Result<Vec<Value>, String>>()?;
+
+    let item_type = |item: &Value| item["type_"].as_i64().unwrap_or_default();
+    let id_to_title = |wanted: i64| -> HashMap<String, String> {
+        items
+            .iter()
+            .filter(|item| item_type(item) == wanted)
+            .filter_map(|item| Some((item["id"].as_str()?.to_string(), item["title"].as_str()?.to_string())))
+            .collect()
+    };
+
+    let folders = id_to_title(JOPLIN_FOLDER);
+    let tag_names = id_to_title(JOPLIN_TAG);
+
+    let mut note_tags: HashMap<String, Vec<String>> = HashMap::new();
+    for item in items.iter().filter(|item| item_type(item) == JOPLIN_NOTE_TAG) {
+        let note_id = item["note_id"].as_str().unwrap_or_default().to_string();
+        if let Some(tag) = item["tag_id"].as_str().and_then(|tag_id| tag_names.get(tag_id)) {
+            note_tags.entry(note_id).or_default().push(tag.clone());
+        }
+    }
+
+    let mut result: Vec<ImportedNote> = Vec::new();
+    for item in items.iter().filter(|item| item_type(item) == JOPLIN_NOTE) {
+        let mut note_metadata = Metadata::new();
+        note_metadata.insert(SOURCE_KEY.to_string(), "joplin".to_string());
+
+        if let Some(created) = joplin_date(&item["user_created_time"]).or_else(|| joplin_date(&item["created_time"])) {
+            note_metadata.insert(CREATED_KEY.to_string(), created);
+        }
+        if let Some(updated) = joplin_date(&item["user_updated_time"]).or_else(|| joplin_date(&item["updated_time"])) {
+            note_metadata.insert(UPDATED_KEY.to_string(), updated);
+        }
+        if let Some(notebook) = item["parent_id"].as_str().and_then(|parent_id| folders.get(parent_id)) {
+            note_metadata.insert(NOTEBOOK_KEY.to_string(), notebook.clone());
+        }
+        if let Some(tags) = item["id"].as_str().and_then(|id| note_tags.get(id)) {
+            note_metadata.insert(TAGS_KEY.to_string(), tags.join(", "));
+        }
+
+        // Joplin to-dos are notes themselves, so they become a single task line.
+        let mut contents = item["body"].as_str().unwrap_or_default().to_string();
+        if item["is_todo"].as_i64() == Some(1) {
+            let mark = if item["todo_completed"].as_i64().unwrap_or_default() > 0 { 'x' } else { ' ' };
+            let title = item["title"].as_str().unwrap_or_default();
+            contents = format!("- [{mark}] {title}\n{contents}");
+        }
+
+        result.push(ImportedNote {
+            title: item["title"].as_str().unwrap_or_default().to_string(),
+            contents,
+            metadata: note_metadata,
+        });
+    }
+
+    Ok(result)
+}
+
+
+/**
+Parse a Joplin date, which may be milliseconds since the epoch or an RFC 3339 string.
+ */
+fn joplin_date(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(millis) => millis.as_i64().and_then(DateTime::from_timestamp_millis).map(format_date),
+        Value::String(text) => parse_rfc3339(text),
+        _ => None,
+    }
+}
+
+
+/**
+Join the strings of a JSON array with commas.
+# Return:
+The joined strings or `None` if the array is missing or has no strings.
+ */
+fn join_strings<'a>(array: &'a Value, get: impl Fn(&'a Value) -> Option<&'a str>) -> Option<String> {
+    let strings: Vec<&str> = array.as_array()?.iter().filter_map(get).collect();
+    if strings.is_empty() {
+        return None;
+    }
+
+    Some(strings.join(", "))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    fn imported(title: &str, contents: &str) -> ImportedNote {
+        ImportedNote { title: title.to_string(), contents: contents.to_string(), metadata: Metadata::new() }
+    }
+
+    #[test]
+    fn sanitizes_titles() {
+        assert_eq!(notes::sanitize_title("Plain title"), "Plain title");
+        assert_eq!(notes::sanitize_title("a/b\\c:d"), "a-b-c-d");
+        assert_eq!(notes::sanitize_title("  ..hidden "), "hidden");
+        assert_eq!(notes::sanitize_title("tab\there"), "tabhere");
+        assert_eq!(notes::sanitize_title(" . "), notes::UNTITLED_NOTE);
+        assert_eq!(notes::sanitize_title(&"x".repeat(200)).chars().count(), notes::MAX_TITLE_CHARS);
+        assert!(notes::is_valid_title(&notes::sanitize_title("../../etc/passwd")));
+    }
+
+    #[test]
+    fn skip_keeps_existing_notes() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Recipe", "old\n"));
+
+        let report = import_notes(vec![imported("Recipe", "new\n"), imported("Other", "text\n")], CollisionPolicy::Skip);
+        assert_eq!(report.skipped, vec!["Recipe".to_string()]);
+        assert_eq!(report.imported, vec!["Other".to_string()]);
+        assert_eq!(notes::read_note("Recipe").as_deref(), Some("old\n"));
+    }
+
+    #[test]
+    fn rename_picks_a_free_title() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Recipe", "old\n"));
+        assert!(notes::create_a_note("Recipe (2)", "older\n"));
+
+        let report = import_notes(vec![imported("Recipe", "new\n")], CollisionPolicy::Rename);
+        assert_eq!(report.renamed, vec![("Recipe".to_string(), "Recipe (3)".to_string())]);
+        assert_eq!(report.new_titles(), vec!["Recipe (3)".to_string()]);
+        assert_eq!(notes::read_note("Recipe (3)").as_deref(), Some("new\n"));
+    }
+
+    #[test]
+    fn overwrite_replaces_existing_notes() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Recipe", "old\n"));
+
+        let report = import_notes(vec![imported("Recipe", "new\n")], CollisionPolicy::Overwrite);
+        assert_eq!(report.overwritten, vec!["Recipe".to_string()]);
+        assert!(report.errors.is_empty());
+        assert_eq!(notes::read_note("Recipe").as_deref(), Some("new\n"));
+    }
+
+    #[test]
+    fn overwrite_keeps_metadata_the_import_doesnt_set() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Recipe", "old\n"));
+        let mut existing = Metadata::new();
+        existing.insert("reminder".to_string(), "2024-05-01 09:30".to_string());
+        existing.insert(TAGS_KEY.to_string(), "old".to_string());
+        assert!(metadata::save_metadata("Recipe", &existing));
+
+        let mut note = imported("Recipe", "new\n");
+        note.metadata.insert(TAGS_KEY.to_string(), "cooking".to_string());
+        import_notes(vec![note], CollisionPolicy::Overwrite);
+
+        let saved = metadata::load_metadata("Recipe");
+        assert_eq!(saved.get("reminder").map(String::as_str), Some("2024-05-01 09:30"));
+        assert_eq!(saved.get(TAGS_KEY).map(String::as_str), Some("cooking"));
+    }
+
+    /**
+    Write export files under the test directory.
+    # Return:
+    The folder holding them.
+     */
+    fn write_fixtures(dirs: &TestDirs, files: &[(&str, &str)]) -> PathBuf {
+        let folder = dirs.root.join("export");
+        fs::create_dir_all(&folder).expect("create the export folder");
+        for (name, contents) in files {
+            fs::write(folder.join(name), contents).expect("write an export file");
+        }
+        folder
+    }
+
+    #[test]
+    fn parses_simplenote() {
+        let dirs = TestDirs::new();
+        let folder = write_fixtures(&dirs, &[("notes.json", r#"{
+            "activeNotes": [{
+                "content": "Groceries\r\nmilk\n",
+                "creationDate": "2024-01-02T10:00:00.000Z",
+                "tags": ["home", "food"],
+                "pinned": true
+            }],
+            "trashedNotes": [{ "content": "Gone" }]
+        }"#)]);
+
+        let parsed = ImportSource::Simplenote.parse(&folder.join("notes.json")).expect("parse the export");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].title, "Groceries");
+        assert_eq!(parsed[0].contents, "milk\n");
+        assert_eq!(parsed[0].metadata.get(TAGS_KEY).map(String::as_str), Some("home, food"));
+        assert_eq!(parsed[0].metadata.get(PINNED_KEY).map(String::as_str), Some("true"));
+        assert!(parsed[0].metadata.contains_key(CREATED_KEY));
+    }
+
+    #[test]
+    fn parses_google_keep() {
+        let dirs = TestDirs::new();
+        let folder = write_fixtures(&dirs, &[
+            ("list.json", r#"{
+                "title": "",
+                "textContent": "Bake a cake\n",
+                "listContent": [{ "text": "eggs", "isChecked": true }, { "text": "flour", "isChecked": false }],
+                "labels": [{ "name": "family" }],
+                "color": "BLUE",
+                "isArchived": true
+            }"#),
+            ("trashed.json", r#"{ "title": "Gone", "textContent": "", "isTrashed": true }"#),
+        ]);
+
+        let parsed = ImportSource::GoogleKeep.parse(&folder).expect("parse the export");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].title, "Bake a cake");
+        assert_eq!(parsed[0].contents, "Bake a cake\n- [x] eggs\n- [ ] flour\n");
+        assert_eq!(parsed[0].metadata.get(TAGS_KEY).map(String::as_str), Some("family"));
+        assert_eq!(parsed[0].metadata.get(COLOR_KEY).map(String::as_str), Some("blue"));
+        assert_eq!(parsed[0].metadata.get(ARCHIVED_KEY).map(String::as_str), Some("true"));
+    }
+
+    #[test]
+    fn parses_evernote_enml() {
+        let dirs = TestDirs::new();
+        let folder = write_fixtures(&dirs, &[("export.enex", r#"<?xml version="1.0" encoding="UTF-8"?>
+<en-export>
+  <note>
+    <title>Trip</title>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?>
+<en-note><div>Pack&nbsp;bags</div><div><en-todo checked="true"/>Tickets</div><ul><li>Passport</li></ul><br/></en-note>]]></content>
+    <created>20240102T100000Z</created>
+    <tag>travel</tag>
+    <tag>summer</tag>
+    <resource><data encoding="base64">aGVsbG8=</data><mime>text/plain</mime></resource>
+  </note>
+</en-export>"#)]);
+
+        let parsed = ImportSource::Evernote.parse(&folder.join("export.enex")).expect("parse the export");
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].title, "Trip");
+        assert_eq!(parsed[0].contents, "Pack bags\n- [x] Tickets\n- Passport");
+        assert_eq!(parsed[0].metadata.get(TAGS_KEY).map(String::as_str), Some("travel, summer"));
+        assert!(parsed[0].metadata.contains_key(CREATED_KEY));
+    }
+
+    #[test]
+    fn parses_joplin() {
+        let dirs = TestDirs::new();
+        let folder = write_fixtures(&dirs, &[
+            ("folder.json", r#"{ "id": "f1", "title": "Work", "type_": 2 }"#),
+            ("note.json", r#"{ "id": "n1", "parent_id": "f1", "title": "Standup", "body": "Notes\n", "type_": 1, "created_time": 1704189600000 }"#),
+            ("note_tag.json", r#"{ "id": "nt1", "note_id": "n1", "tag_id": "t1", "type_": 6 }"#),
+            ("tag.json", r#"{ "id": "t1", "title": "daily", "type_": 5 }"#),
+            ("todo.json", r#"{ "id": "n2", "title": "Ship it", "body": "", "is_todo": 1, "todo_completed": 1704189600000, "type_": 1 }"#),
+        ]);
+
+        let parsed = ImportSource::Joplin.parse(&folder).expect("parse the export");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].title, "Standup");
+        assert_eq!(parsed[0].contents, "Notes\n");
+        assert_eq!(parsed[0].metadata.get(NOTEBOOK_KEY).map(String::as_str), Some("Work"));
+        assert_eq!(parsed[0].metadata.get(TAGS_KEY).map(String::as_str), Some("daily"));
+        assert!(parsed[0].metadata.contains_key(CREATED_KEY));
+        assert_eq!(parsed[1].title, "Ship it");
+        assert_eq!(parsed[1].contents, "- [x] Ship it\n");
+    }
+
+    #[test]
+    fn parses_text_folder_with_front_matter() {
+        let dirs = TestDirs::new();
+        let folder = write_fixtures(&dirs, &[
+            ("Trip.md", "---\ntitle: \"Summer trip\"\ntags: travel\n---\n\nDay one\n"),
+            ("image.png", "not a note"),
+            ("plain.txt", "Just text\n"),
+        ]);
+
+        let parsed = ImportSource::TextFolder.parse(&folder).expect("parse the folder");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].title, "Summer trip");
+        assert_eq!(parsed[0].contents, "Day one\n");
+        assert_eq!(parsed[0].metadata.get(TAGS_KEY).map(String::as_str), Some("travel"));
+        assert!(!parsed[0].metadata.contains_key("title"));
+        assert_eq!(parsed[1].title, "plain");
+        assert_eq!(parsed[1].contents, "Just text\n");
+        assert_eq!(parsed[1].metadata.get(SOURCE_KEY).map(String::as_str), Some("folder"));
+    }
+
+    #[test]
+    fn imported_titles_stay_in_the_notes_dir() {
+        let dirs = TestDirs::new();
+
+        let report = import_notes(vec![imported("../escape", "text\n")], CollisionPolicy::Skip);
+        assert_eq!(report.imported, vec!["-escape".to_string()]);
+        assert!(!dirs.root.join("escape.txt").exists());
+    }
+}
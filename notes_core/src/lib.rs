@@ -0,0 +1,30 @@
+/*!
+Note storage and processing shared by the app's frontends.
+
+Nothing in this crate depends on gtk, every function returns plain data so the
+gtk window and the command line only decide how to show it.
+ */
+
+pub mod notes;
+pub mod templates;
+pub mod daily_notes;
+pub mod checklists;
+pub mod metadata;
+pub mod reminders;
+pub mod attachments;
+pub mod export;
+pub mod import;
+pub mod search;
+pub mod quick_capture;
+pub mod config;
+pub mod editor;
+pub mod logging;
+pub mod trash;
+pub mod preferences;
+pub mod user_themes;
+pub mod window_state;
+
+pub use notes::Note;
+
+#[cfg(test)]
+mod test_utils;
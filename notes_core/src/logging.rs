@@ -0,0 +1,309 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use chrono::Local;
+
+use crate::config;
+
+
+// generic constants
+const LOG_FILE_NAME: &str = "notes.log";
+/// Size at which the log file is rotated.
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+/// Amount of rotated files kept, as `notes.log.1` (the newest) up to `notes.log.<n>`.
+const MAX_ROTATED_FILES: usize = 3;
+const LOG_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+
+/**
+How important a log message is, from the most to the least.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Something failed, e.g. a note could not be written.
+    Error,
+    /// Something unexpected that was worked around, e.g. an ignored line.
+    Warning,
+    /// Normal operation worth keeping in the log file, e.g. the app starting.
+    Info,
+    /// Details only shown with `--verbose`.
+    Debug,
+}
+
+
+impl Level {
+    /**
+    Get the level's name as written in the log file.
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warning => "WARNING",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+
+    fn from_u8(value: u8) -> Level {
+        match value {
+            0 => Level::Error,
+            1 => Level::Warning,
+            2 => Level::Info,
+            _ => Level::Debug,
+        }
+    }
+}
+
+
+/**
+A single log message, passed to the sink.
+ */
+pub struct Record<'a> {
+    pub level: Level,
+    /// The module the message comes from, e.g. `notes_core::notes`.
+    pub target: &'a str,
+    pub file: &'a str,
+    pub line: u32,
+    pub message: &'a str,
+}
+
+
+/// Where messages are shown besides the log file, stderr if none is set.
+pub type Sink = fn(&Record);
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static FILE_LOGGING: AtomicBool = AtomicBool::new(false);
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+static LOG_FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+
+/**
+The open log file and how much has been written to it.
+ */
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+
+/**
+Set up logging, before anything is logged.
+# Parameters:
+* `max_level`: The least important level that is logged, more verbose messages are dropped.
+* `log_to_file`: Whether messages are also written to the log file in the state directory.
+ */
+pub fn init(max_level: Level, log_to_file: bool) {
+    MAX_LEVEL.store(max_level as u8, Ordering::Relaxed);
+    FILE_LOGGING.store(log_to_file, Ordering::Relaxed);
+}
+
+
+/**
+Show messages somewhere else than on stderr, e.g. in the system journal.
+# Parameters:
+* `sink`: The function every message that is not dropped is passed to.
+ */
+pub fn set_sink(sink: Sink) {
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(sink);
+}
+
+
+/**
+Get the least important level that is logged.
+ */
+pub fn max_level() -> Level {
+    Level::from_u8(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+
+/**
+Check if messages of a level are logged, to skip building expensive messages.
+ */
+pub fn enabled(level: Level) -> bool {
+    level <= max_level()
+}
+
+
+/**
+Get the path of the log file.
+# Return:
+The path, which may not exist, or `None` if there is no state directory.
+ */
+pub fn log_path() -> Option<PathBuf> {
+    Some(config::state_dir()?.join(LOG_FILE_NAME))
+}
+
+
+/**
+Log a message, use the `error!`, `warn!`, `info!` and `debug!` macros instead of calling this directly.
+# Parameters:
+* `level`: How important the message is.
+* `target`: The module the message comes from.
+* `file`: The source file the message comes from.
+* `line`: The line of the source file.
+* `args`: The formatted message.
+ */
+pub fn log(level: Level, target: &str, file: &str, line: u32, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    let message = args.to_string();
+    let record = Record { level, target, file, line, message: &message };
+
+    // The sink is copied out so it may log without deadlocking.
+    let sink = *SINK.lock().unwrap_or_else(|e| e.into_inner());
+    match sink {
+        Some(sink) => sink(&record),
+        None => eprintln!("{} {target}: {message}", level.as_str()),
+    }
+
+    if FILE_LOGGING.load(Ordering::Relaxed) {
+        write_to_file(&record);
+    }
+}
+
+
+/**
+Append a message to the log file, opening or rotating it first if needed.
+ */
+fn write_to_file(record: &Record) {
+    let line = format!(
+        "{} {} {}: {}\n",
+        Local::now().format(LOG_TIME_FORMAT),
+        record.level.as_str(),
+        record.target,
+        record.message
+    );
+
+    let mut log_file = LOG_FILE.lock().unwrap_or_else(|e| e.into_inner());
+
+    if log_file.as_ref().is_some_and(|log_file| log_file.size + line.len() as u64 > MAX_LOG_FILE_SIZE) {
+        *log_file = None;
+        rotate_log_files();
+    }
+
+    if log_file.is_none() {
+        *log_file = open_log_file();
+    }
+
+    let current = match log_file.as_mut() {
+        Some(current) => current,
+        None => return,
+    };
+
+    if let Err(e) = current.file.write_all(line.as_bytes()) {
+        // Logging the failure would come back here, so it goes straight to stderr.
+        eprintln!("write_to_file: Error writing to the log file, no longer writing to it: {e}");
+        FILE_LOGGING.store(false, Ordering::Relaxed);
+        *log_file = None;
+        return;
+    }
+    current.size += line.len() as u64;
+}
+
+
+/**
+Open the log file for appending, creating it and its directory if needed.
+# Return:
+The file or `None` if it could not be opened, in which case file logging is turned off.
+ */
+fn open_log_file() -> Option<LogFile> {
+    let path = match log_path() {
+        Some(path) => path,
+        None => {
+            eprintln!("open_log_file: There is no state directory, set $XDG_STATE_HOME or $HOME");
+            FILE_LOGGING.store(false, Ordering::Relaxed);
+            return None;
+        }
+    };
+
+    let opened = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| OpenOptions::new().create(true).append(true).open(&path));
+
+    match opened {
+        Ok(file) => {
+            let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+            Some(LogFile { file, size })
+        }
+        Err(e) => {
+            eprintln!("open_log_file: Error opening log file at path {path:?}, not writing to it: {e}");
+            FILE_LOGGING.store(false, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+
+/**
+Shift every rotated file one number up, dropping the oldest, and move the log file to `notes.log.1`.
+ */
+fn rotate_log_files() {
+    let path = match log_path() {
+        Some(path) => path,
+        None => return,
+    };
+    let rotated_path = |number: usize| PathBuf::from(format!("{}.{number}", path.to_string_lossy()));
+
+    for number in (1..MAX_ROTATED_FILES).rev() {
+        let from = rotated_path(number);
+        if from.exists() {
+            if let Err(e) = fs::rename(&from, rotated_path(number + 1)) {
+                eprintln!("rotate_log_files: Error renaming {from:?}: {e}");
+            }
+        }
+    }
+
+    if let Err(e) = fs::rename(&path, rotated_path(1)) {
+        eprintln!("rotate_log_files: Error renaming {path:?}: {e}");
+    }
+}
+
+
+/**
+Log a message at the error level, formatted like `format!`.
+ */
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Error, module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
+
+
+/**
+Log a message at the warning level, formatted like `format!`.
+ */
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Warning, module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
+
+
+/**
+Log a message at the info level, formatted like `format!`.
+ */
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Info, module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
+
+
+/**
+Log a message at the debug level, formatted like `format!`.
+ */
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::Debug, module_path!(), file!(), line!(), format_args!($($arg)+))
+    };
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::path;
+use std::fs;
+
+use crate::notes;
+use crate::{error, warn};
+
+
+// generic constants
+pub const METADATA_SUFFIX: &str = ".meta";
+const KEY_VALUE_SEPARATOR: char = '=';
+/// Characters escaped in values so each pair stays on its line, `%` first so escapes stay unambiguous.
+const VALUE_ESCAPES: [(&str, &str); 3] = [("%", "%25"), ("\n", "%0A"), ("\r", "%0D")];
+
+/// Key/value pairs stored next to a note, sorted by key.
+pub type Metadata = BTreeMap<String, String>;
+
+
+/**
+Get the path of a note's metadata file on the default path.
+# Parameters:
+* `note_title`: The title of the note without extension.
+# Return:
+The path to the note's metadata file, which may not exist.
+ */
+pub fn metadata_path(note_title: &str) -> path::PathBuf {
+    notes::notes_dir().join(format!("{note_title}{METADATA_SUFFIX}"))
+}
+
+
+/**
+Parse metadata written as one `key=value` pair per line, unescaping the values.
+Empty lines and lines starting with `#` are ignored.
+# Parameters:
+* `contents`: The metadata file contents.
+# Return:
+The parsed metadata.
+ */
+pub fn parse_metadata(contents: &str) -> Metadata {
+    let mut result = Metadata::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once(KEY_VALUE_SEPARATOR) {
+            Some((key, value)) => {
+                result.insert(key.trim().to_string(), unescape_value(value.trim()));
+            }
+            None => warn!("Ignoring line without a value: {line}"),
+        }
+    }
+
+    result
+}
+
+
+/**
+Format metadata as one `key=value` pair per line, escaping line breaks and `%` in the values.
+# Parameters:
+* `metadata`: The metadata to format.
+# Return:
+The metadata file contents.
+ */
+pub fn format_metadata(metadata: &Metadata) -> String {
+    metadata
+        .iter()
+        .map(|(key, value)| format!("{key}{KEY_VALUE_SEPARATOR}{}\n", escape_value(value)))
+        .collect()
+}
+
+
+fn escape_value(value: &str) -> String {
+    VALUE_ESCAPES.iter().fold(value.to_string(), |value, (from, to)| value.replace(from, to))
+}
+
+
+fn unescape_value(value: &str) -> String {
+    VALUE_ESCAPES.iter().rev().fold(value.to_string(), |value, (from, to)| value.replace(to, from))
+}
+
+
+/**
+Load the metadata of a note from the default path.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+The note's metadata, empty if it has none or it could not be read.
+ */
+pub fn load_metadata(note_title: &str) -> Metadata {
+    let meta_path = metadata_path(note_title);
+    if !meta_path.exists() {
+        return Metadata::new();
+    }
+
+    match fs::read_to_string(&meta_path) {
+        Ok(contents) => parse_metadata(&contents),
+        Err(e) => {
+            error!("Error reading metadata at path {meta_path:?}: {e}");
+            Metadata::new()
+        }
+    }
+}
+
+
+/**
+Save the metadata of a note on the default path, removing the file if there is nothing to save.
+# Parameters:
+* `note_title`: The title of the note.
+* `metadata`: The metadata to save.
+# Return:
+Boolean stating if the metadata was saved successfully.
+ */
+pub fn save_metadata(note_title: &str, metadata: &Metadata) -> bool {
+    let meta_path = metadata_path(note_title);
+
+    if metadata.is_empty() {
+        return delete_metadata(note_title);
+    }
+
+    if let Err(e) = fs::write(&meta_path, format_metadata(metadata)) {
+        error!("Error writing metadata at path {meta_path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Delete the metadata file of a note, if there is one.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+Boolean stating if the metadata is gone.
+ */
+pub fn delete_metadata(note_title: &str) -> bool {
+    let meta_path = metadata_path(note_title);
+    if !meta_path.exists() {
+        return true;
+    }
+
+    if let Err(e) = fs::remove_file(&meta_path) {
+        error!("Error deleting metadata at path {meta_path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_round_trip_through_the_file_format() {
+        let mut metadata = Metadata::new();
+        metadata.insert("description".to_string(), "two\nlines\r\nand 100%0A off".to_string());
+        metadata.insert("reminder".to_string(), "2024-05-01 09:30".to_string());
+
+        let contents = format_metadata(&metadata);
+        assert_eq!(contents.lines().count(), 2);
+        assert!(contents.contains("description=two%0Alines%0D%0Aand 100%250A off\n"));
+        assert_eq!(parse_metadata(&contents), metadata);
+    }
+
+    #[test]
+    fn parsing_skips_comments_and_lines_without_a_value() {
+        let metadata = parse_metadata("# comment\n\n key = value \nno separator\nurl=a=b\n");
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata.get("key").map(String::as_str), Some("value"));
+        assert_eq!(metadata.get("url").map(String::as_str), Some("a=b"));
+    }
+}
@@ -0,0 +1,687 @@
+use std::path;
+use std::fs;
+use std::io::{Read, Write};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::metadata::{self, Metadata, METADATA_SUFFIX};
+use crate::attachments::{self, ATTACHMENTS_SUFFIX};
+use crate::checklists::{self, TaskItem};
+use crate::config;
+use crate::{error, warn, info};
+
+
+// generic constants
+/// Where notes are stored unless the `notes_dir` setting says otherwise.
+pub const NOTES_PATH: &str = "./notes/";
+/// Characters of a note's text shown next to its title in the note list.
+pub const SNIPPET_LENGTH: usize = 80;
+/// Title given to notes whose title is made of nothing usable.
+pub const UNTITLED_NOTE: &str = "Untitled";
+pub const MAX_TITLE_CHARS: usize = 100;
+/// A title with these would put the note's files outside the notes directory.
+const PATH_SEPARATORS: [char; 2] = ['/', '\\'];
+
+// Settings
+/// Directory the notes are stored in, `~/` is expanded to the home directory.
+pub const NOTES_DIR_SETTING: &str = "notes_dir";
+/// Order the notes are loaded in, see `SortOrder`.
+pub const SORT_ORDER_SETTING: &str = "sort_order";
+
+/// The notes directory, read from the settings the first time it's needed.
+static NOTES_DIR: Mutex<Option<path::PathBuf>> = Mutex::new(None);
+
+// Error messages
+const NOTE_ALREADY_EXISTS: &str = "Note already exists";
+const INVALID_TITLE: &str = "Invalid note title";
+
+
+/**
+A note read from the default path with everything stored alongside it.
+ */
+#[derive(Clone)]
+pub struct Note {
+    pub title: String,
+    /// The raw text of the note, task lines included.
+    pub contents: String,
+    pub metadata: Metadata,
+    pub tasks: Vec<TaskItem>,
+    /// Paths of the files attached to the note.
+    pub attachments: Vec<path::PathBuf>,
+}
+
+
+impl Note {
+    /**
+    Read a note and its metadata, tasks and attachments from the default path.
+    # Parameters:
+    * `note_title`: The title of the note to read.
+    # Return:
+    The note or `None` if it could not be read.
+     */
+    pub fn load(note_title: &str) -> Option<Note> {
+        let contents = read_note(note_title)?;
+
+        Some(Note {
+            title: note_title.to_string(),
+            metadata: metadata::load_metadata(note_title),
+            tasks: checklists::parse_tasks(&contents),
+            attachments: attachments::list_attachments(note_title),
+            contents,
+        })
+    }
+
+    /**
+    Get the text of the note without its task lines.
+     */
+    pub fn body(&self) -> String {
+        checklists::strip_tasks(&self.contents)
+    }
+
+    /**
+    Get the first line of text of the note, cut to `SNIPPET_LENGTH` characters.
+    # Return:
+    The line, empty if the note only has tasks or is empty.
+     */
+    pub fn snippet(&self) -> String {
+        let body = self.body();
+        let first_line = body.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+
+        match first_line.char_indices().nth(SNIPPET_LENGTH) {
+            Some((end, _)) => format!("{}…", first_line[..end].trim_end()),
+            None => first_line.to_string(),
+        }
+    }
+}
+
+
+/**
+Order in which notes are loaded.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortOrder {
+    /// Alphabetically by title.
+    #[default]
+    Title,
+    /// Most recently modified first.
+    Modified,
+    /// Most recently created first.
+    Created,
+}
+
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [SortOrder::Title, SortOrder::Modified, SortOrder::Created];
+
+    /**
+    Get the order's name as stored in the settings.
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Title => "title",
+            SortOrder::Modified => "modified",
+            SortOrder::Created => "created",
+        }
+    }
+
+    /**
+    Get the order's name as shown to the user.
+     */
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Title => "Title",
+            SortOrder::Modified => "Last modified",
+            SortOrder::Created => "Last created",
+        }
+    }
+
+    /**
+    Parse an order from its name in the settings.
+    # Return:
+    The order or `None` if the name is unknown.
+     */
+    pub fn parse(name: &str) -> Option<SortOrder> {
+        SortOrder::ALL.into_iter().find(|order| order.as_str() == name.trim())
+    }
+
+    /**
+    Get the order set in the settings, by title if there is none.
+     */
+    pub fn from_settings() -> SortOrder {
+        config::get_setting(SORT_ORDER_SETTING)
+            .and_then(|name| SortOrder::parse(&name))
+            .unwrap_or_default()
+    }
+}
+
+
+/**
+Get the directory notes are stored in, from the `notes_dir` setting or `NOTES_PATH`.
+ */
+pub fn notes_dir() -> path::PathBuf {
+    let mut notes_dir = NOTES_DIR.lock().unwrap_or_else(|e| e.into_inner());
+    notes_dir
+        .get_or_insert_with(|| match config::get_setting(NOTES_DIR_SETTING) {
+            Some(dir) => expand_home(&dir),
+            None => path::PathBuf::from(NOTES_PATH),
+        })
+        .clone()
+}
+
+
+/**
+Change the directory notes are stored in for the rest of the run, without saving the setting.
+# Parameters:
+* `dir`: The new notes directory, `~/` is expanded to the home directory.
+ */
+pub fn set_notes_dir(dir: &str) {
+    *NOTES_DIR.lock().unwrap_or_else(|e| e.into_inner()) = Some(expand_home(dir));
+}
+
+
+/**
+Check a directory can hold notes, creating it if it doesn't exist.
+# Parameters:
+* `dir`: The directory, `~/` is expanded to the home directory.
+# Return:
+Boolean stating if the directory exists now.
+ */
+pub fn prepare_notes_dir(dir: &str) -> bool {
+    let dir = expand_home(dir);
+    if dir.is_dir() {
+        return true;
+    }
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Failed to create directory {}: {e}", dir.display());
+        return false;
+    }
+
+    info!("Created directory {}", dir.display());
+    true
+}
+
+
+/**
+Replace a leading `~/` with the home directory.
+ */
+fn expand_home(dir: &str) -> path::PathBuf {
+    let dir = dir.trim();
+    match (dir.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => path::PathBuf::from(home).join(rest),
+        _ => path::PathBuf::from(dir),
+    }
+}
+
+
+/**
+Load notes from the notes directory, in the order set in the settings.
+# Return
+A vector with the title of every note stored or an empty vector.
+ */
+pub fn load_notes() -> Vec<String> {
+    let mut result = list_note_titles();
+    sort_note_titles(&mut result, SortOrder::from_settings());
+    result
+}
+
+
+/**
+Sort note titles.
+# Parameters:
+* `note_titles`: The titles to sort.
+* `order`: How to sort them, notes whose dates can't be read go last.
+ */
+pub fn sort_note_titles(note_titles: &mut [String], order: SortOrder) {
+    let note_time = |note_title: &str| -> Option<SystemTime> {
+        let file_metadata = fs::metadata(note_path(note_title)).ok()?;
+        match order {
+            SortOrder::Title => None,
+            SortOrder::Modified => file_metadata.modified().ok(),
+            // Not every file system keeps creation times.
+            SortOrder::Created => file_metadata.created().or_else(|_| file_metadata.modified()).ok(),
+        }
+    };
+
+    match order {
+        SortOrder::Title => note_titles.sort_by_key(|note_title| note_title.to_lowercase()),
+        SortOrder::Modified | SortOrder::Created => {
+            note_titles.sort_by_cached_key(|note_title| std::cmp::Reverse(note_time(note_title)));
+        }
+    }
+}
+
+
+/**
+List every note in the notes directory, creating the directory if it doesn't exist.
+# Return
+A vector with the title of all the notes stored or an empty vector.
+ */
+pub fn list_note_titles() -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let notes_dir = notes_dir();
+
+    if !notes_dir.exists() {
+        info!("Creating a new directory {}", notes_dir.display());
+
+        // Handle possible errors creating the directory
+        if let Err(e) = fs::create_dir_all(&notes_dir) {
+            error!("Failed to create dirrectory {}: {e}", notes_dir.display());
+            return result;
+        }
+    }
+    
+    let dir_iterator = match fs::read_dir(&notes_dir) {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            error!("Error reading directory {}: {e}", notes_dir.display());
+            return result;
+        }
+    };
+
+    for file in dir_iterator {
+        let file_name = match file {
+            Ok(entry) =>  entry.file_name(),
+            Err(e) => {
+                error!("Error reading file entry: {e}");
+                continue;
+            }
+        };
+
+        let note_title = match file_name.into_string() {
+            Ok(title) => title,
+            Err(e) => {
+                error!("Error getting file name as string: {e:?}");
+                continue;
+            }
+        };
+
+        if let Some(title) = note_title.strip_suffix(".txt") {
+            result.push(title.trim().to_string());
+        } else if note_title.ends_with(METADATA_SUFFIX) || note_title.ends_with(ATTACHMENTS_SUFFIX) {
+            // Metadata files and attachment directories live next to their notes.
+            continue;
+        } else if note_title.starts_with('.') {
+            // Hidden entries, like the trash, aren't notes either.
+            continue;
+        } else {
+            warn!("File is not a .txt file: {note_title}");
+            continue;
+        }
+    }
+
+    result
+}
+
+
+/**
+Get the path of a note in the notes directory.
+# Parameters:
+* `note_title`: The title for the note without extension.
+# Return:
+The path to the note's txt file, which may not exist.
+ */
+pub fn note_path(note_title: &str) -> path::PathBuf {
+    notes_dir().join(format!("{note_title}.txt"))
+}
+
+
+/**
+Get the title of a note from the path of its txt file.
+# Parameters:
+* `file_path`: Path to a file, relative or absolute.
+# Return:
+The note's title or `None` if the file is not a note in the notes directory.
+ */
+pub fn note_title_from_path(file_path: &path::Path) -> Option<String> {
+    if file_path.extension().and_then(|extension| extension.to_str()) != Some("txt") {
+        return None;
+    }
+
+    // Both paths are canonicalized so "./notes/a.txt" and "/home/me/app/notes/a.txt" match.
+    let parent = file_path.parent()?.canonicalize().ok()?;
+    let notes_dir = notes_dir().canonicalize().ok()?;
+    if parent != notes_dir {
+        return None;
+    }
+
+    let note_title = file_path.file_stem()?.to_str()?.to_string();
+    if note_exists(&note_title) { Some(note_title) } else { None }
+}
+
+
+/**
+Check if a title can be used for a note: it isn't empty, has no path separators or control
+characters and doesn't start with a dot, so the note's files stay in the notes directory and
+aren't hidden.
+# Parameters:
+* `note_title`: The title to check.
+# Return:
+Boolean stating if the title is valid.
+ */
+pub fn is_valid_title(note_title: &str) -> bool {
+    !note_title.trim().is_empty()
+        && !note_title.starts_with('.')
+        && !note_title.chars().any(|c| PATH_SEPARATORS.contains(&c) || c.is_control())
+}
+
+
+/**
+Turn any text, e.g. an imported title, into a valid title.
+Path separators and `:`, which some file systems don't allow, become `-`.
+# Parameters:
+* `title`: The text to turn into a title.
+# Return:
+A title accepted by `is_valid_title`, `UNTITLED_NOTE` if nothing of the text is left.
+ */
+pub fn sanitize_title(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if PATH_SEPARATORS.contains(&c) || c == ':' { '-' } else { c })
+        .filter(|c| !c.is_control())
+        .take(MAX_TITLE_CHARS)
+        .collect();
+
+    // A leading dot would make the note a hidden file.
+    let cleaned = cleaned.trim().trim_start_matches('.').trim();
+    if cleaned.is_empty() {
+        UNTITLED_NOTE.to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+
+/**
+Check if a note exists on the default path.
+# Parameters:
+* `note_title`: The title for the note without extension.
+# Return:
+Boolean stating if the note exists.
+ */
+pub fn note_exists(note_title: &str) -> bool {
+    is_valid_title(note_title) && note_path(note_title).exists()
+}
+
+
+/**
+Get when a note was last modified.
+# Parameters:
+* `note_title`: The title for the note without extension.
+# Return:
+The time or `None` if the note doesn't exist or its file system doesn't keep it.
+ */
+pub fn note_modified(note_title: &str) -> Option<SystemTime> {
+    fs::metadata(note_path(note_title)).ok()?.modified().ok()
+}
+
+
+/**
+Create a new note on the default path as a txt file.
+# Parameters:
+* `note_title`:The title for the note without extension
+* `contents`: The note contents.
+
+# Return:
+Boolean stating if the note creation was successful.
+ */
+pub fn create_a_note(note_title: &str, contents: &str) -> bool {
+    if !is_valid_title(note_title) {
+        warn!("{INVALID_TITLE}: {note_title:?}");
+        return false;
+    }
+    let note_path = note_path(note_title);
+    if note_path.exists() {
+        warn!("{NOTE_ALREADY_EXISTS}: {note_title}");
+        return false;
+    }
+
+    let mut new_note = match fs::File::create(&note_path) {
+        Ok(fstream) => fstream,
+        Err(e) => {
+            error!("Error crating note {note_title} at path {note_path:?}: {e}");
+            return false;
+        }
+    };
+
+    // We don't care about the amount of bytes written so we use write_all.
+    if let Err(e) = new_note.write_all(contents.as_bytes()) {
+        error!("Error writing to new note at path {note_path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Delete a note from the default path.
+# Parameters:
+*`note_title`: The title of the note to be deleted.
+# Return:
+A boolean stating if the operation went successfully.
+ */
+pub fn delete_a_note(note_title: &str) -> bool {
+    if !is_valid_title(note_title) {
+        warn!("{INVALID_TITLE}: {note_title:?}");
+        return false;
+    }
+    let note_path = note_path(note_title);
+    if !note_path.exists() {
+        warn!("Note with path {note_path:?} does not exist");
+        return false;
+    }
+
+    if let Err(e) = fs::remove_file(&note_path) {
+        error!("Error deleting note at path {note_path:?}: {e}");
+        return false;
+    }
+
+    // Leftover metadata or attachments would be picked up by a new note with the same title.
+    metadata::delete_metadata(note_title);
+    attachments::delete_attachments(note_title);
+    
+    true
+}
+
+
+/**
+Rename a note on the default path, along with its metadata and attachments.
+# Parameters:
+* `note_title`: The current title of the note.
+* `new_title`: The new title for the note.
+# Return:
+A boolean stating if the note was renamed.
+ */
+pub fn rename_note(note_title: &str, new_title: &str) -> bool {
+    if !is_valid_title(note_title) || !is_valid_title(new_title) {
+        warn!("{INVALID_TITLE}: {note_title:?} or {new_title:?}");
+        return false;
+    }
+    let old_path = note_path(note_title);
+    let new_path = note_path(new_title);
+    if !old_path.exists() {
+        warn!("Note with path {old_path:?} does not exist");
+        return false;
+    }
+    if new_path.exists() {
+        warn!("{NOTE_ALREADY_EXISTS}: {new_title}");
+        return false;
+    }
+
+    if let Err(e) = fs::rename(&old_path, &new_path) {
+        error!("Error renaming note {old_path:?} to {new_path:?}: {e}");
+        return false;
+    }
+
+    // Files that live next to the note follow it.
+    let companions = [
+        (metadata::metadata_path(note_title), metadata::metadata_path(new_title)),
+        (attachments::attachments_dir(note_title), attachments::attachments_dir(new_title)),
+    ];
+    for (old_companion, new_companion) in companions {
+        if !old_companion.exists() {
+            continue;
+        }
+        if let Err(e) = fs::rename(&old_companion, &new_companion) {
+            error!("Error moving {old_companion:?} to {new_companion:?}: {e}");
+        }
+    }
+
+    true
+}
+
+
+/**
+Read the contents of a note from the default path.
+# Parameters:
+* `note_title`: The title of the note to read.
+# Return:
+The note's contents or `None` if it could not be read.
+ */
+pub fn read_note(note_title: &str) -> Option<String> {
+    if !is_valid_title(note_title) {
+        warn!("{INVALID_TITLE}: {note_title:?}");
+        return None;
+    }
+    let file_path = note_path(note_title);
+    let mut fstream = match fs::File::open(&file_path) {
+        Ok(stream) => stream,
+        Err(e) => {
+            error!("Error opening file at path {file_path:?}: {e}");
+            return None;
+        }   
+    };
+
+    let mut buffer = String::with_capacity(1024);
+    if let Err(e) = fstream.read_to_string(&mut buffer) {
+        error!("Error reading file at path {file_path:?}: {e}");
+        return None;
+    }
+
+    Some(buffer)
+}
+
+
+/**
+Replace the contents of an existing note on the default path.
+# Parameters:
+* `note_title`: The title of the note to overwrite.
+* `contents`: The new note contents.
+# Return:
+Boolean stating if the note was written successfully.
+ */
+pub fn overwrite_note(note_title: &str, contents: &str) -> bool {
+    if !is_valid_title(note_title) {
+        warn!("{INVALID_TITLE}: {note_title:?}");
+        return false;
+    }
+    let note_path = note_path(note_title);
+    if !note_path.exists() {
+        warn!("Note with path {note_path:?} does not exist");
+        return false;
+    }
+
+    if let Err(e) = fs::write(&note_path, contents) {
+        error!("Error writing note at path {note_path:?}: {e}");
+        return false;
+    }
+
+    true
+}
+
+
+/**
+Append text to the end of an existing note on the default path, on a line of its own.
+# Parameters:
+* `note_title`: The title of the note to append to.
+* `text`: The text to append.
+# Return:
+Boolean stating if the note was written successfully.
+ */
+pub fn append_to_note(note_title: &str, text: &str) -> bool {
+    let mut contents = match read_note(note_title) {
+        Some(contents) => contents,
+        None => return false,
+    };
+
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(text);
+    if !text.ends_with('\n') {
+        contents.push('\n');
+    }
+
+    overwrite_note(note_title, &contents)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    #[test]
+    fn create_read_and_delete() {
+        let _dirs = TestDirs::new();
+
+        assert!(create_a_note("Groceries", "milk\n"));
+        assert!(!create_a_note("Groceries", "eggs\n"));
+        assert_eq!(read_note("Groceries").as_deref(), Some("milk\n"));
+        assert_eq!(list_note_titles(), vec!["Groceries".to_string()]);
+
+        let mut note_metadata = Metadata::new();
+        note_metadata.insert("tags".to_string(), "food".to_string());
+        assert!(metadata::save_metadata("Groceries", &note_metadata));
+
+        assert!(delete_a_note("Groceries"));
+        assert!(!note_exists("Groceries"));
+        assert!(!metadata::metadata_path("Groceries").exists());
+        assert!(!delete_a_note("Groceries"));
+    }
+
+    #[test]
+    fn rename_moves_metadata() {
+        let _dirs = TestDirs::new();
+        assert!(create_a_note("Old", "text\n"));
+        assert!(create_a_note("Taken", "text\n"));
+        let mut note_metadata = Metadata::new();
+        note_metadata.insert("tags".to_string(), "work".to_string());
+        assert!(metadata::save_metadata("Old", &note_metadata));
+
+        assert!(!rename_note("Old", "Taken"));
+        assert!(!rename_note("Missing", "Other"));
+        assert!(rename_note("Old", "New"));
+
+        assert!(!note_exists("Old"));
+        assert_eq!(read_note("New").as_deref(), Some("text\n"));
+        assert_eq!(metadata::load_metadata("New"), note_metadata);
+    }
+
+    #[test]
+    fn append_adds_a_line() {
+        let _dirs = TestDirs::new();
+        assert!(create_a_note("Log", "first"));
+
+        assert!(append_to_note("Log", "second"));
+        assert!(append_to_note("Log", "third\n"));
+        assert_eq!(read_note("Log").as_deref(), Some("first\nsecond\nthird\n"));
+        assert!(!append_to_note("Missing", "text"));
+    }
+
+    #[test]
+    fn invalid_titles_are_refused() {
+        let dirs = TestDirs::new();
+
+        for title in ["", "  ", "../escape", "a/b", "a\\b", ".hidden", "line\nbreak"] {
+            assert!(!is_valid_title(title), "{title:?}");
+            assert!(!create_a_note(title, "text"), "{title:?}");
+        }
+        assert!(!dirs.root.join("escape.txt").exists());
+
+        assert!(create_a_note("Fine", "text"));
+        assert!(!rename_note("Fine", "../Fine"));
+        assert!(read_note("../notes/Fine").is_none());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::{self, Config};
+use crate::editor::EDITOR_SETTING;
+use crate::notes::{self, SortOrder, NOTES_DIR_SETTING, SORT_ORDER_SETTING, NOTES_PATH};
+use crate::user_themes::USER_THEME_SETTING;
+
+
+// Settings
+/// `system`, `light` or `dark`.
+pub const THEME_SETTING: &str = "theme";
+/// Font of the note contents, as a Pango font description like `Serif 12`.
+pub const FONT_SETTING: &str = "font";
+/// Milliseconds to wait before saving changes made in the window, like a ticked task.
+pub const AUTOSAVE_DELAY_SETTING: &str = "autosave_delay";
+/// `true` or `false`, whether deleting and trashing notes asks first.
+pub const CONFIRM_DELETE_SETTING: &str = "confirm_delete";
+
+// generic constants
+pub const MAX_AUTOSAVE_DELAY: u32 = 10_000;
+
+
+/**
+Color scheme of the app.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Theme {
+    /// Whatever the desktop uses.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Light, Theme::Dark];
+
+    /**
+    Get the theme's name as stored in the settings.
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Theme::System => "system",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    /**
+    Get the theme's name as shown to the user.
+     */
+    pub fn label(&self) -> &'static str {
+        match self {
+            Theme::System => "Follow the system",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+
+    /**
+    Parse a theme from its name in the settings.
+    # Return:
+    The theme or `None` if the name is unknown.
+     */
+    pub fn parse(name: &str) -> Option<Theme> {
+        Theme::ALL.into_iter().find(|theme| theme.as_str() == name.trim())
+    }
+}
+
+
+/**
+Every setting shown in the preferences window.
+Empty strings mean the default is used.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preferences {
+    /// Directory the notes are stored in, `NOTES_PATH` if empty.
+    pub notes_dir: String,
+    /// Editor command, picked from the environment if empty.
+    pub editor: String,
+    pub theme: Theme,
+    /// User theme loaded on top of the app's stylesheet, none if empty.
+    pub user_theme: String,
+    /// Pango font description of the note contents, the theme's font if empty.
+    pub font: String,
+    /// Milliseconds, 0 saves right away.
+    pub autosave_delay: u32,
+    pub sort_order: SortOrder,
+    pub confirm_delete: bool,
+}
+
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            notes_dir: String::new(),
+            editor: String::new(),
+            theme: Theme::default(),
+            user_theme: String::new(),
+            font: String::new(),
+            autosave_delay: 0,
+            sort_order: SortOrder::default(),
+            confirm_delete: true,
+        }
+    }
+}
+
+
+impl Preferences {
+    /**
+    Read the preferences from the settings file, invalid values are replaced by their default.
+     */
+    pub fn load() -> Preferences {
+        let config = config::load_config();
+        let defaults = Preferences::default();
+        let setting = |key: &str| config.get(key).map(|value| value.trim()).filter(|value| !value.is_empty());
+
+        Preferences {
+            notes_dir: setting(NOTES_DIR_SETTING).unwrap_or_default().to_string(),
+            editor: setting(EDITOR_SETTING).unwrap_or_default().to_string(),
+            theme: setting(THEME_SETTING).and_then(Theme::parse).unwrap_or(defaults.theme),
+            user_theme: setting(USER_THEME_SETTING).unwrap_or_default().to_string(),
+            font: setting(FONT_SETTING).unwrap_or_default().to_string(),
+            autosave_delay: setting(AUTOSAVE_DELAY_SETTING)
+                .and_then(|delay| delay.parse::<u32>().ok())
+                .map(|delay| delay.min(MAX_AUTOSAVE_DELAY))
+                .unwrap_or(defaults.autosave_delay),
+            sort_order: setting(SORT_ORDER_SETTING).and_then(SortOrder::parse).unwrap_or(defaults.sort_order),
+            confirm_delete: setting(CONFIRM_DELETE_SETTING)
+                .and_then(|confirm| confirm.parse::<bool>().ok())
+                .unwrap_or(defaults.confirm_delete),
+        }
+    }
+
+    /**
+    Write the preferences to the settings file, keeping every other setting.
+    The notes directory is switched right away.
+    # Return:
+    Boolean stating if the settings file was written.
+     */
+    pub fn save(&self) -> bool {
+        let mut config = config::load_config();
+        set_or_remove(&mut config, NOTES_DIR_SETTING, &self.notes_dir);
+        set_or_remove(&mut config, EDITOR_SETTING, &self.editor);
+        set_or_remove(&mut config, THEME_SETTING, self.theme.as_str());
+        set_or_remove(&mut config, USER_THEME_SETTING, &self.user_theme);
+        set_or_remove(&mut config, FONT_SETTING, &self.font);
+        set_or_remove(&mut config, AUTOSAVE_DELAY_SETTING, &self.autosave_delay.min(MAX_AUTOSAVE_DELAY).to_string());
+        set_or_remove(&mut config, SORT_ORDER_SETTING, self.sort_order.as_str());
+        set_or_remove(&mut config, CONFIRM_DELETE_SETTING, &self.confirm_delete.to_string());
+
+        if self.notes_dir.trim().is_empty() {
+            notes::set_notes_dir(NOTES_PATH);
+        } else {
+            notes::set_notes_dir(&self.notes_dir);
+        }
+
+        config::save_config(&config)
+    }
+}
+
+
+/**
+Set a setting, or remove it if the value is empty so the default is used.
+ */
+fn set_or_remove(config: &mut Config, key: &str, value: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        config.remove(key);
+    } else {
+        config.insert(key.to_string(), value.to_string());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestDirs;
+
+    #[test]
+    fn defaults_without_settings_file() {
+        let _dirs = TestDirs::new();
+        assert_eq!(Preferences::load(), Preferences::default());
+    }
+
+    #[test]
+    fn saves_and_loads_each_setting() {
+        let dirs = TestDirs::new();
+        let notes_dir = dirs.root.join("elsewhere").to_string_lossy().to_string();
+        let preferences = Preferences {
+            notes_dir: notes_dir.clone(),
+            editor: "vim -p".to_string(),
+            theme: Theme::Dark,
+            user_theme: "solarized".to_string(),
+            font: "Serif 12".to_string(),
+            autosave_delay: 1500,
+            sort_order: SortOrder::Modified,
+            confirm_delete: false,
+        };
+
+        assert!(preferences.save());
+        assert_eq!(Preferences::load(), preferences);
+        assert_eq!(notes::notes_dir().to_string_lossy(), notes_dir);
+
+        let expected = [
+            (NOTES_DIR_SETTING, notes_dir.as_str()),
+            (EDITOR_SETTING, "vim -p"),
+            (THEME_SETTING, "dark"),
+            (USER_THEME_SETTING, "solarized"),
+            (FONT_SETTING, "Serif 12"),
+            (AUTOSAVE_DELAY_SETTING, "1500"),
+            (SORT_ORDER_SETTING, "modified"),
+            (CONFIRM_DELETE_SETTING, "false"),
+        ];
+        for (key, value) in expected {
+            assert_eq!(config::get_setting(key).as_deref(), Some(value), "{key}");
+        }
+    }
+
+    #[test]
+    fn empty_values_remove_settings_and_keep_others() {
+        let _dirs = TestDirs::new();
+        assert!(config::set_setting("window_note", "kept"));
+        assert!(config::set_setting(FONT_SETTING, "Sans 10"));
+
+        assert!(Preferences::default().save());
+        assert_eq!(config::get_setting(FONT_SETTING), None);
+        assert_eq!(config::get_setting(NOTES_DIR_SETTING), None);
+        assert_eq!(config::get_setting("window_note").as_deref(), Some("kept"));
+        assert_eq!(notes::notes_dir(), std::path::PathBuf::from(NOTES_PATH));
+    }
+
+    #[test]
+    fn invalid_values_fall_back_to_defaults() {
+        let _dirs = TestDirs::new();
+        let config: Config = [
+            (THEME_SETTING, "purple"),
+            (AUTOSAVE_DELAY_SETTING, "soon"),
+            (SORT_ORDER_SETTING, "random"),
+            (CONFIRM_DELETE_SETTING, "maybe"),
+        ].into_iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        assert!(config::save_config(&config));
+        assert_eq!(Preferences::load(), Preferences::default());
+
+        assert!(config::set_setting(AUTOSAVE_DELAY_SETTING, "999999"));
+        assert_eq!(Preferences::load().autosave_delay, MAX_AUTOSAVE_DELAY);
+    }
+}
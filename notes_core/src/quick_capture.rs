@@ -0,0 +1,120 @@
+use chrono::{Local, NaiveDateTime};
+
+use crate::notes;
+use crate::{error, warn};
+
+
+// generic constants
+/// Note that captured text goes to when no other note is chosen.
+pub const INBOX_NOTE: &str = "Inbox";
+const CAPTURE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+
+/**
+Format a captured text as a single timestamped line.
+# Parameters:
+* `text`: The captured text, line breaks are turned into spaces.
+* `moment`: When the text was captured.
+# Return:
+The line to append, without a trailing line break.
+ */
+pub fn capture_line(text: &str, moment: NaiveDateTime) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    format!("- {} {text}", moment.format(CAPTURE_TIME_FORMAT))
+}
+
+
+/**
+Append a timestamped line to a note, creating the note if it doesn't exist.
+# Parameters:
+* `note_title`: The note to append to, `INBOX_NOTE` if `None`.
+* `text`: The text to capture.
+# Return:
+The title of the note the text went to or `None` if it could not be written.
+ */
+pub fn capture(note_title: Option<&str>, text: &str) -> Option<String> {
+    capture_at(note_title, text, Local::now().naive_local())
+}
+
+
+/**
+Append a line captured at a given moment to a note, creating the note if it doesn't exist.
+# Parameters:
+* `note_title`: The note to append to, `INBOX_NOTE` if `None`.
+* `text`: The text to capture.
+* `moment`: When the text was captured.
+# Return:
+The title of the note the text went to or `None` if it could not be written.
+ */
+pub fn capture_at(note_title: Option<&str>, text: &str, moment: NaiveDateTime) -> Option<String> {
+    let note_title = note_title.map(str::trim).filter(|title| !title.is_empty()).unwrap_or(INBOX_NOTE);
+    if text.trim().is_empty() {
+        warn!("Nothing to capture for note {note_title}");
+        return None;
+    }
+
+    // Makes sure the notes directory exists.
+    notes::list_note_titles();
+
+    let line = capture_line(text, moment);
+    let success = if notes::note_exists(note_title) {
+        notes::append_to_note(note_title, &line)
+    } else {
+        notes::create_a_note(note_title, &format!("{line}\n"))
+    };
+
+    if !success {
+        error!("Failed to capture text to note {note_title}");
+        return None;
+    }
+
+    Some(note_title.to_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::test_utils::TestDirs;
+
+    fn moment() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 9).and_then(|date| date.and_hms_opt(7, 5, 0)).expect("a valid date")
+    }
+
+    #[test]
+    fn prefixes_a_timestamp_on_a_single_line() {
+        assert_eq!(capture_line("Call the dentist", moment()), "- 2024-03-09 07:05 Call the dentist");
+        assert_eq!(capture_line("  two\nlines\t here ", moment()), "- 2024-03-09 07:05 two lines here");
+    }
+
+    #[test]
+    fn captures_to_the_inbox_by_default() {
+        let _dirs = TestDirs::new();
+
+        assert_eq!(capture_at(None, "first", moment()).as_deref(), Some(INBOX_NOTE));
+        assert_eq!(capture_at(Some("  "), "second", moment()).as_deref(), Some(INBOX_NOTE));
+        assert_eq!(
+            notes::read_note(INBOX_NOTE).as_deref(),
+            Some("- 2024-03-09 07:05 first\n- 2024-03-09 07:05 second\n")
+        );
+    }
+
+    #[test]
+    fn captures_to_a_chosen_note() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Groceries", "milk"));
+
+        assert_eq!(capture_at(Some(" Groceries "), "bread", moment()).as_deref(), Some("Groceries"));
+        assert_eq!(notes::read_note("Groceries").as_deref(), Some("milk\n- 2024-03-09 07:05 bread\n"));
+        assert!(!notes::note_exists(INBOX_NOTE));
+    }
+
+    #[test]
+    fn ignores_empty_text() {
+        let _dirs = TestDirs::new();
+
+        assert!(capture_at(None, " \n ", moment()).is_none());
+        assert!(!notes::note_exists(INBOX_NOTE));
+    }
+}
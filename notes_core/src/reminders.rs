@@ -0,0 +1,336 @@
+use chrono::{Local, NaiveDateTime};
+
+use crate::metadata;
+use crate::notes;
+use crate::{error, warn};
+
+
+// Metadata keys, task reminders are keyed by the task's text so they follow it when lines move.
+const NOTE_REMINDER_KEY: &str = "reminder";
+const TASK_REMINDER_PREFIX: &str = "reminder.task.";
+/// Characters escaped in task texts used as keys, `%` first so escapes stay unambiguous.
+const KEY_ESCAPES: [(&str, &str); 2] = [("%", "%25"), ("=", "%3D")];
+
+/// Format used to store reminders in note metadata.
+pub const REMINDER_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+
+/**
+Source of the current time, so reminder scheduling can be driven by a fake clock.
+ */
+pub trait Clock {
+    fn now(&self) -> NaiveDateTime;
+}
+
+
+/**
+Clock using the system's local time.
+ */
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+
+/**
+A reminder attached to a note or to one of its tasks.
+ */
+#[derive(Clone)]
+pub struct Reminder {
+    pub note_title: String,
+    /// Text of the task the reminder belongs to, `None` for the whole note.
+    /// Tasks with the same text in a note share their reminder.
+    pub task: Option<String>,
+    pub due: NaiveDateTime,
+}
+
+
+impl Reminder {
+    /**
+    Describe what the reminder is about, the task text or the note title.
+     */
+    pub fn description(&self) -> String {
+        self.task.clone().unwrap_or_else(|| self.note_title.clone())
+    }
+}
+
+
+/**
+Get the metadata key used for a reminder.
+ */
+fn reminder_key(task: Option<&str>) -> String {
+    match task {
+        Some(text) => {
+            let escaped = KEY_ESCAPES.iter().fold(text.trim().to_string(), |key, (from, to)| key.replace(from, to));
+            format!("{TASK_REMINDER_PREFIX}{escaped}")
+        }
+        None => NOTE_REMINDER_KEY.to_string(),
+    }
+}
+
+
+/**
+Get the task a metadata key refers to.
+# Return:
+`Some(None)` for a note reminder, `Some(Some(text))` for a task reminder or `None` if it's not a reminder key.
+ */
+fn parse_reminder_key(key: &str) -> Option<Option<String>> {
+    if key == NOTE_REMINDER_KEY {
+        return Some(None);
+    }
+
+    let escaped = key.strip_prefix(TASK_REMINDER_PREFIX)?;
+    let text = KEY_ESCAPES.iter().rev().fold(escaped.to_string(), |text, (from, to)| text.replace(to, from));
+    Some(Some(text))
+}
+
+
+/**
+Load the reminders of a note from its metadata.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+A vector with the note's reminders.
+ */
+pub fn note_reminders(note_title: &str) -> Vec<Reminder> {
+    let mut result: Vec<Reminder> = Vec::new();
+
+    for (key, value) in metadata::load_metadata(note_title) {
+        let task = match parse_reminder_key(&key) {
+            Some(task) => task,
+            None => continue,
+        };
+
+        match NaiveDateTime::parse_from_str(&value, REMINDER_FORMAT) {
+            Ok(due) => result.push(Reminder { note_title: note_title.to_string(), task, due }),
+            Err(e) => error!("Invalid reminder {value} on note {note_title}: {e}"),
+        }
+    }
+
+    result
+}
+
+
+/**
+Load the reminders of every note on the default path.
+# Return:
+A vector with all reminders sorted by due date.
+ */
+pub fn list_reminders() -> Vec<Reminder> {
+    let mut result: Vec<Reminder> = notes::list_note_titles()
+        .iter()
+        .flat_map(|note_title| note_reminders(note_title))
+        .collect();
+
+    result.sort_by_key(|reminder| reminder.due);
+    result
+}
+
+
+/**
+Get the reminder set on a note or task.
+# Parameters:
+* `note_title`: The title of the note.
+* `task`: The text of the task, or `None` for the whole note.
+# Return:
+The reminder's due date or `None` if there's no reminder.
+ */
+pub fn get_reminder(note_title: &str, task: Option<&str>) -> Option<NaiveDateTime> {
+    let key = reminder_key(task);
+    note_reminders(note_title)
+        .into_iter()
+        .find(|reminder| reminder_key(reminder.task.as_deref()) == key)
+        .map(|reminder| reminder.due)
+}
+
+
+/**
+Set a reminder on a note or task, replacing any previous one.
+# Parameters:
+* `note_title`: The title of the note.
+* `task`: The text of the task, or `None` for the whole note.
+* `due`: When the reminder should fire.
+# Return:
+Boolean stating if the reminder was saved.
+ */
+pub fn set_reminder(note_title: &str, task: Option<&str>, due: NaiveDateTime) -> bool {
+    if !notes::note_exists(note_title) {
+        warn!("Note {note_title} does not exist");
+        return false;
+    }
+
+    let mut note_metadata = metadata::load_metadata(note_title);
+    note_metadata.insert(reminder_key(task), due.format(REMINDER_FORMAT).to_string());
+    metadata::save_metadata(note_title, &note_metadata)
+}
+
+
+/**
+Remove the reminder of a note or task.
+# Parameters:
+* `note_title`: The title of the note.
+* `task`: The text of the task, or `None` for the whole note.
+# Return:
+Boolean stating if the metadata was saved.
+ */
+pub fn clear_reminder(note_title: &str, task: Option<&str>) -> bool {
+    let mut note_metadata = metadata::load_metadata(note_title);
+    if note_metadata.remove(&reminder_key(task)).is_none() {
+        return true;
+    }
+
+    metadata::save_metadata(note_title, &note_metadata)
+}
+
+
+/**
+Filter the reminders that are due, including the ones whose time passed long ago.
+# Parameters:
+* `reminders`: The reminders to check.
+* `clock`: The clock giving the current time.
+# Return:
+A vector with the due reminders.
+ */
+pub fn due_reminders(reminders: &[Reminder], clock: &dyn Clock) -> Vec<Reminder> {
+    let now = clock.now();
+    reminders
+        .iter()
+        .filter(|reminder| reminder.due <= now)
+        .cloned()
+        .collect()
+}
+
+
+/**
+Get the time left until the next pending reminder.
+# Parameters:
+* `reminders`: The reminders to check.
+* `clock`: The clock giving the current time.
+# Return:
+The time left, or `None` if no reminder is pending.
+ */
+pub fn time_until_next(reminders: &[Reminder], clock: &dyn Clock) -> Option<chrono::Duration> {
+    let now = clock.now();
+    reminders
+        .iter()
+        .filter(|reminder| reminder.due > now)
+        .map(|reminder| reminder.due - now)
+        .min()
+}
+
+
+/**
+Send the due reminders of every note, including the ones missed while the app was closed.
+Each one is removed from metadata only once it was sent, so it fires once and is never lost.
+# Parameters:
+* `clock`: The clock giving the current time.
+* `send`: Called with each due reminder, returns whether its notification was sent.
+# Return:
+A vector with the reminders that were sent.
+ */
+pub fn take_due_reminders(clock: &dyn Clock, mut send: impl FnMut(&Reminder) -> bool) -> Vec<Reminder> {
+    let mut sent: Vec<Reminder> = Vec::new();
+
+    for reminder in due_reminders(&list_reminders(), clock) {
+        if !send(&reminder) {
+            warn!("Reminder for note {} was not sent, keeping it", reminder.note_title);
+            continue;
+        }
+
+        clear_reminder(&reminder.note_title, reminder.task.as_deref());
+        sent.push(reminder);
+    }
+
+    sent
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checklists;
+    use crate::test_utils::TestDirs;
+
+    /// Clock stopped at a fixed time.
+    struct FakeClock(NaiveDateTime);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> NaiveDateTime {
+            self.0
+        }
+    }
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, REMINDER_FORMAT).expect("valid test date")
+    }
+
+    fn reminder(task: Option<&str>, due: &str) -> Reminder {
+        Reminder { note_title: "Note".to_string(), task: task.map(str::to_string), due: at(due) }
+    }
+
+    #[test]
+    fn due_reminders_include_missed_ones() {
+        let clock = FakeClock(at("2024-05-10 12:00"));
+        let reminders = [
+            reminder(None, "2024-05-01 09:00"),
+            reminder(Some("now"), "2024-05-10 12:00"),
+            reminder(Some("later"), "2024-05-10 12:01"),
+        ];
+
+        let due: Vec<Option<String>> = due_reminders(&reminders, &clock).into_iter().map(|due| due.task).collect();
+        assert_eq!(due, vec![None, Some("now".to_string())]);
+    }
+
+    #[test]
+    fn time_until_next_skips_due_reminders() {
+        let clock = FakeClock(at("2024-05-10 12:00"));
+        let reminders = [
+            reminder(None, "2024-05-10 11:00"),
+            reminder(Some("soon"), "2024-05-10 12:30"),
+            reminder(Some("later"), "2024-05-11 12:00"),
+        ];
+
+        assert_eq!(time_until_next(&reminders, &clock), Some(chrono::Duration::minutes(30)));
+        assert_eq!(time_until_next(&reminders[..1], &clock), None);
+    }
+
+    #[test]
+    fn take_due_reminders_keeps_unsent_ones() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Chores", "- [ ] bins\n- [ ] dishes\n- [ ] laundry\n"));
+        assert!(set_reminder("Chores", Some("bins"), at("2024-05-10 08:00")));
+        assert!(set_reminder("Chores", Some("dishes"), at("2024-05-10 09:00")));
+        assert!(set_reminder("Chores", Some("laundry"), at("2024-05-11 09:00")));
+        let clock = FakeClock(at("2024-05-10 12:00"));
+
+        let sent = take_due_reminders(&clock, |reminder| reminder.task.as_deref() == Some("bins"));
+        assert_eq!(sent.len(), 1);
+        assert_eq!(get_reminder("Chores", Some("bins")), None);
+        assert_eq!(get_reminder("Chores", Some("dishes")), Some(at("2024-05-10 09:00")));
+        assert_eq!(get_reminder("Chores", Some("laundry")), Some(at("2024-05-11 09:00")));
+
+        let sent = take_due_reminders(&clock, |_| true);
+        assert_eq!(sent.len(), 1);
+        assert!(take_due_reminders(&clock, |_| true).is_empty());
+    }
+
+    #[test]
+    fn task_reminders_follow_moved_tasks() {
+        let _dirs = TestDirs::new();
+        assert!(notes::create_a_note("Trip", "- [ ] pack = light\n"));
+        assert!(set_reminder("Trip", Some("pack = light"), at("2024-06-01 07:00")));
+
+        assert!(notes::overwrite_note("Trip", "Before leaving:\n\n- [ ] pack = light\n"));
+        assert!(checklists::set_task_done("Trip", 2, true));
+
+        let reminders = note_reminders("Trip");
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].description(), "pack = light");
+        assert_eq!(get_reminder("Trip", Some("pack = light")), Some(at("2024-06-01 07:00")));
+        assert!(clear_reminder("Trip", Some("pack = light")));
+        assert!(note_reminders("Trip").is_empty());
+    }
+}
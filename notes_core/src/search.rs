@@ -0,0 +1,73 @@
+use crate::notes;
+
+
+// Amount of matching lines kept per note
+const MAX_MATCH_LINES: usize = 3;
+
+
+/**
+A note matching a search.
+ */
+pub struct SearchMatch {
+    pub note_title: String,
+    /// Whether every search term is in the title.
+    pub title_matches: bool,
+    /// Lines of the note's contents containing any of the terms.
+    pub lines: Vec<String>,
+}
+
+
+/**
+Split a query into lowercase terms.
+ */
+pub fn query_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+
+/**
+Search the notes on the default path, ignoring case.
+A note matches when each term is in its title or its contents.
+# Parameters:
+* `terms`: The search terms, as returned by `query_terms`.
+# Return:
+The matching notes, the ones matching by title first and then by title order.
+ */
+pub fn search_notes(terms: &[String]) -> Vec<SearchMatch> {
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    let mut result: Vec<SearchMatch> = Vec::new();
+    for note_title in notes::list_note_titles() {
+        let contents = notes::read_note(&note_title).unwrap_or_default();
+        let lower_title = note_title.to_lowercase();
+        let lower_contents = contents.to_lowercase();
+
+        let matches_all = terms
+            .iter()
+            .all(|term| lower_title.contains(term.as_str()) || lower_contents.contains(term.as_str()));
+        if !matches_all {
+            continue;
+        }
+
+        let lines: Vec<String> = contents
+            .lines()
+            .filter(|line| {
+                let lower_line = line.to_lowercase();
+                terms.iter().any(|term| lower_line.contains(term.as_str()))
+            })
+            .take(MAX_MATCH_LINES)
+            .map(|line| line.trim().to_string())
+            .collect();
+
+        result.push(SearchMatch {
+            title_matches: terms.iter().all(|term| lower_title.contains(term.as_str())),
+            note_title,
+            lines,
+        });
+    }
+
+    result.sort_by(|a, b| b.title_matches.cmp(&a.title_matches).then_with(|| a.note_title.cmp(&b.note_title)));
+    result
+}
@@ -0,0 +1,229 @@
+use std::path::PathBuf;
+use std::fs;
+use chrono::{Local, NaiveDateTime};
+use crate::config;
+use crate::{error, info, warn};
+
+
+// generic constants
+/// Directory under the config directory holding the templates, one `<name>.txt` file each.
+pub const TEMPLATES_DIR_NAME: &str = "templates";
+const TEMPLATE_EXTENSION: &str = ".txt";
+
+// Placeholders
+pub const TITLE_PLACEHOLDER: &str = "{{title}}";
+const DATE_PLACEHOLDER: &str = "{{date}}";
+const TIME_PLACEHOLDER: &str = "{{time}}";
+const CURSOR_PLACEHOLDER: &str = "{{cursor}}";
+
+// Formats used when filling placeholders
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+const TIME_FORMAT: &str = "%H:%M";
+
+
+/**
+A template with its placeholders already filled in.
+ */
+pub struct RenderedTemplate {
+    /// The template contents ready to be used as a note body.
+    pub contents: String,
+    /// Character offset where the `{{cursor}}` placeholder was, if any.
+    pub cursor_offset: Option<i32>,
+}
+
+
+/**
+Get the directory templates are read from, under the app's config directory.
+# Return:
+The path, which may not exist, or `None` if there is no config directory.
+ */
+pub fn templates_dir() -> Option<PathBuf> {
+    Some(config::config_dir()?.join(TEMPLATES_DIR_NAME))
+}
+
+
+/**
+Load template names from default path
+# Return
+A vector with the names of the templates stored or an empty vector.
+ */
+pub fn load_templates() -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+
+    let templates_dir = match templates_dir() {
+        Some(dir) => dir,
+        None => {
+            warn!("There is no config directory for templates, set $XDG_CONFIG_HOME or $HOME");
+            return result;
+        }
+    };
+
+    if !templates_dir.exists() {
+        info!("Creating a new directory {}", templates_dir.display());
+
+        if let Err(e) = fs::create_dir_all(&templates_dir) {
+            error!("Failed to create directory {}: {e}", templates_dir.display());
+            return result;
+        }
+    }
+
+    let dir_iterator = match fs::read_dir(&templates_dir) {
+        Ok(iterator) => iterator,
+        Err(e) => {
+            error!("Error reading directory {}: {e}", templates_dir.display());
+            return result;
+        }
+    };
+
+    for file in dir_iterator {
+        let file_name = match file {
+            Ok(entry) => entry.file_name(),
+            Err(e) => {
+                error!("Error reading file entry: {e}");
+                continue;
+            }
+        };
+
+        let template_name = match file_name.into_string() {
+            Ok(name) => name,
+            Err(e) => {
+                error!("Error getting file name as string: {e:?}");
+                continue;
+            }
+        };
+
+        if let Some(name) = template_name.strip_suffix(TEMPLATE_EXTENSION) {
+            result.push(name.trim().to_string());
+        }
+    }
+
+    result.sort();
+    result
+}
+
+
+/**
+Read a template from the default path and fill in its placeholders.
+
+`{{date}}` and `{{time}}` are replaced by the current local date and time,
+`{{title}}` by the given note title and `{{cursor}}` is removed, keeping its position.
+
+# Parameters:
+* `template_name`: The name of the template without extension.
+* `note_title`: The title to use for the `{{title}}` placeholder.
+
+# Return:
+The rendered template or `None` if it could not be read.
+ */
+pub fn render_template(template_name: &str, note_title: &str) -> Option<RenderedTemplate> {
+    render_template_at(template_name, note_title, Local::now().naive_local())
+}
+
+
+/**
+Read a template from the default path and fill in its placeholders for a given moment.
+# Parameters:
+* `template_name`: The name of the template without extension.
+* `note_title`: The title to use for the `{{title}}` placeholder.
+* `moment`: The date and time to use for the `{{date}}` and `{{time}}` placeholders.
+# Return:
+The rendered template or `None` if it could not be read.
+ */
+pub fn render_template_at(
+    template_name: &str,
+    note_title: &str,
+    moment: NaiveDateTime
+) -> Option<RenderedTemplate> {
+    let template_path = templates_dir()?.join(format!("{template_name}{TEMPLATE_EXTENSION}"));
+    let template = match fs::read_to_string(&template_path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            error!("Error reading template at path {template_path:?}: {e}");
+            return None;
+        }
+    };
+
+    Some(fill_placeholders(&template, note_title, moment))
+}
+
+
+/**
+Fill in the placeholders of a template's contents.
+# Parameters:
+* `template`: The raw template contents.
+* `note_title`: The title to use for the `{{title}}` placeholder.
+* `moment`: The date and time to use for the `{{date}}` and `{{time}}` placeholders.
+# Return:
+The rendered template.
+ */
+pub fn fill_placeholders(template: &str, note_title: &str, moment: NaiveDateTime) -> RenderedTemplate {
+    let filled = template
+        .replace(DATE_PLACEHOLDER, &moment.format(DATE_FORMAT).to_string())
+        .replace(TIME_PLACEHOLDER, &moment.format(TIME_FORMAT).to_string());
+
+    // An empty title is left as a placeholder so it can be filled when the note is created.
+    let filled = if note_title.is_empty() {
+        filled
+    } else {
+        filled.replace(TITLE_PLACEHOLDER, note_title)
+    };
+
+    // Only the first cursor placeholder is used, the rest are just removed.
+    let cursor_offset = filled
+        .find(CURSOR_PLACEHOLDER)
+        .map(|byte_index| filled[..byte_index].chars().count() as i32);
+
+    RenderedTemplate {
+        contents: filled.replace(CURSOR_PLACEHOLDER, ""),
+        cursor_offset,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::test_utils::TestDirs;
+
+    fn moment() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 9).and_then(|date| date.and_hms_opt(7, 5, 0)).expect("a valid date")
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let rendered = fill_placeholders("# {{title}}\n{{date}} {{time}}\n{{cursor}}\n", "Plan", moment());
+        assert_eq!(rendered.contents, "# Plan\n2024-03-09 07:05\n\n");
+        assert_eq!(rendered.cursor_offset, Some(24));
+    }
+
+    #[test]
+    fn keeps_the_title_placeholder_without_a_title() {
+        let rendered = fill_placeholders("# {{title}}\n", "", moment());
+        assert_eq!(rendered.contents, "# {{title}}\n");
+        assert_eq!(rendered.cursor_offset, None);
+    }
+
+    #[test]
+    fn counts_the_cursor_in_characters_and_removes_extra_ones() {
+        let rendered = fill_placeholders("día {{cursor}}más{{cursor}}", "", moment());
+        assert_eq!(rendered.contents, "día más");
+        assert_eq!(rendered.cursor_offset, Some(4));
+    }
+
+    #[test]
+    fn loads_templates_from_the_config_dir() {
+        let dirs = TestDirs::new();
+        assert!(load_templates().is_empty());
+
+        let dir = dirs.root.join("config").join(config::APP_DIR_NAME).join(TEMPLATES_DIR_NAME);
+        assert_eq!(templates_dir(), Some(dir.clone()));
+        fs::write(dir.join("meeting.txt"), "# {{title}} on {{date}}\n").expect("write a template");
+        fs::write(dir.join("notes.md"), "not a template").expect("write another file");
+
+        assert_eq!(load_templates(), vec!["meeting".to_string()]);
+        let rendered = render_template_at("meeting", "Standup", moment()).expect("render the template");
+        assert_eq!(rendered.contents, "# Standup on 2024-03-09\n");
+        assert!(render_template_at("missing", "Standup", moment()).is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::notes;
+
+
+/// The notes directory and the XDG variables are global, so tests using them run one at a time.
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+
+/**
+A temporary notes directory with its own config and state directories, removed when dropped.
+ */
+pub struct TestDirs {
+    pub root: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+
+impl TestDirs {
+    /**
+    Point the notes directory and the XDG config and state directories at a new temporary directory.
+     */
+    pub fn new() -> TestDirs {
+        // A failed test poisons the lock, the next ones still get fresh directories.
+        let lock = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let root = env::temp_dir().join(format!(
+            "notes_core_test_{}_{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        let notes_dir = root.join("notes");
+        fs::create_dir_all(&notes_dir).expect("create the test notes directory");
+
+        notes::set_notes_dir(&notes_dir.to_string_lossy());
+        env::set_var("XDG_CONFIG_HOME", root.join("config"));
+        env::set_var("XDG_STATE_HOME", root.join("state"));
+
+        TestDirs { root, _lock: lock }
+    }
+}
+
+
+impl Drop for TestDirs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
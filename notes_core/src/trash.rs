@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::notes;
+use crate::metadata;
+use crate::attachments;
+use crate::{error, warn};
+
+
+// generic constants
+/// The trash is a hidden directory inside the notes directory, so it moves along with it.
+pub const TRASH_DIR_NAME: &str = ".trash";
+const NOTE_EXTENSION: &str = "txt";
+
+
+/**
+Get the files of a note on the default path: the note itself, its metadata and its attachments directory.
+ */
+fn note_files(note_title: &str) -> [PathBuf; 3] {
+    [
+        notes::note_path(note_title),
+        metadata::metadata_path(note_title),
+        attachments::attachments_dir(note_title),
+    ]
+}
+
+
+/**
+Get the trash directory, which may not exist.
+ */
+pub fn trash_dir() -> PathBuf {
+    notes::notes_dir().join(TRASH_DIR_NAME)
+}
+
+
+/**
+Get where a file of the notes directory goes in the trash.
+ */
+fn trashed_path(path: &Path) -> Option<PathBuf> {
+    Some(trash_dir().join(path.file_name()?))
+}
+
+
+/**
+Remove a file or a directory, whichever the path is.
+ */
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+
+/**
+Move a note, with its metadata and attachments, to the trash.
+A note with the same title already in the trash is replaced.
+# Parameters:
+* `note_title`: The title of the note to trash.
+# Return:
+Boolean stating if the note was moved to the trash.
+ */
+pub fn trash_note(note_title: &str) -> bool {
+    if !notes::note_exists(note_title) {
+        warn!("Note {note_title} does not exist");
+        return false;
+    }
+
+    let trash_dir = trash_dir();
+    if let Err(e) = fs::create_dir_all(&trash_dir) {
+        error!("Failed to create directory {}: {e}", trash_dir.display());
+        return false;
+    }
+
+    // Only the latest trashed version of a title is kept.
+    if is_trashed(note_title) {
+        delete_from_trash(note_title);
+    }
+
+    for path in note_files(note_title) {
+        if !path.exists() {
+            continue;
+        }
+        let target = match trashed_path(&path) {
+            Some(target) => target,
+            None => continue,
+        };
+        if let Err(e) = fs::rename(&path, &target) {
+            error!("Error moving {path:?} to {target:?}: {e}");
+            // Without the note itself nothing was trashed, a missing companion is only lost.
+            if path == notes::note_path(note_title) {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+
+/**
+Move a note back from the trash to the notes directory.
+# Parameters:
+* `note_title`: The title of the trashed note.
+# Return:
+Boolean stating if the note was restored, which fails if a note with the same title exists.
+ */
+pub fn restore_note(note_title: &str) -> bool {
+    if !is_trashed(note_title) {
+        warn!("Note {note_title} is not in the trash");
+        return false;
+    }
+    if notes::note_exists(note_title) {
+        warn!("Note {note_title} already exists in {}", notes::notes_dir().display());
+        return false;
+    }
+
+    for path in note_files(note_title) {
+        let source = match trashed_path(&path) {
+            Some(source) => source,
+            None => continue,
+        };
+        if !source.exists() {
+            continue;
+        }
+        if let Err(e) = fs::rename(&source, &path) {
+            error!("Error moving {source:?} to {path:?}: {e}");
+            if path == notes::note_path(note_title) {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+
+/**
+Check if a note with the given title is in the trash.
+ */
+pub fn is_trashed(note_title: &str) -> bool {
+    notes::is_valid_title(note_title) && trashed_path(&notes::note_path(note_title)).is_some_and(|path| path.exists())
+}
+
+
+/**
+List the notes in the trash.
+# Return:
+The titles of the trashed notes, sorted, or an empty vector if the trash is empty or missing.
+ */
+pub fn list_trashed_notes() -> Vec<String> {
+    let entries = match fs::read_dir(trash_dir()) {
+        Ok(entries) => entries,
+        // A missing trash is an empty one.
+        Err(_) => return Vec::new(),
+    };
+
+    let mut result: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == NOTE_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect();
+    result.sort();
+    result
+}
+
+
+/**
+Permanently delete a note from the trash, along with its metadata and attachments.
+# Parameters:
+* `note_title`: The title of the trashed note.
+# Return:
+Boolean stating if the note was deleted.
+ */
+pub fn delete_from_trash(note_title: &str) -> bool {
+    if !is_trashed(note_title) {
+        warn!("Note {note_title} is not in the trash");
+        return false;
+    }
+
+    let mut success = true;
+    for path in note_files(note_title) {
+        let trashed = match trashed_path(&path) {
+            Some(trashed) => trashed,
+            None => continue,
+        };
+        if !trashed.exists() {
+            continue;
+        }
+        if let Err(e) = remove_path(&trashed) {
+            error!("Error deleting {trashed:?}: {e}");
+            success = false;
+        }
+    }
+
+    success
+}
+
+
+/**
+Permanently delete every note in the trash.
+# Return:
+Boolean stating if every note was deleted.
+ */
+pub fn empty_trash() -> bool {
+    let mut success = true;
+    for note_title in list_trashed_notes() {
+        // Keep going so one broken note doesn't leave the rest behind.
+        if !delete_from_trash(&note_title) {
+            success = false;
+        }
+    }
+
+    success
+}
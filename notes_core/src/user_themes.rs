@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::error;
+
+
+// Settings
+/// Name of the user theme loaded on top of the app's stylesheet, none if empty.
+pub const USER_THEME_SETTING: &str = "user_theme";
+
+// generic constants
+/// Directory under the config directory holding the user themes, one `<name>.css` file each.
+pub const THEMES_DIR_NAME: &str = "themes";
+const THEME_EXTENSION: &str = "css";
+
+
+/**
+Get the directory user themes are read from.
+# Return:
+The path, which may not exist, or `None` if there is no config directory.
+ */
+pub fn themes_dir() -> Option<PathBuf> {
+    Some(config::config_dir()?.join(THEMES_DIR_NAME))
+}
+
+
+/**
+List the user themes.
+# Return:
+The names of the themes, sorted, or an empty vector if there are none.
+ */
+pub fn list_user_themes() -> Vec<String> {
+    let dir = match themes_dir() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        // Most users have no themes directory at all.
+        Err(_) => return Vec::new(),
+    };
+
+    let mut result: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == THEME_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect();
+    result.sort();
+    result
+}
+
+
+/**
+Get the stylesheet of a user theme.
+# Parameters:
+* `name`: The name of the theme.
+# Return:
+The path, which may not exist, or `None` if the name is empty or there is no config directory.
+ */
+pub fn user_theme_path(name: &str) -> Option<PathBuf> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(themes_dir()?.join(format!("{name}.{THEME_EXTENSION}")))
+}
+
+
+/**
+Create the themes directory so users have somewhere to put their themes.
+# Return:
+The directory or `None` if it could not be created.
+ */
+pub fn create_themes_dir() -> Option<PathBuf> {
+    let dir = themes_dir()?;
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Error creating directory {dir:?}: {e}");
+        return None;
+    }
+
+    Some(dir)
+}
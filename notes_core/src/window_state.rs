@@ -0,0 +1,181 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::metadata::{self, Metadata};
+use crate::{error, warn};
+
+
+// generic constants
+/// Kept in the state directory, it's remembered between sessions but not worth backing up.
+const STATE_FILE_NAME: &str = "window.state";
+pub const DEFAULT_WIDTH: i32 = 800;
+pub const DEFAULT_HEIGHT: i32 = 500;
+pub const DEFAULT_SIDEBAR_WIDTH: i32 = 250;
+/// Smaller sizes are ignored, e.g. a window that was never shown reports a size of 0.
+const MIN_SIZE: i32 = 50;
+
+// State keys
+const WIDTH_KEY: &str = "width";
+const HEIGHT_KEY: &str = "height";
+const SIDEBAR_WIDTH_KEY: &str = "sidebar_width";
+const MAXIMIZED_KEY: &str = "maximized";
+const SELECTED_NOTE_KEY: &str = "selected_note";
+const SCROLL_KEY: &str = "scroll";
+const CURSOR_KEY: &str = "cursor";
+
+
+/**
+Size and layout of the main window and the note it was showing, restored when the app starts.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct WindowState {
+    /// Size of the window when it's not maximized.
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+    /// Position of the divider between the note list and the note contents.
+    pub sidebar_width: i32,
+    /// Title of the note being displayed, none if empty.
+    pub selected_note: String,
+    /// Pixels the note contents were scrolled down.
+    pub scroll: f64,
+    /// Character offset where the text selected in the note contents starts, if any was.
+    pub cursor: Option<i32>,
+}
+
+
+impl Default for WindowState {
+    fn default() -> Self {
+        WindowState {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            maximized: false,
+            sidebar_width: DEFAULT_SIDEBAR_WIDTH,
+            selected_note: String::new(),
+            scroll: 0.0,
+            cursor: None,
+        }
+    }
+}
+
+
+impl WindowState {
+    /**
+    Read the window state saved by the last session, missing or invalid values are replaced by their default.
+     */
+    pub fn load() -> WindowState {
+        let state = load_state_file();
+        let defaults = WindowState::default();
+        let value = |key: &str| state.get(key).map(|value| value.trim()).filter(|value| !value.is_empty());
+        let size = |key: &str, default: i32| {
+            value(key)
+                .and_then(|size| size.parse::<i32>().ok())
+                .filter(|size| *size >= MIN_SIZE)
+                .unwrap_or(default)
+        };
+
+        WindowState {
+            width: size(WIDTH_KEY, defaults.width),
+            height: size(HEIGHT_KEY, defaults.height),
+            maximized: value(MAXIMIZED_KEY)
+                .and_then(|maximized| maximized.parse::<bool>().ok())
+                .unwrap_or(defaults.maximized),
+            sidebar_width: size(SIDEBAR_WIDTH_KEY, defaults.sidebar_width),
+            selected_note: value(SELECTED_NOTE_KEY).unwrap_or_default().to_string(),
+            scroll: value(SCROLL_KEY)
+                .and_then(|scroll| scroll.parse::<f64>().ok())
+                .filter(|scroll| scroll.is_finite() && *scroll >= 0.0)
+                .unwrap_or(defaults.scroll),
+            cursor: value(CURSOR_KEY)
+                .and_then(|cursor| cursor.parse::<i32>().ok())
+                .filter(|cursor| *cursor >= 0),
+        }
+    }
+
+    /**
+    Write the window state, keeping every other value in the state file.
+    Sizes too small to be real are skipped so the last good ones are kept.
+    # Return:
+    Boolean stating if the state file was written.
+     */
+    pub fn save(&self) -> bool {
+        let mut state = load_state_file();
+        for (key, size) in [(WIDTH_KEY, self.width), (HEIGHT_KEY, self.height), (SIDEBAR_WIDTH_KEY, self.sidebar_width)] {
+            if size >= MIN_SIZE {
+                state.insert(key.to_string(), size.to_string());
+            }
+        }
+        state.insert(MAXIMIZED_KEY.to_string(), self.maximized.to_string());
+        state.insert(SELECTED_NOTE_KEY.to_string(), self.selected_note.trim().to_string());
+        state.insert(SCROLL_KEY.to_string(), self.scroll.max(0.0).round().to_string());
+        match self.cursor {
+            Some(cursor) => state.insert(CURSOR_KEY.to_string(), cursor.to_string()),
+            None => state.remove(CURSOR_KEY),
+        };
+
+        save_state_file(&state)
+    }
+}
+
+
+/**
+Get the path of the window state file.
+# Return:
+The path, which may not exist, or `None` if there is no state directory.
+ */
+pub fn state_path() -> Option<PathBuf> {
+    Some(config::state_dir()?.join(STATE_FILE_NAME))
+}
+
+
+/**
+Load every value from the window state file.
+# Return:
+The values, empty if the file doesn't exist or could not be read.
+ */
+fn load_state_file() -> Metadata {
+    let path = match state_path() {
+        Some(path) => path,
+        None => return Metadata::new(),
+    };
+    if !path.exists() {
+        return Metadata::new();
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => metadata::parse_metadata(&contents),
+        Err(e) => {
+            error!("Error reading file at path {path:?}: {e}");
+            Metadata::new()
+        }
+    }
+}
+
+
+/**
+Write every value to the window state file, creating its directory if needed.
+# Return:
+Boolean stating if the file was written successfully.
+ */
+fn save_state_file(state: &Metadata) -> bool {
+    let (dir, path) = match (config::state_dir(), state_path()) {
+        (Some(dir), Some(path)) => (dir, path),
+        _ => {
+            warn!("There is no state directory, set $XDG_STATE_HOME or $HOME");
+            return false;
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(&dir) {
+        error!("Error creating directory {dir:?}: {e}");
+        return false;
+    }
+
+    if let Err(e) = fs::write(&path, metadata::format_metadata(state)) {
+        error!("Error writing file at path {path:?}: {e}");
+        return false;
+    }
+
+    true
+}
@@ -0,0 +1,327 @@
+use std::io::Read;
+use std::path::Path;
+use serde_json::{json, Value};
+
+use notes_core::{notes, Note};
+use notes_core::metadata;
+use notes_core::checklists;
+use notes_core::export;
+use notes_core::search;
+use notes_core::quick_capture;
+use notes_core::trash;
+use notes_core::error;
+
+
+// Exit codes
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const JSON_FLAG: &str = "--json";
+const PERMANENT_FLAG: &str = "--permanent";
+const TO_FLAG: &str = "--to";
+
+// Error messages
+const NOTE_NOT_FOUND: &str = "Note not found";
+const INVALID_TITLE: &str = "Invalid note title, titles can't be empty, start with a dot or contain / or \\";
+const MISSING_CAPTURE_TITLE: &str = "Missing note title after --to";
+const UNKNOWN_FORMAT: &str = "Unknown export format, use html or md (PDF is only available in the app)";
+
+const USAGE: &str = "Usage: rust_gtk_notes_app [<command> [arguments]] [--json] [--verbose]
+
+Without a command the app window is opened, or the running one is focused:
+  rust_gtk_notes_app [note file...]          Open note files from the notes directory
+  rust_gtk_notes_app --note <title>          Open a note by its title
+  rust_gtk_notes_app --new                   Open the dialog to create a new note
+  rust_gtk_notes_app --capture               Open the quick capture window only
+
+Commands:
+  list                              List all notes
+  show <title>                      Print a note
+  new <title>                       Create a note with the contents read from stdin
+  rm [--permanent] <title>          Move a note to the trash, or delete it for good
+  rename <title> <new title>        Rename a note
+  search <query>                    Search notes by title and contents
+  export <html|md> <output> [title...]
+                                    Export notes (all of them if no title is given)
+  capture [--to <title>] [text...]  Append a timestamped line to a note, \"Inbox\" by default
+                                    (the text is read from stdin if not given)
+  help                              Show this message
+
+Use --json on any command for machine-readable output.
+Use --verbose (-v) anywhere to log debug messages and show them on stderr.";
+
+
+/**
+Run a command line subcommand without starting the gtk application.
+
+# Parameters:
+* `args`: The command line arguments, without the program name.
+
+# Return:
+The exit code of the command or `None` if there is no command and the app window should be opened.
+ */
+pub fn run(args: &[String]) -> Option<i32> {
+    let json_output = args.iter().any(|arg| arg == JSON_FLAG);
+    let args: Vec<&str> = args.iter().map(String::as_str).filter(|arg| *arg != JSON_FLAG).collect();
+
+    let (command, rest) = args.split_first()?;
+
+    let exit_code = match (*command, rest) {
+        ("list", []) => list_command(json_output),
+        ("show", [title]) => show_command(title, json_output),
+        ("new" | "rm", [title]) | ("rm", [PERMANENT_FLAG, title]) if !notes::is_valid_title(title) => {
+            report_error(title, INVALID_TITLE, json_output)
+        }
+        ("new", [title]) => new_command(title, json_output),
+        ("rm", [title]) => rm_command(title, false, json_output),
+        ("rm", [PERMANENT_FLAG, title]) => rm_command(title, true, json_output),
+        ("rename", [title, new_title]) if !notes::is_valid_title(title) || !notes::is_valid_title(new_title) => {
+            let invalid = if notes::is_valid_title(title) { new_title } else { title };
+            report_error(invalid, INVALID_TITLE, json_output)
+        }
+        ("rename", [title, new_title]) => rename_command(title, new_title, json_output),
+        ("search", query) if !query.is_empty() => search_command(&query.join(" "), json_output),
+        ("export", [format, output, titles @ ..]) => export_command(format, Path::new(output), titles, json_output),
+        // An empty title captures to the inbox note.
+        ("capture", [TO_FLAG]) => usage_error(MISSING_CAPTURE_TITLE, json_output),
+        ("capture", [TO_FLAG, title, ..]) if !title.trim().is_empty() && !notes::is_valid_title(title) => {
+            report_error(title, INVALID_TITLE, json_output)
+        }
+        ("capture", [TO_FLAG, title, text @ ..]) => capture_command(Some(title), text, json_output),
+        ("capture", text) => capture_command(None, text, json_output),
+        ("help" | "--help" | "-h", _) => {
+            println!("{USAGE}");
+            EXIT_OK
+        }
+        // Options and note files are left for the gtk application.
+        (command, _) if command.starts_with('-') || Path::new(command).exists() => return None,
+        _ => {
+            eprintln!("{USAGE}");
+            EXIT_USAGE
+        }
+    };
+
+    Some(exit_code)
+}
+
+
+/**
+Print a JSON value on stdout.
+ */
+fn print_json(value: &Value) {
+    match serde_json::to_string_pretty(value) {
+        Ok(text) => println!("{text}"),
+        Err(e) => error!("Error serializing output: {e}"),
+    }
+}
+
+
+/**
+Print the outcome of a command that only succeeds or fails.
+ */
+fn report(success: bool, action: &str, title: &str, json_output: bool) -> i32 {
+    if json_output {
+        print_json(&json!({ "title": title, "action": action, "success": success }));
+    } else if success {
+        println!("{action}: {title}");
+    }
+
+    if success { EXIT_OK } else { EXIT_FAILURE }
+}
+
+
+/**
+Print why a command could not run on a note, as a JSON object with the `--json` flag.
+# Return:
+The exit code of the command.
+ */
+fn report_error(title: &str, message: &str, json_output: bool) -> i32 {
+    if json_output {
+        print_json(&json!({ "title": title, "error": message, "success": false }));
+    } else {
+        error!("{message}: {title:?}");
+    }
+
+    EXIT_FAILURE
+}
+
+
+/**
+Print why the arguments of a command are wrong, as a JSON object with the `--json` flag.
+# Return:
+The usage exit code.
+ */
+fn usage_error(message: &str, json_output: bool) -> i32 {
+    if json_output {
+        print_json(&json!({ "error": message, "success": false }));
+    } else {
+        error!("{message}");
+        eprintln!("{USAGE}");
+    }
+
+    EXIT_USAGE
+}
+
+
+fn list_command(json_output: bool) -> i32 {
+    let mut note_titles = notes::list_note_titles();
+    note_titles.sort();
+
+    if !json_output {
+        for note_title in note_titles {
+            println!("{note_title}");
+        }
+        return EXIT_OK;
+    }
+
+    let entries: Vec<Value> = note_titles
+        .iter()
+        .map(|note_title| {
+            let progress = checklists::task_progress(note_title);
+            json!({
+                "title": note_title,
+                "metadata": metadata::load_metadata(note_title),
+                "tasks_done": progress.map(|(done, _)| done),
+                "tasks_total": progress.map(|(_, total)| total),
+            })
+        })
+        .collect();
+
+    print_json(&Value::Array(entries));
+    EXIT_OK
+}
+
+
+fn show_command(title: &str, json_output: bool) -> i32 {
+    let note = match Note::load(title) {
+        Some(note) => note,
+        None => return report_error(title, NOTE_NOT_FOUND, json_output),
+    };
+
+    if !json_output {
+        print!("{}", note.contents);
+        return EXIT_OK;
+    }
+
+    let tasks: Vec<Value> = note
+        .tasks
+        .iter()
+        .map(|task| json!({ "line": task.line, "text": task.text, "done": task.done }))
+        .collect();
+    let note_attachments: Vec<String> = note
+        .attachments
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    print_json(&json!({
+        "title": note.title,
+        "contents": note.contents,
+        "metadata": note.metadata,
+        "tasks": tasks,
+        "attachments": note_attachments,
+    }));
+    EXIT_OK
+}
+
+
+fn new_command(title: &str, json_output: bool) -> i32 {
+    let mut contents = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut contents) {
+        error!("Error reading note contents from stdin: {e}");
+        return EXIT_FAILURE;
+    }
+
+    // Makes sure the notes directory exists.
+    notes::list_note_titles();
+    report(notes::create_a_note(title, &contents), "created", title, json_output)
+}
+
+
+fn rm_command(title: &str, permanent: bool, json_output: bool) -> i32 {
+    if permanent {
+        report(notes::delete_a_note(title), "deleted", title, json_output)
+    } else {
+        report(trash::trash_note(title), "trashed", title, json_output)
+    }
+}
+
+
+fn rename_command(title: &str, new_title: &str, json_output: bool) -> i32 {
+    let success = notes::rename_note(title, new_title);
+    if json_output {
+        print_json(&json!({ "title": title, "new_title": new_title, "action": "renamed", "success": success }));
+    } else if success {
+        println!("renamed: {title} -> {new_title}");
+    }
+
+    if success { EXIT_OK } else { EXIT_FAILURE }
+}
+
+
+fn search_command(query: &str, json_output: bool) -> i32 {
+    let matches = search::search_notes(&search::query_terms(query));
+
+    if !json_output {
+        for found in &matches {
+            println!("{}", found.note_title);
+            for line in &found.lines {
+                println!("    {line}");
+            }
+        }
+        return EXIT_OK;
+    }
+
+    let entries: Vec<Value> = matches
+        .iter()
+        .map(|found| json!({ "title": found.note_title, "title_matches": found.title_matches, "lines": found.lines }))
+        .collect();
+
+    print_json(&Value::Array(entries));
+    EXIT_OK
+}
+
+
+fn export_command(format: &str, output: &Path, titles: &[&str], json_output: bool) -> i32 {
+    let note_titles: Vec<String> = if titles.is_empty() {
+        notes::list_note_titles()
+    } else {
+        titles.iter().map(|title| title.to_string()).collect()
+    };
+
+    let success = match format {
+        "html" => export::export_html(&note_titles, output),
+        "md" => export::export_markdown_bundle(&note_titles, output),
+        _ => return usage_error(&format!("{UNKNOWN_FORMAT}: {format:?}"), json_output),
+    };
+
+    if json_output {
+        print_json(&json!({
+            "format": format,
+            "output": output.to_string_lossy(),
+            "notes": note_titles,
+            "success": success,
+        }));
+    } else if success {
+        println!("exported {} notes to {}", note_titles.len(), output.to_string_lossy());
+    }
+
+    if success { EXIT_OK } else { EXIT_FAILURE }
+}
+
+
+fn capture_command(title: Option<&str>, text: &[&str], json_output: bool) -> i32 {
+    let mut captured = text.join(" ");
+    if captured.is_empty() {
+        if let Err(e) = std::io::stdin().read_to_string(&mut captured) {
+            error!("Error reading text from stdin: {e}");
+            return EXIT_FAILURE;
+        }
+    }
+
+    let note_title = quick_capture::capture(title, &captured);
+    let success = note_title.is_some();
+    let note_title = note_title.unwrap_or_else(|| title.unwrap_or(quick_capture::INBOX_NOTE).to_string());
+    report(success, "captured", &note_title, json_output)
+}
@@ -0,0 +1,368 @@
+use gtk::{gio, glib, prelude::*};
+
+use notes_core::notes;
+use notes_core::quick_capture;
+use notes_core::trash;
+use notes_core::{error, debug};
+
+
+// Interface exported next to the application's own on its object path
+const NOTES_INTERFACE: &str = "org.zeke_desktop_app.Notes";
+const NOTE_CHANGED_SIGNAL: &str = "NoteChanged";
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="org.zeke_desktop_app.Notes">
+    <method name="ListNotes">
+      <arg type="as" name="titles" direction="out"/>
+    </method>
+    <method name="GetNote">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="contents" direction="out"/>
+    </method>
+    <method name="CreateNote">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="contents" direction="in"/>
+    </method>
+    <method name="AppendToNote">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="text" direction="in"/>
+    </method>
+    <method name="DeleteNote">
+      <arg type="s" name="title" direction="in"/>
+    </method>
+    <method name="QuickCapture">
+      <arg type="s" name="title" direction="in"/>
+      <arg type="s" name="text" direction="in"/>
+      <arg type="s" name="captured_to" direction="out"/>
+    </method>
+    <signal name="NoteChanged">
+      <arg type="s" name="title"/>
+      <arg type="s" name="change"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+// D-Bus error names
+const NOT_FOUND_ERROR: &str = "org.zeke_desktop_app.Notes.Error.NotFound";
+const ALREADY_EXISTS_ERROR: &str = "org.zeke_desktop_app.Notes.Error.AlreadyExists";
+const FAILED_ERROR: &str = "org.zeke_desktop_app.Notes.Error.Failed";
+const INVALID_TITLE_ERROR: &str = "org.zeke_desktop_app.Notes.Error.InvalidTitle";
+const INVALID_ARGS_ERROR: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const UNKNOWN_METHOD_ERROR: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+const EXPECTED_TITLE: &str = "Expected a note title";
+const EXPECTED_TITLE_AND_TEXT: &str = "Expected a note title and text";
+
+
+/**
+Kind of change reported by the NoteChanged signal.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum NoteChange {
+    Created,
+    Modified,
+    Deleted,
+}
+
+
+impl NoteChange {
+    /**
+    Get the name of the change as sent on the bus.
+     */
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteChange::Created => "created",
+            NoteChange::Modified => "modified",
+            NoteChange::Deleted => "deleted",
+        }
+    }
+}
+
+
+/**
+Export the notes interface on a D-Bus connection.
+Any connection works, so a private bus can be used instead of the session one.
+# Parameters:
+* `connection`: The connection to export the interface on.
+* `object_path`: The path of the exported object, usually the application's.
+# Return:
+The registration id or `None` if the interface could not be exported.
+ */
+pub fn register_notes_service(connection: &gio::DBusConnection, object_path: &str) -> Option<gio::RegistrationId> {
+    let node_info = match gio::DBusNodeInfo::for_xml(INTERFACE_XML) {
+        Ok(node_info) => node_info,
+        Err(e) => {
+            error!("Error parsing interface description: {e}");
+            return None;
+        }
+    };
+    let interface_info = node_info.lookup_interface(NOTES_INTERFACE)?;
+
+    let registration = connection
+        .register_object(object_path, &interface_info)
+        .method_call(|_, sender, _, _, method_name, parameters, invocation| {
+            debug!("{method_name}{parameters} called by {}", sender.unwrap_or("an unknown sender"));
+            handle_method_call(method_name, &parameters, invocation);
+        })
+        .build();
+
+    match registration {
+        Ok(id) => Some(id),
+        Err(e) => {
+            error!("Error exporting object at path {object_path}: {e}");
+            None
+        }
+    }
+}
+
+
+/**
+Emit the NoteChanged signal to every listener on the bus.
+# Parameters:
+* `connection`: The connection the interface is exported on.
+* `object_path`: The path of the exported object.
+* `note_title`: The title of the note that changed.
+* `change`: What happened to the note.
+ */
+pub fn emit_note_changed(connection: &gio::DBusConnection, object_path: &str, note_title: &str, change: NoteChange) {
+    let parameters = (note_title, change.as_str()).to_variant();
+    if let Err(e) = connection.emit_signal(None, object_path, NOTES_INTERFACE, NOTE_CHANGED_SIGNAL, Some(&parameters)) {
+        error!("Error emitting signal for note {note_title}: {e}");
+    }
+}
+
+
+/**
+Watch the notes directory, including changes made outside the app.
+# Parameters:
+* `on_change`: Called with the title of each note that is created, modified or deleted.
+# Return:
+The monitor, which must be kept alive for as long as changes are wanted, or `None` on error.
+ */
+pub fn watch_notes(on_change: impl Fn(&str, NoteChange) + 'static) -> Option<gio::FileMonitor> {
+    let notes_dir = notes::notes_dir();
+    let monitor = match gio::File::for_path(&notes_dir).monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            error!("Error watching directory {}: {e}", notes_dir.display());
+            return None;
+        }
+    };
+
+    monitor.connect_changed(move |_, file, _, event| {
+        // Writes come in several Changed events, the hint is sent once they're done.
+        let change = match event {
+            gio::FileMonitorEvent::Created => NoteChange::Created,
+            gio::FileMonitorEvent::ChangesDoneHint => NoteChange::Modified,
+            gio::FileMonitorEvent::Deleted => NoteChange::Deleted,
+            _ => return,
+        };
+
+        // Metadata and attachments share the directory, only txt files are notes.
+        let file_name = file.basename().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let note_title = match file_name.strip_suffix(".txt") {
+            Some(title) => title,
+            None => return,
+        };
+
+        debug!("Note {note_title} {}", change.as_str());
+        on_change(note_title, change);
+    });
+
+    Some(monitor)
+}
+
+
+/**
+Run a method of the notes interface and send its reply.
+ */
+fn handle_method_call(method_name: &str, parameters: &glib::Variant, invocation: gio::DBusMethodInvocation) {
+    match method_name {
+        "ListNotes" => {
+            let mut note_titles = notes::list_note_titles();
+            note_titles.sort();
+            invocation.return_value(Some(&(note_titles,).to_variant()));
+        }
+        "GetNote" => match parameters.get::<(String,)>() {
+            Some((note_title,)) if !notes::is_valid_title(&note_title) => reply_invalid_title(invocation, &note_title),
+            Some((note_title,)) => match notes::read_note(&note_title) {
+                Some(contents) => invocation.return_value(Some(&(contents,).to_variant())),
+                None => reply_not_found(invocation, &note_title),
+            },
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, EXPECTED_TITLE),
+        },
+        "CreateNote" => match parameters.get::<(String, String)>() {
+            Some((note_title, _)) if !notes::is_valid_title(&note_title) => reply_invalid_title(invocation, &note_title),
+            Some((note_title, _)) if notes::note_exists(&note_title) => {
+                invocation.return_dbus_error(ALREADY_EXISTS_ERROR, &format!("Note {note_title} already exists"));
+            }
+            Some((note_title, contents)) => {
+                reply_success(invocation, notes::create_a_note(&note_title, &contents), &note_title);
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, EXPECTED_TITLE_AND_TEXT),
+        },
+        "AppendToNote" => match parameters.get::<(String, String)>() {
+            Some((note_title, _)) if !notes::is_valid_title(&note_title) => reply_invalid_title(invocation, &note_title),
+            Some((note_title, _)) if !notes::note_exists(&note_title) => reply_not_found(invocation, &note_title),
+            Some((note_title, text)) => {
+                reply_success(invocation, notes::append_to_note(&note_title, &text), &note_title);
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, EXPECTED_TITLE_AND_TEXT),
+        },
+        "DeleteNote" => match parameters.get::<(String,)>() {
+            Some((note_title,)) if !notes::is_valid_title(&note_title) => reply_invalid_title(invocation, &note_title),
+            Some((note_title,)) if !notes::note_exists(&note_title) => reply_not_found(invocation, &note_title),
+            // Deleted notes go to the trash like from the app, so they can be restored.
+            Some((note_title,)) => reply_success(invocation, trash::trash_note(&note_title), &note_title),
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, EXPECTED_TITLE),
+        },
+        // An empty title captures to the inbox note.
+        "QuickCapture" => match parameters.get::<(String, String)>() {
+            Some((note_title, _)) if !note_title.trim().is_empty() && !notes::is_valid_title(&note_title) => {
+                reply_invalid_title(invocation, &note_title);
+            }
+            Some((note_title, text)) => match quick_capture::capture(Some(&note_title), &text) {
+                Some(captured_to) => invocation.return_value(Some(&(captured_to,).to_variant())),
+                None => invocation.return_dbus_error(FAILED_ERROR, &format!("Could not capture text to note {note_title}")),
+            },
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, EXPECTED_TITLE_AND_TEXT),
+        },
+        _ => invocation.return_dbus_error(UNKNOWN_METHOD_ERROR, &format!("Unknown method {method_name}")),
+    }
+}
+
+
+/**
+Reply with an error for a title that can't be used for a note, e.g. one with path separators.
+ */
+fn reply_invalid_title(invocation: gio::DBusMethodInvocation, note_title: &str) {
+    invocation.return_dbus_error(INVALID_TITLE_ERROR, &format!("Invalid note title {note_title:?}"));
+}
+
+
+/**
+Reply with an error for a note that doesn't exist.
+ */
+fn reply_not_found(invocation: gio::DBusMethodInvocation, note_title: &str) {
+    invocation.return_dbus_error(NOT_FOUND_ERROR, &format!("Note {note_title} does not exist"));
+}
+
+
+/**
+Reply to a method without return values, with an error if it failed.
+ */
+fn reply_success(invocation: gio::DBusMethodInvocation, success: bool, note_title: &str) {
+    if success {
+        invocation.return_value(None);
+    } else {
+        invocation.return_dbus_error(FAILED_ERROR, &format!("Could not write note {note_title}"));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, time::{Duration, Instant}};
+    use super::*;
+
+    const OBJECT_PATH: &str = "/org/zeke_desktop_app/Notes/Test";
+    const CALL_TIMEOUT_MS: i32 = 5000;
+    const SIGNAL_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn connect(bus_address: &str) -> gio::DBusConnection {
+        gio::DBusConnection::for_address_sync(
+            bus_address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        ).expect("connect to the test bus")
+    }
+
+    fn call(
+        client: &gio::DBusConnection,
+        service_name: &str,
+        method_name: &str,
+        parameters: Option<glib::Variant>
+    ) -> Result<glib::Variant, glib::Error> {
+        glib::MainContext::default().block_on(client.call_future(
+            Some(service_name),
+            OBJECT_PATH,
+            NOTES_INTERFACE,
+            method_name,
+            parameters.as_ref(),
+            None,
+            gio::DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+        ))
+    }
+
+    /// Run the main loop until a change shows up, since signals are only delivered by it.
+    fn wait_for_change(changes: &RefCell<Vec<(String, String)>>, change: (&str, &str)) -> bool {
+        let context = glib::MainContext::default();
+        let start = Instant::now();
+        while start.elapsed() < SIGNAL_TIMEOUT {
+            if changes.borrow().iter().any(|(title, kind)| (title.as_str(), kind.as_str()) == change) {
+                return true;
+            }
+            context.iteration(false);
+        }
+        false
+    }
+
+    #[test]
+    fn notes_service_over_test_bus() {
+        let notes_dir = std::env::temp_dir().join(format!("notes_dbus_test_{}", std::process::id()));
+        notes::set_notes_dir(&notes_dir.to_string_lossy());
+        notes::list_note_titles();
+
+        let test_bus = gio::TestDBus::new(gio::TestDBusFlags::NONE);
+        test_bus.up();
+        let bus_address = test_bus.bus_address().expect("test bus address");
+
+        let service = connect(&bus_address);
+        let service_name = service.unique_name().expect("service bus name").to_string();
+        assert!(register_notes_service(&service, OBJECT_PATH).is_some());
+
+        // Wired like the app does, so the signal comes from the directory monitor.
+        let service_clone = service.clone();
+        let _monitor = watch_notes(move |note_title, change| {
+            emit_note_changed(&service_clone, OBJECT_PATH, note_title, change);
+        }).expect("watch the notes directory");
+
+        let client = connect(&bus_address);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let changes_clone = Rc::clone(&changes);
+        client.signal_subscribe(
+            None,
+            Some(NOTES_INTERFACE),
+            Some(NOTE_CHANGED_SIGNAL),
+            Some(OBJECT_PATH),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |_, _, _, _, _, parameters| {
+                if let Some(change) = parameters.get::<(String, String)>() {
+                    changes_clone.borrow_mut().push(change);
+                }
+            },
+        );
+
+        let create = ("Shopping", "milk\n").to_variant();
+        assert!(call(&client, &service_name, "CreateNote", Some(create.clone())).is_ok());
+        assert!(call(&client, &service_name, "CreateNote", Some(create)).is_err());
+        assert!(wait_for_change(&changes, ("Shopping", NoteChange::Created.as_str())));
+
+        let listed = call(&client, &service_name, "ListNotes", None).expect("list notes");
+        assert_eq!(listed.get::<(Vec<String>,)>(), Some((vec!["Shopping".to_string()],)));
+
+        let escape = call(&client, &service_name, "GetNote", Some(("../secrets",).to_variant()));
+        assert!(escape.is_err_and(|e| e.message().contains("Invalid note title")));
+
+        assert!(call(&client, &service_name, "DeleteNote", Some(("Shopping",).to_variant())).is_ok());
+        assert!(call(&client, &service_name, "DeleteNote", Some(("Shopping",).to_variant())).is_err());
+        assert!(trash::is_trashed("Shopping"));
+        assert!(wait_for_change(&changes, ("Shopping", NoteChange::Deleted.as_str())));
+
+        test_bus.down();
+        let _ = std::fs::remove_dir_all(&notes_dir);
+    }
+}
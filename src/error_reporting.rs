@@ -0,0 +1,222 @@
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use chrono::{Local, NaiveDateTime};
+use gtk::{self, glib, prelude::*};
+
+use notes_core::error;
+
+// Amount of errors kept for the log viewer
+const MAX_LOGGED_ERRORS: usize = 100;
+const TOAST_SECONDS: u32 = 5;
+const CLOSE_TOAST_ICON: &str = "window-close-symbolic";
+
+// CSS Classes and names
+const TOAST: &str = "toast";
+const ENTRY_ERROR_CLASS: &str = "error";
+const VALIDATION_MESSAGE_CLASS: &str = "validation_message";
+
+
+/**
+An error reported to the user, kept for the log viewer.
+ */
+#[derive(Clone)]
+pub struct LoggedError {
+    pub time: NaiveDateTime,
+    /// Where the error happened, usually a function name.
+    pub context: String,
+    pub message: String,
+}
+
+
+/**
+A message shown at the bottom of a window for a few seconds.
+ */
+struct Toast {
+    revealer: gtk::Revealer,
+    label: gtk::Label,
+    hide_source: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+
+// Errors and toasts are only touched from the gtk main thread.
+thread_local! {
+    static ERROR_LOG: RefCell<VecDeque<LoggedError>> = const { RefCell::new(VecDeque::new()) };
+    static TOASTS: RefCell<Vec<Toast>> = const { RefCell::new(Vec::new()) };
+}
+
+
+/**
+Wrap a window's contents in an overlay that shows reported errors as toasts.
+# Parameters:
+* `child`: The window's contents.
+# Return:
+The overlay, to be set as the window's child.
+ */
+pub fn toast_overlay(child: &impl IsA<gtk::Widget>) -> gtk::Overlay {
+    let label = gtk::Label::builder()
+        .wrap(true)
+        .xalign(0.0)
+        .build();
+
+    let close_button = gtk::Button::from_icon_name(CLOSE_TOAST_ICON);
+    close_button.set_has_frame(false);
+
+    let toast_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(10)
+        .css_name(TOAST)
+        .build();
+    toast_box.append(&label);
+    toast_box.append(&close_button);
+
+    let revealer = gtk::Revealer::builder()
+        .transition_type(gtk::RevealerTransitionType::SlideUp)
+        .halign(gtk::Align::Center)
+        .valign(gtk::Align::End)
+        .child(&toast_box)
+        .build();
+
+    let overlay = gtk::Overlay::builder()
+        .child(child)
+        .build();
+    overlay.add_overlay(&revealer);
+
+    let revealer_clone = revealer.clone();
+    close_button.connect_clicked(move |_| {
+        revealer_clone.set_reveal_child(false);
+    });
+
+    TOASTS.with(|toasts| {
+        toasts.borrow_mut().push(Toast {
+            revealer,
+            label,
+            hide_source: Rc::new(RefCell::new(None)),
+        });
+    });
+
+    overlay
+}
+
+
+/**
+Report an error to the user: it's logged, kept for the log viewer and shown as a toast.
+# Parameters:
+* `context`: Where the error happened, usually the function name.
+* `message`: The message shown to the user.
+ */
+pub fn report_error(context: &str, message: &str) {
+    error!("{context}: {message}");
+
+    ERROR_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        if log.len() >= MAX_LOGGED_ERRORS {
+            log.pop_front();
+        }
+        log.push_back(LoggedError {
+            time: Local::now().naive_local(),
+            context: context.to_string(),
+            message: message.to_string(),
+        });
+    });
+
+    show_toast(message);
+}
+
+
+/**
+Get the errors reported so far, up to `MAX_LOGGED_ERRORS`.
+# Return:
+The errors, the most recent last.
+ */
+pub fn recent_errors() -> Vec<LoggedError> {
+    ERROR_LOG.with(|log| log.borrow().iter().cloned().collect())
+}
+
+
+/**
+Show a message on the toast of the active window, or of any window if none is active.
+ */
+fn show_toast(message: &str) {
+    TOASTS.with(|toasts| {
+        let mut toasts = toasts.borrow_mut();
+
+        // Toasts of closed windows are no longer in a widget tree.
+        toasts.retain(|toast| toast.revealer.root().is_some());
+
+        let is_active = |toast: &&Toast| {
+            toast
+                .revealer
+                .root()
+                .and_then(|root| root.downcast::<gtk::Window>().ok())
+                .is_some_and(|window| window.is_active())
+        };
+        let toast = match toasts.iter().find(is_active).or(toasts.last()) {
+            Some(toast) => toast,
+            None => return,
+        };
+
+        toast.label.set_text(message);
+        toast.revealer.set_reveal_child(true);
+
+        // A new message restarts the countdown of the previous one.
+        if let Some(source) = toast.hide_source.borrow_mut().take() {
+            source.remove();
+        }
+
+        let revealer_clone = toast.revealer.clone();
+        let hide_source_clone = Rc::clone(&toast.hide_source);
+        let source = glib::timeout_add_seconds_local_once(TOAST_SECONDS, move || {
+            hide_source_clone.borrow_mut().take();
+            revealer_clone.set_reveal_child(false);
+        });
+        *toast.hide_source.borrow_mut() = Some(source);
+    });
+}
+
+
+/**
+Create a hidden label placed below an entry to explain why its value is not valid.
+The error state is cleared as soon as the entry is edited.
+# Parameters:
+* `entry`: The entry the messages are about.
+# Return:
+The label, to be added to the dialog after the entry.
+ */
+pub fn validation_label(entry: &gtk::Entry) -> gtk::Label {
+    let label = gtk::Label::builder()
+        .xalign(0.0)
+        .wrap(true)
+        .visible(false)
+        .css_classes([VALIDATION_MESSAGE_CLASS])
+        .build();
+
+    let label_clone = label.clone();
+    entry.connect_changed(move |entry| {
+        clear_validation_error(entry, &label_clone);
+    });
+
+    label
+}
+
+
+/**
+Mark a widget's value as not valid and explain why in a validation label.
+# Parameters:
+* `widget`: The widget holding the value that is not valid, usually an entry.
+* `label`: The label from `validation_label`.
+* `message`: The explanation shown to the user.
+ */
+pub fn show_validation_error(widget: &impl IsA<gtk::Widget>, label: &gtk::Label, message: &str) {
+    widget.style_context().add_class(ENTRY_ERROR_CLASS);
+    label.set_text(message);
+    label.set_visible(true);
+    widget.grab_focus();
+}
+
+
+/**
+Remove the error state set by `show_validation_error`.
+ */
+pub fn clear_validation_error(widget: &impl IsA<gtk::Widget>, label: &gtk::Label) {
+    widget.style_context().remove_class(ENTRY_ERROR_CLASS);
+    label.set_visible(false);
+}
@@ -0,0 +1,105 @@
+use std::{path::Path, process::Command};
+use gtk::{gio, glib, prelude::*};
+
+use notes_core::editor::{self, EditorCommand};
+use notes_core::{error, warn, debug};
+
+// Content type used to find the desktop's default text editor
+const TEXT_CONTENT_TYPE: &str = "text/plain";
+
+
+/**
+The editor notes are opened in.
+ */
+pub enum Editor {
+    /// A command from the settings, `$VISUAL` or `$EDITOR`.
+    Command(EditorCommand),
+    /// The desktop's default application for text files.
+    Desktop(gio::AppInfo),
+}
+
+
+impl Editor {
+    /**
+    Get a short name of the editor to show to the user.
+     */
+    pub fn name(&self) -> String {
+        match self {
+            Editor::Command(command) => command.name(),
+            Editor::Desktop(app_info) => app_info.display_name().to_string(),
+        }
+    }
+}
+
+
+/**
+Find the editor to open notes in: the configured command first and then the desktop default.
+# Return:
+The editor or `None` if there is none at all.
+ */
+pub fn resolve_editor() -> Option<Editor> {
+    if let Some(command) = editor::configured_editor() {
+        return Some(Editor::Command(command));
+    }
+
+    gio::AppInfo::default_for_type(TEXT_CONTENT_TYPE, false).map(Editor::Desktop)
+}
+
+
+/**
+Open a file in the user's editor.
+# Parameters:
+* `file_path`: The file to edit.
+* `on_exit`: Called once the editor process exits. Desktop applications can't be waited on, and
+  terminals often hand the editor over to a running instance and exit right away, so for both
+  it's never called and changes show up through the notes directory monitor.
+# Return:
+Boolean stating if the editor was launched.
+ */
+pub fn open_in_editor(file_path: &Path, on_exit: impl FnOnce() + 'static) -> bool {
+    let editor = match resolve_editor() {
+        Some(editor) => editor,
+        None => {
+            warn!("No editor found, set one in the settings or in $VISUAL or $EDITOR");
+            return false;
+        }
+    };
+
+    let command = match editor {
+        Editor::Command(command) => command,
+        Editor::Desktop(app_info) => {
+            if let Err(e) = app_info.launch(&[gio::File::for_path(file_path)], None::<&gio::AppLaunchContext>) {
+                error!("Failed to launch {} for {file_path:?}: {e}", app_info.display_name());
+                return false;
+            }
+            return true;
+        }
+    };
+
+    let terminal = command.terminal;
+    let command_line = command.command_line(file_path);
+    let (program, args) = match command_line.split_first() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    debug!("Opening {file_path:?} with {command_line:?}");
+    let child = match Command::new(program).args(args).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            error!("Failed to run {program}: {e}");
+            return false;
+        }
+    };
+
+    // The main loop reaps the process, so the child handle is not waited on.
+    // The terminal exiting says nothing about the editor inside it, so only the editor itself is waited for.
+    let mut on_exit = if terminal { None } else { Some(on_exit) };
+    glib::child_watch_add_local(glib::Pid(child.id() as i32), move |_, _| {
+        if let Some(on_exit) = on_exit.take() {
+            on_exit();
+        }
+    });
+
+    true
+}
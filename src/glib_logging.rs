@@ -0,0 +1,60 @@
+use std::env;
+use gtk::glib::{self, gstr, LogField};
+
+use notes_core::logging::{self, Level, Record};
+
+// Domain of the app's messages, as shown by GLib and in the journal
+const LOG_DOMAIN: &str = "rust_gtk_notes_app";
+// Variable GLib reads to decide which domains show info and debug messages
+const MESSAGES_DEBUG_VARIABLE: &str = "G_MESSAGES_DEBUG";
+
+
+/**
+Set up logging for the app: messages go through GLib's structured logging, which writes them to
+the journal or to stderr, and are also written to the rotating log file.
+# Parameters:
+* `verbose`: Whether debug messages are logged and info and debug messages are shown.
+ */
+pub fn init(verbose: bool) {
+    let max_level = if verbose { Level::Debug } else { Level::Info };
+    logging::init(max_level, true);
+    logging::set_sink(write_to_glib);
+
+    // GLib hides info and debug messages unless their domain is listed.
+    if verbose {
+        let domains = match env::var(MESSAGES_DEBUG_VARIABLE) {
+            Ok(domains) if !domains.is_empty() => format!("{domains} {LOG_DOMAIN}"),
+            _ => LOG_DOMAIN.to_string(),
+        };
+        env::set_var(MESSAGES_DEBUG_VARIABLE, domains);
+    }
+}
+
+
+/**
+Pass a message to GLib with the fields the journal knows about.
+ */
+fn write_to_glib(record: &Record) {
+    // GLib's error level aborts the program, so errors are logged as critical.
+    let (glib_level, priority) = match record.level {
+        Level::Error => (glib::LogLevel::Critical, "3"),
+        Level::Warning => (glib::LogLevel::Warning, "4"),
+        Level::Info => (glib::LogLevel::Info, "6"),
+        Level::Debug => (glib::LogLevel::Debug, "7"),
+    };
+
+    let message = format!("{}: {}", record.target, record.message);
+    let line = record.line.to_string();
+
+    glib::log_structured_array(
+        glib_level,
+        &[
+            LogField::new(gstr!("PRIORITY"), priority.as_bytes()),
+            LogField::new(gstr!("GLIB_DOMAIN"), LOG_DOMAIN.as_bytes()),
+            LogField::new(gstr!("MESSAGE"), message.as_bytes()),
+            LogField::new(gstr!("CODE_FILE"), record.file.as_bytes()),
+            LogField::new(gstr!("CODE_LINE"), line.as_bytes()),
+            LogField::new(gstr!("RUST_MODULE"), record.target.as_bytes()),
+        ],
+    );
+}
@@ -1,35 +1,114 @@
-use std::{rc::Rc, process::Command, path::PathBuf};
-use gtk::{self, prelude::*};
-
-use crate::notes::{self, NOTES_PATH};
+use std::{cell::RefCell, rc::Rc, path::PathBuf};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, Timelike};
+use gtk::{self, gdk, gio, glib, prelude::*};
+
+use notes_core::notes;
+use notes_core::templates;
+use notes_core::daily_notes;
+use notes_core::checklists;
+use notes_core::reminders;
+use notes_core::export;
+use notes_core::import::{self, CollisionPolicy, ImportSource};
+use notes_core::quick_capture;
+use notes_core::search;
+use notes_core::trash;
+use notes_core::preferences::Preferences;
+use notes_core::{error, info};
+use crate::note_view::NoteView;
+use crate::pdf_export;
+use crate::external_editor;
+use crate::shortcuts;
+use crate::note_windows;
+use crate::note_tabs;
+use crate::error_reporting::{self, report_error, show_validation_error, clear_validation_error};
 
 // Dialog message defaults
 const NEW_NOTE_DIAG: &str = "New Note";
 const DELETE_NOTE_DIAG: &str = "Delete Note";
+const TRASH_DIAG: &str = "Trash";
+const EMPTY_TRASH: &str = "The trash is empty";
 const EDIT_NOTE_DIAG: &str = "Edit note";
+const OPEN_TASKS_DIAG: &str = "Open tasks";
+const NO_OPEN_TASKS: &str = "There are no open tasks";
+const REMINDER_DIAG: &str = "Reminder";
+const WHOLE_NOTE_OPTION: &str = "(Whole note)";
+const ATTACH_FILES_DIAG: &str = "Attach files";
+const EXPORT_NOTES_DIAG: &str = "Export notes";
+const EXPORT_FILE_DIAG: &str = "Export to file";
+const EXPORT_FILE_NAME: &str = "notes";
+const IMPORT_NOTES_DIAG: &str = "Import notes";
+const IMPORT_SOURCE_DIAG: &str = "Choose what to import";
+const IMPORT_REPORT_DIAG: &str = "Import report";
+const NO_IMPORT_PATH: &str = "Nothing chosen to import";
+const QUICK_CAPTURE_DIAG: &str = "Quick capture";
+const CAPTURE_PLACEHOLDER: &str = "What's on your mind?";
+const QUICK_CAPTURE_WIDTH: i32 = 400;
+const RENAME_NOTE_DIAG: &str = "Rename note";
+const SEARCH_PLACEHOLDER: &str = "Search titles and contents";
+const NO_SEARCH_RESULTS: &str = "No notes match the search";
+const ABOUT_COMMENTS: &str = "Plain text notes with tasks, reminders and attachments";
+const SEARCH_RESULTS_HEIGHT: i32 = 250;
+const ERROR_LOG_DIAG: &str = "Error log";
+const NO_ERRORS: &str = "No errors so far";
+const ERROR_TIME_FORMAT: &str = "%H:%M:%S";
+/// Modified date of the notes in the note list, only the time is shown for today's changes.
+const NOTE_DATE_FORMAT: &str = "%b %-d, %Y";
+const NOTE_TIME_FORMAT: &str = "%H:%M";
+const CONFIRM_TRASH_NOTE: &str = "Move the note to the trash?";
+const CONFIRM_DELETE_NOTE: &str = "Delete the note for good? This can't be undone.";
+const CONFIRM_EMPTY_TRASH: &str = "Delete every note in the trash for good? This can't be undone.";
+
+// Export formats, with the extension used for each one
+const EXPORT_FORMATS: [&str; 3] = ["HTML", "PDF", "Markdown bundle (zip)"];
+const EXPORT_EXTENSIONS: [&str; 3] = ["html", "pdf", "zip"];
+const NO_TEMPLATE_OPTION: &str = "(No template)";
 
 // Error messages
-const CANT_ADD_NOTES: &str = "Can't add more notes";
 const NO_NOTES_AVAILABLE: &str = "There are no notes available";
-const NOTE_CANT_BE_EMPTY: &str = "Note data can't be empty";
+const TITLE_CANT_BE_EMPTY: &str = "Write the note's title";
+const INVALID_TITLE: &str = "Titles can't start with a dot or contain / or \\";
+const CONTENTS_CANT_BE_EMPTY: &str = "Write the note's contents";
+const NOTE_ALREADY_EXISTS: &str = "There is already a note with that title";
+const FAILED_TO_CREATE: &str = "Could not save the note";
+const FAILED_TO_DELETE: &str = "Could not move the note to the trash";
+const FAILED_TO_RESTORE: &str = "Could not restore the note, there may already be one with that title";
+const FAILED_TO_EMPTY_TRASH: &str = "Could not delete every note in the trash";
+const FAILED_TO_RENAME: &str = "Could not rename the note";
+const FAILED_TO_SET_REMINDER: &str = "Could not save the reminder";
+const FAILED_TO_CLEAR_REMINDER: &str = "Could not remove the reminder";
+const FAILED_TO_EXPORT: &str = "Could not export the notes";
+const CAPTURE_CANT_BE_EMPTY: &str = "Write something to capture";
+const FAILED_TO_CAPTURE: &str = "Could not capture the text";
 const FAILED_TO_EDIT: &str = "Could not launch editor for note";
-const NOTE_NOT_FOUND: &str = "Note could not be found";
+pub const NOTE_NOT_FOUND: &str = "Note could not be found";
+const NO_NOTE_SELECTED: &str = "Select a note first";
+const INVALID_DATE: &str = "Invalid reminder date";
+const NO_NOTES_SELECTED: &str = "Select at least one note to export";
+pub const NOTE_DELETED: &str = "This note was deleted";
 
 // CSS Classes
 const DIAG_TITLE_CLASS: &str = "diag_title";
 const DIAG_CONTENTS_CLASS: &str = "diag_contents";
 const DIAG_BUTTON_CLASS: &str = "diag_button";
 pub const NOTE_BUTTON_CLASS: &str = "note_btn";
-pub const INTERACT_BUTTON_CLASS: &str = "interact_btn";
+const TASK_BADGE_CLASS: &str = "task_badge";
+const NOTE_TITLE_CLASS: &str = "note_title";
+const NOTE_SNIPPET_CLASS: &str = "note_snippet";
+const NOTE_DATE_CLASS: &str = "note_date";
+const DESTRUCTIVE_ACTION_CLASS: &str = "destructive-action";
 
 // CSS Names
 const DIALOG_BOX: &str = "diag_box";
 pub const MAIN_CONTAINER: &str = "main_container";
 pub const BUTTON_BOX: &str = "button_box";
 pub const CONTENT_BOX: &str = "content_box";
+pub const SIDE_PANEL: &str = "side_panel";
+pub const SIDEBAR: &str = "sidebar";
 
-// Commands
-pub const TEXT_EDITOR: &str = "gnome-text-editor";
+thread_local! {
+    /// Label of the note menus' edit item, naming the editor so it's only looked up once for every menu.
+    static EDIT_LABEL: RefCell<Option<String>> = const { RefCell::new(None) };
+}
 
 
 /**
@@ -37,20 +116,14 @@ Click event handler for "add note" button.
 
 # Parameters:
 * `buttons_box_ref`: A reference to the buttons box.
-* `text_box_ref`: A reference to the box that displays note's contents.
+* `note_view_ref`: A reference to the view that displays note's contents.
 * `app_ref`: A reference to the gtk application.
  */
 pub fn add_button_click_event(
     buttons_box_ref: &Rc<gtk::Box>,
-    text_box_ref: &Rc<gtk::Label>,
+    note_view_ref: &Rc<NoteView>,
     app_ref: &Rc<gtk::Application>
 ) {
-    let hchilds = get_hbox_childs(buttons_box_ref);
-    if hchilds.len() >= 5 {
-        eprintln!("add_button_click_event: {}", CANT_ADD_NOTES);
-        return;
-    }
-
     // Initialize the main dialog window
     let dialog = create_dialog(app_ref, NEW_NOTE_DIAG);
 
@@ -72,6 +145,7 @@ pub fn add_button_click_event(
         .placeholder_text("Note title")
         .css_classes([DIAG_TITLE_CLASS])
         .build();
+    let title_validation = error_reporting::validation_label(&title_entry);
 
     // Note contents label and Text View
     let content_label = gtk::Label::builder()
@@ -88,20 +162,47 @@ pub fn add_button_click_event(
         .css_classes([DIAG_CONTENTS_CLASS])
         .build();
 
+    // Template label and dropdown, the first option leaves the note empty.
+    let template_label = gtk::Label::builder()
+        .label("Template")
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+
+    let template_names = templates::load_templates();
+    let mut template_options = vec![NO_TEMPLATE_OPTION];
+    template_options.extend(template_names.iter().map(String::as_str));
+
+    let template_dropdown = gtk::DropDown::from_strings(&template_options);
+    template_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
     let create_button = gtk::Button::with_label("Create");
     create_button.style_context().add_class(DIAG_BUTTON_CLASS);
     
-    grid.attach(&title_label, 0, 0, 1, 1);
-    grid.attach(&title_entry, 1, 0, 1, 1);
-    grid.attach(&content_label, 0, 1, 1, 1);
-    grid.attach(&content_text_view, 1, 1, 1, 3);
+    grid.attach(&template_label, 0, 0, 1, 1);
+    grid.attach(&template_dropdown, 1, 0, 1, 1);
+    grid.attach(&title_label, 0, 1, 1, 1);
+    grid.attach(&title_entry, 1, 1, 1, 1);
+    grid.attach(&title_validation, 1, 2, 1, 1);
+    grid.attach(&content_label, 0, 3, 1, 1);
+    grid.attach(&content_text_view, 1, 3, 1, 3);
 
     content_area.append(&grid);
     content_area.append(&create_button);
 
     // Create clones to use inside Fn enclosure
-    let bbox_clone = Rc::clone(&buttons_box_ref);
-    let text_box_clone = Rc::clone(&text_box_ref);
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    let title_entry_clone = title_entry.clone();
+    let text_view_clone = content_text_view.clone();
+
+    template_dropdown.connect_selected_notify(move |dropdown| {
+        // Position 0 is the "no template" option, so template names are shifted by one.
+        let template_name = (dropdown.selected() as usize)
+            .checked_sub(1)
+            .and_then(|index| template_names.get(index));
+
+        apply_template(template_name, &title_entry_clone, &text_view_clone);
+    });
 
     dialog.show();
 
@@ -109,7 +210,8 @@ pub fn add_button_click_event(
         create_note_button_click_event(
             &title_entry,
             &content_text_view,
-            &text_box_clone,
+            &title_validation,
+            &note_view_clone,
             &bbox_clone,
             &dialog
         );
@@ -118,15 +220,20 @@ pub fn add_button_click_event(
 
 
 /**
-Click event handler for the "remove note" button.
+Event handler for the "delete note" action, moving the chosen note to the trash.
 # Parameters:
 * `buttons_box_ref`: A reference to the buttons horizontal box.
+* `note_view_ref`: A reference to the view that displays notes contents.
 * `app_ref`: A reference to the gtk application
  */
-pub fn rm_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk::Application>){
+pub fn rm_button_click_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
     let hchilds = get_hbox_childs(buttons_box_ref);
     if hchilds.is_empty() {
-        eprintln!("rm_button_click_event: {}", NO_NOTES_AVAILABLE);
+        report_error("rm_button_click_event", NO_NOTES_AVAILABLE);
         return;
     }
 
@@ -142,35 +249,35 @@ pub fn rm_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk::A
         .css_classes([DIAG_TITLE_CLASS])
         .build();
 
-    let delete_button: gtk::Button = gtk::Button::with_label("Remove");
+    let input_validation = error_reporting::validation_label(&input_box);
+
+    let delete_button: gtk::Button = gtk::Button::with_label("Move to trash");
     delete_button.style_context().add_class(DIAG_BUTTON_CLASS);
 
     content_area.append(&input_box);
+    content_area.append(&input_validation);
     content_area.append(&delete_button);
 
     // Create clones to use inside Fn enclosure
-    let bbox_clone = Rc::clone(&buttons_box_ref);
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
 
     dialog.show();
 
     delete_button.connect_clicked(move |_| {
         let note_title = input_box.text().trim().to_string();
         if note_title.is_empty() {
-            eprintln!("rm_button_click_event: {}", NOTE_CANT_BE_EMPTY);
+            show_validation_error(&input_box, &input_validation, TITLE_CANT_BE_EMPTY);
             return;
         }
 
         for child in hchilds.clone() {
-            // Downcast a widget to a button to access it's ;abe;
-            let btn = child.downcast::<gtk::Button>().unwrap();
-            if note_title != btn.label().expect("Button has no label").trim().to_string() {
+            if note_button_title(&child).as_deref() != Some(note_title.as_str()) {
                 continue;
             }
 
-            // Remove the note from the buttons box and from storage.
-            bbox_clone.remove(&btn);
-            let success = notes::delete_a_note(&note_title);
-            if success == false {
+            // The note's button goes away along with it.
+            if !trash_note(&note_title, &bbox_clone, &note_view_clone) {
                 return;
             }
 
@@ -179,7 +286,7 @@ pub fn rm_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk::A
             return;
         }
 
-        eprintln!("rm_button_click_event: {NOTE_NOT_FOUND} \"{note_title}\""); 
+        show_validation_error(&input_box, &input_validation, NOTE_NOT_FOUND);
     });
 }
 
@@ -189,14 +296,16 @@ pub fn rm_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk::A
 # Parameters:
 * `title_entry_ref`: A reference to an entry for the created note's title input.
 * `content_text_view_ref`: A reference to a text view with the note's content.
-* `text_box_ref`: A reference to the label that displays notes contents.
+* `validation_ref`: A reference to the label explaining why the input is not valid.
+* `note_view_ref`: A reference to the view that displays notes contents.
 * `buttons_box_ref`: A reference to the buttons box.
 * `dialog_ref`: A reference to the initialized dialog.
  */
 fn create_note_button_click_event(
     title_entry_ref: &gtk::Entry,
     content_text_view_ref: &gtk::TextView,
-    text_box_ref: &Rc<gtk::Label>,
+    validation_ref: &gtk::Label,
+    note_view_ref: &Rc<NoteView>,
     buttons_box_ref: &Rc<gtk::Box>,
     dialog_ref: &gtk::Dialog
 ) {
@@ -207,28 +316,34 @@ fn create_note_button_click_event(
     let end_iter = buffer.end_iter();
     let contents = buffer.text(&start_iter, &end_iter, false).trim().to_string();
 
-    if title.is_empty() || contents.is_empty() {
-        eprintln!("create_note_button_click_event: {}", NOTE_CANT_BE_EMPTY);
+    clear_validation_error(content_text_view_ref, validation_ref);
+    if title.is_empty() {
+        show_validation_error(title_entry_ref, validation_ref, TITLE_CANT_BE_EMPTY);
+        return;
+    }
+    if !notes::is_valid_title(&title) {
+        show_validation_error(title_entry_ref, validation_ref, INVALID_TITLE);
+        return;
+    }
+    if notes::note_exists(&title) {
+        show_validation_error(title_entry_ref, validation_ref, NOTE_ALREADY_EXISTS);
+        return;
+    }
+    if contents.is_empty() {
+        show_validation_error(content_text_view_ref, validation_ref, CONTENTS_CANT_BE_EMPTY);
         return;
     }
 
+    // Templates may leave the title placeholder unfilled if it was chosen before typing a title.
+    let contents = contents.replace(templates::TITLE_PLACEHOLDER, &title);
+
     let success = notes::create_a_note(&title, &contents);
     if !success {
+        report_error("create_note_button_click_event", FAILED_TO_CREATE);
         return;
     }
 
-    // Create a new note button
-    let new_button = gtk::Button::with_label(&title);
-    new_button.style_context().add_class(NOTE_BUTTON_CLASS);
-
-    // Create another clone for another Fn enclosure
-    let tb_clone = Rc::clone(&text_box_ref);
-
-    new_button.connect_clicked(move |_| {
-        notes::display_file_contents(&title, &tb_clone);
-    });
-
-    buttons_box_ref.append(&new_button);
+    let new_button = append_note_button(&title, buttons_box_ref, note_view_ref);
     new_button.show();
     dialog_ref.close();
     dialog_ref.destroy();
@@ -236,16 +351,59 @@ fn create_note_button_click_event(
 
 
 /**
-Event handler for the edit button event
+Fill the new note text view with a rendered template.
+# Parameters:
+* `template_name`: The name of the selected template or `None` to clear the text view.
+* `title_entry_ref`: A reference to the entry with the note's title.
+* `content_text_view_ref`: A reference to the text view with the note's content.
+ */
+fn apply_template(
+    template_name: Option<&String>,
+    title_entry_ref: &gtk::Entry,
+    content_text_view_ref: &gtk::TextView
+) {
+    let buffer = content_text_view_ref.buffer();
+    let template_name = match template_name {
+        Some(name) => name,
+        None => {
+            buffer.set_text("");
+            return;
+        }
+    };
+
+    let title = title_entry_ref.text().trim().to_string();
+    let rendered = match templates::render_template(template_name, &title) {
+        Some(rendered) => rendered,
+        None => return,
+    };
+
+    buffer.set_text(&rendered.contents);
+
+    // Move the cursor to where the template asked for it.
+    if let Some(offset) = rendered.cursor_offset {
+        buffer.place_cursor(&buffer.iter_at_offset(offset));
+        content_text_view_ref.grab_focus();
+    }
+}
+
+
+/**
+Event handler for the edit button event.
+Opens the note in the user's editor and reloads it once the editor exits.
 
 # Parameters:
 * `button_box_ref`: A reference to the button box.
+* `note_view_ref`: A reference to the view that displays note's contents.
 * `app_ref`: A reference to the gtk application.
  */
-pub fn edit_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk::Application>) {
+pub fn edit_button_click_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
     let hchilds = get_hbox_childs(buttons_box_ref);
     if hchilds.is_empty() {
-        eprintln!("edit_button_click_event: {}", NO_NOTES_AVAILABLE);
+        report_error("edit_button_click_event", NO_NOTES_AVAILABLE);
         return;
     }
 
@@ -260,38 +418,33 @@ pub fn edit_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk:
         .css_classes([DIAG_TITLE_CLASS])
         .build();
 
+    let input_validation = error_reporting::validation_label(&input_box);
+
     let edit_button: gtk::Button = gtk::Button::with_label("Edit");
     edit_button.style_context().add_class(DIAG_BUTTON_CLASS);
 
     content_area.append(&input_box);
+    content_area.append(&input_validation);
     content_area.append(&edit_button);
 
+    // Create clones to use inside Fn enclosure
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+
     dialog.show();
 
     edit_button.connect_clicked(move |_| {
         let note_title = input_box.text().trim().to_string();
         if note_title.is_empty() {
-            eprintln!("edit_button_click_event: {}", NOTE_CANT_BE_EMPTY);
+            show_validation_error(&input_box, &input_validation, TITLE_CANT_BE_EMPTY);
             return;
         }
 
         for child in hchilds.clone() {
-            // Downcast a widget to a button to access it's label
-            let btn = child.downcast::<gtk::Button>().unwrap();
-            if note_title != btn.label().expect("Button has no label").trim().to_string() {
+            if note_button_title(&child).as_deref() != Some(note_title.as_str()) {
                 continue;
             }
-            let note_path_buff = PathBuf::from(NOTES_PATH).join(format!("{note_title}.txt"));
-            let note_path = match note_path_buff.to_str() {
-                Some(path) => path,
-                None => {
-                    eprintln!("Failed to get path for note {note_title}");
-                    return;
-                }
-            };
-
-            if let Err(e) = Command::new(TEXT_EDITOR).arg(note_path).spawn() {
-                eprintln!("edit_button_click_event: {}: {}", FAILED_TO_EDIT, e);
+            if !edit_note(&note_title, &bbox_clone, &note_view_clone) {
                 return;
             }
 
@@ -300,48 +453,1727 @@ pub fn edit_button_click_event(buttons_box_ref: &Rc<gtk::Box>, app_ref: &Rc<gtk:
             return;
         }
 
-        eprintln!("edit_button_click_event: {NOTE_NOT_FOUND} \"{note_title}\"");
+        show_validation_error(&input_box, &input_validation, NOTE_NOT_FOUND);
     });
     
 }
 
 
 /**
-Get all childs from a gtk box.
+Open a note in the external editor, reading it again once the editor is closed.
+Editors that can't be waited on, like terminal ones, rely on the notes directory monitor instead.
 # Parameters:
-* `hbox`: A reference to a gtk box to get their childs.
+* `note_title`: The title of the note to edit.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
 # Return:
-A vector of gtk widgets. 
+Boolean stating if the editor was launched.
  */
-fn get_hbox_childs(hbox: &gtk::Box) -> Vec<gtk::Widget> {
-    let mut children: Vec<gtk::Widget> = Vec::new();
-    let mut sibling = hbox.first_child();
+pub fn edit_note(note_title: &str, buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) -> bool {
+    // Create clones to use inside Fn enclosure
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    let title_clone = note_title.to_string();
 
-    while let Some(child) = sibling {
-        children.push(child.clone());
-        sibling = child.next_sibling();
+    let launched = external_editor::open_in_editor(&notes::note_path(note_title), move || {
+        sync_note(&title_clone, &bbox_clone, &note_view_clone);
+    });
+    if !launched {
+        report_error("edit_note", &format!("{FAILED_TO_EDIT} \"{note_title}\""));
     }
 
-    children
+    launched
 }
 
 
 /**
-Create a gtk dialog.
+Move a note to the trash and remove its button.
+# Parameters:
+* `note_title`: The title of the note to trash.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+# Return:
+Boolean stating if the note was moved to the trash.
+ */
+pub fn trash_note(note_title: &str, buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) -> bool {
+    if !trash::trash_note(note_title) {
+        report_error("trash_note", &format!("{FAILED_TO_DELETE} \"{note_title}\""));
+        return false;
+    }
 
-# Parameters
-`app_ref`: A reference to a pointer of the Application
-`diag_msg`: The message to be displayed on the dialog.
+    sync_note(note_title, buttons_box_ref, note_view_ref);
+    true
+}
+
+
+/**
+Event handler for a note's "move to trash" menu item, asking first if the preferences say so.
+# Parameters:
+* `note_title`: The title of the note to trash.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn trash_note_event(
+    note_title: &str,
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
+    let window = match parent_window(app_ref) {
+        Some(window) => window,
+        None => {
+            trash_note(note_title, buttons_box_ref, note_view_ref);
+            return;
+        }
+    };
+
+    // Create clones to use inside Fn enclosure
+    let title = note_title.to_string();
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    confirm_deletion(&window, CONFIRM_TRASH_NOTE, "Move to trash", move || {
+        trash_note(&title, &bbox_clone, &note_view_clone);
+    });
+}
+
+
+/**
+Ask before deleting notes, or go ahead right away if confirmations are turned off in the preferences.
+# Parameters:
+* `parent`: The window the question is shown over.
+* `question`: What is about to happen.
+* `confirm_label`: The label of the button that goes ahead.
+* `on_confirm`: Called once the user agreed.
+ */
+fn confirm_deletion(parent: &gtk::Window, question: &str, confirm_label: &str, on_confirm: impl Fn() + 'static) {
+    if !Preferences::load().confirm_delete {
+        on_confirm();
+        return;
+    }
+
+    let dialog = gtk::MessageDialog::new(
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        gtk::MessageType::Question,
+        gtk::ButtonsType::None,
+        question
+    );
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    let confirm_button = dialog.add_button(confirm_label, gtk::ResponseType::Accept);
+    confirm_button.style_context().add_class(DESTRUCTIVE_ACTION_CLASS);
+
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            on_confirm();
+        }
+        dialog.destroy();
+    });
+
+    dialog.show();
+}
+
+
+/**
+Event handler for the "rename note" actions.
+Renames a note, keeping its button in place.
+
+# Parameters:
+* `note_title`: The note to rename, or `None` for the displayed one.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn rename_note_event(
+    note_title: Option<&str>,
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
+    let dialog = create_dialog(app_ref, RENAME_NOTE_DIAG);
+    let content_area = dialog.content_area();
+
+    let title_entry = gtk::Entry::builder()
+        .placeholder_text("Note title")
+        .text(note_title.map(str::to_string).or_else(|| note_view_ref.current_note()).unwrap_or_default())
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+    let title_validation = error_reporting::validation_label(&title_entry);
+
+    let new_title_entry = gtk::Entry::builder()
+        .placeholder_text("New title")
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+    let new_title_validation = error_reporting::validation_label(&new_title_entry);
+
+    let rename_button = gtk::Button::with_label("Rename");
+    rename_button.style_context().add_class(DIAG_BUTTON_CLASS);
+
+    content_area.append(&title_entry);
+    content_area.append(&title_validation);
+    content_area.append(&new_title_entry);
+    content_area.append(&new_title_validation);
+    content_area.append(&rename_button);
+
+    // Pressing enter on the new title renames too.
+    let rename_button_clone = rename_button.clone();
+    new_title_entry.connect_activate(move |_| {
+        rename_button_clone.emit_clicked();
+    });
+
+    // Create clones to use inside Fn enclosure
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    let dialog_clone = dialog.clone();
+
+    dialog.show();
+    new_title_entry.grab_focus();
+
+    rename_button.connect_clicked(move |_| {
+        let note_title = title_entry.text().trim().to_string();
+        let new_title = new_title_entry.text().trim().to_string();
+        if note_title.is_empty() {
+            show_validation_error(&title_entry, &title_validation, TITLE_CANT_BE_EMPTY);
+            return;
+        }
+        if !notes::note_exists(&note_title) {
+            show_validation_error(&title_entry, &title_validation, NOTE_NOT_FOUND);
+            return;
+        }
+        if new_title.is_empty() {
+            show_validation_error(&new_title_entry, &new_title_validation, TITLE_CANT_BE_EMPTY);
+            return;
+        }
+        if !notes::is_valid_title(&new_title) {
+            show_validation_error(&new_title_entry, &new_title_validation, INVALID_TITLE);
+            return;
+        }
+        if notes::note_exists(&new_title) {
+            show_validation_error(&new_title_entry, &new_title_validation, NOTE_ALREADY_EXISTS);
+            return;
+        }
+
+        if !notes::rename_note(&note_title, &new_title) {
+            report_error("rename_note_event", FAILED_TO_RENAME);
+            return;
+        }
+
+        // The renamed note takes the place of the old button.
+        let old_btn = get_hbox_childs(&bbox_clone)
+            .into_iter()
+            .find(|child| note_button_title(child).as_deref() == Some(note_title.as_str()));
+        if let Some(old_btn) = old_btn {
+            let new_btn = append_note_button(&new_title, &bbox_clone, &note_view_clone);
+            bbox_clone.reorder_child_after(&new_btn, Some(&old_btn));
+            bbox_clone.remove(&old_btn);
+        }
+
+        // The tabs are renamed first, so showing the new title doesn't open another tab.
+        note_tabs::note_renamed(&note_title, &new_title);
+        if note_view_clone.current_note().as_deref() == Some(note_title.as_str()) {
+            note_view_clone.show_note(&new_title);
+        }
+        note_windows::note_renamed(&note_title, &new_title);
+
+        dialog_clone.close();
+        dialog_clone.destroy();
+    });
+}
+
+
+/**
+Build the search bar shown under the header bar.
+Lists the notes matching the typed query, choosing one displays it and closes the search.
 
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
 # Return:
-The gtk dialog widget.
+The search bar, hidden until its search mode is enabled.
  */
-fn create_dialog(app_ref: &Rc<gtk::Application>, diag_msg: &str) -> gtk::Dialog {
-    gtk::Dialog::builder()
-        .title(diag_msg)
-        .transient_for(&app_ref.active_window().expect("No active window found"))
-        .destroy_with_parent(true)
+pub fn create_search_bar(buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) -> gtk::SearchBar {
+    let search_entry = gtk::SearchEntry::builder()
+        .placeholder_text(SEARCH_PLACEHOLDER)
+        .hexpand(true)
+        .build();
+
+    let results_list = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .propagate_natural_height(true)
+        .max_content_height(SEARCH_RESULTS_HEIGHT)
+        .child(&results_list)
+        .build();
+
+    let search_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+    search_box.append(&search_entry);
+    search_box.append(&scrolled_window);
+
+    let search_bar = gtk::SearchBar::builder()
+        .child(&search_box)
+        .show_close_button(true)
+        .build();
+    search_bar.connect_entry(&search_entry);
+
+    // Create clones to use inside Fn enclosure
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    let search_bar_clone = search_bar.clone();
+
+    search_entry.connect_search_changed(move |search_entry| {
+        for child in get_hbox_childs(&results_list) {
+            results_list.remove(&child);
+        }
+
+        let terms = search::query_terms(&search_entry.text());
+        if terms.is_empty() {
+            return;
+        }
+
+        let matches = search::search_notes(&terms);
+        if matches.is_empty() {
+            results_list.append(&gtk::Label::new(Some(NO_SEARCH_RESULTS)));
+        }
+
+        for found in matches {
+            let result_content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .build();
+            result_content.append(&gtk::Label::builder()
+                .label(&found.note_title)
+                .xalign(0.0)
+                .css_classes([DIAG_TITLE_CLASS])
+                .build());
+            if let Some(line) = found.lines.first() {
+                result_content.append(&gtk::Label::builder()
+                    .label(line)
+                    .xalign(0.0)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .build());
+            }
+
+            let result_btn = gtk::Button::builder()
+                .child(&result_content)
+                .has_frame(false)
+                .build();
+
+            let bbox_clone = Rc::clone(&bbox_clone);
+            let note_view_clone = Rc::clone(&note_view_clone);
+            let search_bar_clone = search_bar_clone.clone();
+            result_btn.connect_clicked(move |_| {
+                if select_note(&found.note_title, &bbox_clone, &note_view_clone) {
+                    search_bar_clone.set_search_mode(false);
+                }
+            });
+
+            results_list.append(&result_btn);
+        }
+    });
+
+    // Closing the search starts the next one from scratch.
+    search_bar.connect_search_mode_enabled_notify(move |search_bar| {
+        if !search_bar.is_search_mode() {
+            search_entry.set_text("");
+        }
+    });
+
+    search_bar
+}
+
+
+/**
+Open the search bar with a query already typed, listing its results.
+# Parameters:
+* `search_bar`: A search bar built by `create_search_bar`.
+* `query`: The text to search for.
+ */
+pub fn start_search(search_bar: &gtk::SearchBar, query: &str) {
+    // The entry is the first child of the search box, see `create_search_bar`.
+    let search_entry = search_bar
+        .child()
+        .and_then(|search_box| search_box.first_child())
+        .and_then(|search_entry| search_entry.downcast::<gtk::SearchEntry>().ok());
+
+    // The query is typed once the bar is shown, as hiding it clears the entry.
+    search_bar.set_search_mode(true);
+    match search_entry {
+        Some(search_entry) => {
+            search_entry.set_text(query);
+            search_entry.set_position(-1);
+        }
+        None => error!("Search bar has no search entry"),
+    }
+}
+
+
+/**
+Event handler for the "next note" and "previous note" actions.
+Displays the note whose button comes after or before the displayed one, wrapping around.
+
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `forward`: Whether to go to the next note rather than the previous one.
+ */
+pub fn cycle_note_event(buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>, forward: bool) {
+    let note_titles: Vec<String> = get_hbox_childs(buttons_box_ref)
+        .iter()
+        .filter_map(note_button_title)
+        .collect();
+    if note_titles.is_empty() {
+        return;
+    }
+
+    let current = note_view_ref.current_note();
+    let position = note_titles.iter().position(|title| Some(title) == current.as_ref());
+    let next = match (position, forward) {
+        (Some(position), true) => (position + 1) % note_titles.len(),
+        (Some(position), false) => (position + note_titles.len() - 1) % note_titles.len(),
+        // Nothing displayed yet starts at either end.
+        (None, true) => 0,
+        (None, false) => note_titles.len() - 1,
+    };
+
+    note_view_ref.show_note(&note_titles[next]);
+}
+
+
+/**
+Event handler for the "trash" action.
+Lists the notes in the trash, each one can be restored or deleted for good.
+
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn trash_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
+    let dialog = create_dialog(app_ref, TRASH_DIAG);
+    let content_area = dialog.content_area();
+
+    let trashed_list = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+
+    let empty_label = gtk::Label::new(Some(EMPTY_TRASH));
+
+    let trashed_notes = trash::list_trashed_notes();
+    empty_label.set_visible(trashed_notes.is_empty());
+
+    for note_title in trashed_notes {
+        let row = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .build();
+        let restore_button = gtk::Button::with_label("Restore");
+        let delete_button = gtk::Button::with_label("Delete");
+        delete_button.style_context().add_class(DESTRUCTIVE_ACTION_CLASS);
+
+        row.append(&gtk::Label::builder().label(&note_title).xalign(0.0).hexpand(true).build());
+        row.append(&restore_button);
+        row.append(&delete_button);
+        trashed_list.append(&row);
+
+        // Create clones to use inside Fn enclosures
+        let bbox_clone = Rc::clone(buttons_box_ref);
+        let note_view_clone = Rc::clone(note_view_ref);
+        let title_clone = note_title.clone();
+        let row_clone = row.clone();
+        let empty_label_clone = empty_label.clone();
+        restore_button.connect_clicked(move |_| {
+            if !trash::restore_note(&title_clone) {
+                report_error("trash_event", &format!("{FAILED_TO_RESTORE} \"{title_clone}\""));
+                return;
+            }
+            sync_note(&title_clone, &bbox_clone, &note_view_clone);
+            remove_trash_row(&row_clone, &empty_label_clone);
+        });
+
+        let empty_label_clone = empty_label.clone();
+        let dialog_clone = dialog.clone();
+        delete_button.connect_clicked(move |_| {
+            let note_title = note_title.clone();
+            let row = row.clone();
+            let empty_label = empty_label_clone.clone();
+            confirm_deletion(dialog_clone.upcast_ref(), CONFIRM_DELETE_NOTE, "Delete", move || {
+                if trash::delete_from_trash(&note_title) {
+                    remove_trash_row(&row, &empty_label);
+                }
+            });
+        });
+    }
+
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .min_content_width(400)
+        .child(&trashed_list)
+        .build();
+
+    let empty_trash_button = gtk::Button::with_label("Empty trash");
+    empty_trash_button.style_context().add_class(DIAG_BUTTON_CLASS);
+    empty_trash_button.style_context().add_class(DESTRUCTIVE_ACTION_CLASS);
+
+    content_area.append(&empty_label);
+    content_area.append(&scrolled_window);
+    content_area.append(&empty_trash_button);
+
+    let dialog_clone = dialog.clone();
+    empty_trash_button.connect_clicked(move |_| {
+        let trashed_list = trashed_list.clone();
+        let empty_label = empty_label.clone();
+        confirm_deletion(dialog_clone.upcast_ref(), CONFIRM_EMPTY_TRASH, "Empty trash", move || {
+            if !trash::empty_trash() {
+                report_error("trash_event", FAILED_TO_EMPTY_TRASH);
+            }
+
+            // Rows are only removed for notes that are really gone.
+            let remaining = trash::list_trashed_notes();
+            for row in get_hbox_childs(&trashed_list) {
+                let title = row
+                    .first_child()
+                    .and_then(|label| label.downcast::<gtk::Label>().ok())
+                    .map(|label| label.text().to_string());
+                if title.is_some_and(|title| !remaining.contains(&title)) {
+                    trashed_list.remove(&row);
+                }
+            }
+            empty_label.set_visible(remaining.is_empty());
+        });
+    });
+
+    dialog.show();
+}
+
+
+/**
+Remove a note's row from the trash dialog, telling the trash is empty once no row is left.
+ */
+fn remove_trash_row(row: &gtk::Box, empty_label: &gtk::Label) {
+    let trashed_list = match row.parent().and_then(|parent| parent.downcast::<gtk::Box>().ok()) {
+        Some(trashed_list) => trashed_list,
+        None => return,
+    };
+
+    trashed_list.remove(row);
+    if trashed_list.first_child().is_none() {
+        empty_label.set_visible(true);
+    }
+}
+
+
+/**
+Event handler for the "about" action, showing the app's name and version.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `app_name`: The name shown to the user.
+ */
+pub fn about_event(app_ref: &gtk::Application, app_name: &str) {
+    let about_dialog = gtk::AboutDialog::builder()
+        .program_name(app_name)
+        .version(env!("CARGO_PKG_VERSION"))
+        .comments(ABOUT_COMMENTS)
         .modal(true)
-        .css_name(DIALOG_BOX)
-        .build()
+        .build();
+
+    about_dialog.set_transient_for(parent_window(app_ref).as_ref());
+    about_dialog.present();
+}
+
+
+/**
+Click event handler for the "open tasks" button.
+Lists the unchecked tasks of every note, checking one writes it back to its note.
+
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn open_tasks_button_click_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
+    let dialog = create_dialog(app_ref, OPEN_TASKS_DIAG);
+    let content_area = dialog.content_area();
+
+    let tasks_list = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+
+    let open_tasks = checklists::open_tasks();
+    if open_tasks.is_empty() {
+        tasks_list.append(&gtk::Label::new(Some(NO_OPEN_TASKS)));
+    }
+
+    let mut last_note: Option<String> = None;
+    for open_task in open_tasks {
+        // Tasks come grouped by note, so a title is added whenever the note changes.
+        if last_note.as_deref() != Some(open_task.note_title.as_str()) {
+            let note_label = gtk::Label::builder()
+                .label(&open_task.note_title)
+                .xalign(0.0)
+                .css_classes([DIAG_TITLE_CLASS])
+                .build();
+            tasks_list.append(&note_label);
+            last_note = Some(open_task.note_title.clone());
+        }
+
+        let check = gtk::CheckButton::with_label(&open_task.task.text);
+        check.style_context().add_class(crate::note_view::TASK_CLASS);
+
+        // Create clones to use inside Fn enclosure
+        let bbox_clone = Rc::clone(buttons_box_ref);
+        let note_view_clone = Rc::clone(note_view_ref);
+        let note_title = open_task.note_title;
+        let line = open_task.task.line;
+
+        check.connect_toggled(move |check| {
+            if !checklists::set_task_done(&note_title, line, check.is_active()) {
+                return;
+            }
+
+            refresh_note_button(&bbox_clone, &note_title);
+            if note_view_clone.current_note().as_deref() == Some(note_title.as_str()) {
+                note_view_clone.refresh();
+            }
+        });
+
+        tasks_list.append(&check);
+    }
+
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .min_content_width(400)
+        .child(&tasks_list)
+        .build();
+
+    content_area.append(&scrolled_window);
+    dialog.show();
+}
+
+
+/**
+Click event handler for the "error log" button.
+Lists the errors reported since the app started, the most recent first.
+
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn error_log_button_click_event(app_ref: &Rc<gtk::Application>) {
+    let dialog = create_dialog(app_ref, ERROR_LOG_DIAG);
+    let content_area = dialog.content_area();
+
+    let errors_list = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+
+    let errors = error_reporting::recent_errors();
+    if errors.is_empty() {
+        errors_list.append(&gtk::Label::new(Some(NO_ERRORS)));
+    }
+
+    for error in errors.iter().rev() {
+        let heading = format!("{} {}", error.time.format(ERROR_TIME_FORMAT), error.context);
+        let heading_label = gtk::Label::builder()
+            .label(&heading)
+            .xalign(0.0)
+            .css_classes([DIAG_TITLE_CLASS])
+            .build();
+        let message_label = gtk::Label::builder()
+            .label(&error.message)
+            .xalign(0.0)
+            .wrap(true)
+            .selectable(true)
+            .build();
+
+        errors_list.append(&heading_label);
+        errors_list.append(&message_label);
+    }
+
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .min_content_height(200)
+        .min_content_width(400)
+        .child(&errors_list)
+        .build();
+
+    content_area.append(&scrolled_window);
+    dialog.show();
+}
+
+
+/**
+Click event handler for the "reminder" button.
+Sets or clears a reminder on the displayed note or one of its tasks.
+
+# Parameters:
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn reminder_button_click_event(note_view_ref: &Rc<NoteView>, app_ref: &Rc<gtk::Application>) {
+    let note_title = match note_view_ref.current_note() {
+        Some(title) => title,
+        None => {
+            report_error("reminder_button_click_event", NO_NOTE_SELECTED);
+            return;
+        }
+    };
+
+    let dialog = create_dialog(app_ref, &format!("{REMINDER_DIAG}: {note_title}"));
+    let content_area = dialog.content_area();
+    let grid = gtk::Grid::builder()
+        .row_spacing(10)
+        .column_spacing(10)
+        .build();
+
+    // The reminder can be for the whole note or for one of its tasks.
+    let target_label = gtk::Label::builder()
+        .label("Remind about")
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+
+    let tasks = checklists::load_tasks(&note_title);
+    let task_texts: Vec<String> = tasks.iter().map(|task| task.text.clone()).collect();
+    let mut target_options = vec![WHOLE_NOTE_OPTION];
+    target_options.extend(tasks.iter().map(|task| task.text.as_str()));
+
+    let target_dropdown = gtk::DropDown::from_strings(&target_options);
+    target_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
+    // Due date and time
+    let due_label = gtk::Label::builder()
+        .label("Due")
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+
+    let calendar = gtk::Calendar::new();
+    let hour_spin = gtk::SpinButton::with_range(0.0, 23.0, 1.0);
+    let minute_spin = gtk::SpinButton::with_range(0.0, 59.0, 1.0);
+
+    let time_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .build();
+    time_box.append(&hour_spin);
+    time_box.append(&gtk::Label::new(Some(":")));
+    time_box.append(&minute_spin);
+
+    let set_button = gtk::Button::with_label("Set reminder");
+    let clear_button = gtk::Button::with_label("Clear reminder");
+    set_button.style_context().add_class(DIAG_BUTTON_CLASS);
+    clear_button.style_context().add_class(DIAG_BUTTON_CLASS);
+
+    grid.attach(&target_label, 0, 0, 1, 1);
+    grid.attach(&target_dropdown, 1, 0, 1, 1);
+    grid.attach(&due_label, 0, 1, 1, 1);
+    grid.attach(&calendar, 1, 1, 1, 1);
+    grid.attach(&time_box, 1, 2, 1, 1);
+
+    content_area.append(&grid);
+    content_area.append(&set_button);
+    content_area.append(&clear_button);
+
+    show_reminder_due(&note_title, None, &calendar, &hour_spin, &minute_spin);
+
+    // Create clones to use inside Fn enclosures
+    let title_clone = note_title.clone();
+    let texts_clone = task_texts.clone();
+    let calendar_clone = calendar.clone();
+    let hour_clone = hour_spin.clone();
+    let minute_clone = minute_spin.clone();
+
+    target_dropdown.connect_selected_notify(move |dropdown| {
+        let task = selected_task(dropdown, &texts_clone);
+        show_reminder_due(&title_clone, task, &calendar_clone, &hour_clone, &minute_clone);
+    });
+
+    dialog.show();
+
+    let title_clone = note_title.clone();
+    let texts_clone = task_texts.clone();
+    let dropdown_clone = target_dropdown.clone();
+    let dialog_clone = dialog.clone();
+
+    set_button.connect_clicked(move |_| {
+        let due = calendar_date(&calendar).and_then(|date| {
+            date.and_hms_opt(hour_spin.value_as_int() as u32, minute_spin.value_as_int() as u32, 0)
+        });
+        let due = match due {
+            Some(due) => due,
+            None => {
+                report_error("reminder_button_click_event", INVALID_DATE);
+                return;
+            }
+        };
+
+        let task = selected_task(&dropdown_clone, &texts_clone);
+        if !reminders::set_reminder(&title_clone, task, due) {
+            report_error("reminder_button_click_event", FAILED_TO_SET_REMINDER);
+            return;
+        }
+
+        dialog_clone.close();
+        dialog_clone.destroy();
+    });
+
+    clear_button.connect_clicked(move |_| {
+        let task = selected_task(&target_dropdown, &task_texts);
+        if !reminders::clear_reminder(&note_title, task) {
+            report_error("reminder_button_click_event", FAILED_TO_CLEAR_REMINDER);
+            return;
+        }
+
+        dialog.close();
+        dialog.destroy();
+    });
+}
+
+
+/**
+Click event handler for the "attach files" button.
+Opens a file chooser and attaches the chosen files to the displayed note.
+
+# Parameters:
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn attach_button_click_event(note_view_ref: &Rc<NoteView>, app_ref: &Rc<gtk::Application>) {
+    if note_view_ref.current_note().is_none() {
+        report_error("attach_button_click_event", NO_NOTE_SELECTED);
+        return;
+    }
+
+    let chooser = gtk::FileChooserDialog::new(
+        Some(ATTACH_FILES_DIAG),
+        parent_window(app_ref).as_ref(),
+        gtk::FileChooserAction::Open,
+        &[("Cancel", gtk::ResponseType::Cancel), ("Attach", gtk::ResponseType::Accept)]
+    );
+    chooser.set_select_multiple(true);
+    chooser.set_modal(true);
+
+    // Create clones to use inside Fn enclosure
+    let note_view_clone = Rc::clone(note_view_ref);
+
+    chooser.connect_response(move |chooser, response| {
+        if response == gtk::ResponseType::Accept {
+            let files = chooser.files();
+            let paths: Vec<PathBuf> = (0..files.n_items())
+                .filter_map(|index| files.item(index))
+                .filter_map(|item| item.downcast::<gio::File>().ok())
+                .filter_map(|file| file.path())
+                .collect();
+
+            note_view_clone.attach_files(&paths);
+        }
+
+        chooser.close();
+        chooser.destroy();
+    });
+
+    chooser.show();
+}
+
+
+/**
+Click event handler for the "export" button.
+Lets the user pick notes and a format, then asks where to save the exported file.
+
+# Parameters:
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn export_button_click_event(note_view_ref: &Rc<NoteView>, app_ref: &Rc<gtk::Application>) {
+    let note_titles = notes::list_note_titles();
+    if note_titles.is_empty() {
+        report_error("export_button_click_event", NO_NOTES_AVAILABLE);
+        return;
+    }
+
+    let dialog = create_dialog(app_ref, EXPORT_NOTES_DIAG);
+    let content_area = dialog.content_area();
+
+    let notes_label = gtk::Label::builder()
+        .label("Notes")
+        .xalign(0.0)
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+    content_area.append(&notes_label);
+
+    // The displayed note is selected by default.
+    let current_note = note_view_ref.current_note();
+    let mut note_checks: Vec<(String, gtk::CheckButton)> = Vec::new();
+    for note_title in note_titles {
+        let check = gtk::CheckButton::with_label(&note_title);
+        check.set_active(current_note.as_deref() == Some(note_title.as_str()));
+        content_area.append(&check);
+        note_checks.push((note_title, check));
+    }
+
+    let format_label = gtk::Label::builder()
+        .label("Format")
+        .xalign(0.0)
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+    let format_dropdown = gtk::DropDown::from_strings(&EXPORT_FORMATS);
+    format_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
+    let export_button = gtk::Button::with_label("Export");
+    export_button.style_context().add_class(DIAG_BUTTON_CLASS);
+
+    content_area.append(&format_label);
+    content_area.append(&format_dropdown);
+    content_area.append(&export_button);
+
+    dialog.show();
+
+    export_button.connect_clicked(move |_| {
+        let selected: Vec<String> = note_checks
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(note_title, _)| note_title.clone())
+            .collect();
+
+        if selected.is_empty() {
+            report_error("export_button_click_event", NO_NOTES_SELECTED);
+            return;
+        }
+
+        let format_index = (format_dropdown.selected() as usize).min(EXPORT_FORMATS.len() - 1);
+        choose_export_file(&dialog, selected, format_index);
+    });
+}
+
+
+/**
+Ask where to save an export and write it.
+# Parameters:
+* `export_dialog`: The export dialog, closed once the file is written.
+* `note_titles`: The titles of the notes to export.
+* `format_index`: Index of the chosen format in `EXPORT_FORMATS`.
+ */
+fn choose_export_file(export_dialog: &gtk::Dialog, note_titles: Vec<String>, format_index: usize) {
+    let chooser = gtk::FileChooserDialog::new(
+        Some(EXPORT_FILE_DIAG),
+        Some(export_dialog),
+        gtk::FileChooserAction::Save,
+        &[("Cancel", gtk::ResponseType::Cancel), ("Save", gtk::ResponseType::Accept)]
+    );
+    chooser.set_modal(true);
+    chooser.set_current_name(&format!("{EXPORT_FILE_NAME}.{}", EXPORT_EXTENSIONS[format_index]));
+
+    let export_dialog_clone = export_dialog.clone();
+    chooser.connect_response(move |chooser, response| {
+        let output = match chooser.file().and_then(|file| file.path()) {
+            Some(path) if response == gtk::ResponseType::Accept => path,
+            _ => {
+                chooser.destroy();
+                return;
+            }
+        };
+
+        let success = match EXPORT_EXTENSIONS[format_index] {
+            "html" => export::export_html(&note_titles, &output),
+            "pdf" => pdf_export::export_pdf(&note_titles, &output, Some(export_dialog_clone.upcast_ref())),
+            _ => export::export_markdown_bundle(&note_titles, &output),
+        };
+
+        chooser.destroy();
+        if !success {
+            report_error("choose_export_file", FAILED_TO_EXPORT);
+            return;
+        }
+
+        info!("Exported {} notes to {output:?}", note_titles.len());
+        export_dialog_clone.close();
+        export_dialog_clone.destroy();
+    });
+
+    chooser.show();
+}
+
+
+/**
+Click event handler for the "import" button.
+Imports notes from another app's export and shows a report of what was done.
+
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn import_button_click_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    app_ref: &Rc<gtk::Application>
+) {
+    let dialog = create_dialog(app_ref, IMPORT_NOTES_DIAG);
+    let content_area = dialog.content_area();
+    let grid = gtk::Grid::builder()
+        .row_spacing(10)
+        .column_spacing(10)
+        .build();
+
+    let source_labels: Vec<&str> = ImportSource::ALL.iter().map(|source| source.label()).collect();
+    let source_dropdown = gtk::DropDown::from_strings(&source_labels);
+    source_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
+    let policy_labels: Vec<&str> = CollisionPolicy::ALL.iter().map(|policy| policy.label()).collect();
+    let policy_dropdown = gtk::DropDown::from_strings(&policy_labels);
+    policy_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
+    let path_label = gtk::Label::builder()
+        .label(NO_IMPORT_PATH)
+        .xalign(0.0)
+        .css_classes([DIAG_CONTENTS_CLASS])
+        .build();
+    let choose_button = gtk::Button::with_label("Choose...");
+    let import_button = gtk::Button::with_label("Import");
+    import_button.style_context().add_class(DIAG_BUTTON_CLASS);
+
+    for (row, (text, widget)) in [
+        ("Import from", source_dropdown.clone().upcast::<gtk::Widget>()),
+        ("Existing titles", policy_dropdown.clone().upcast::<gtk::Widget>()),
+        ("From", path_label.clone().upcast::<gtk::Widget>()),
+    ].into_iter().enumerate() {
+        let label = gtk::Label::builder()
+            .label(text)
+            .css_classes([DIAG_TITLE_CLASS])
+            .build();
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(&widget, 1, row as i32, 1, 1);
+    }
+    grid.attach(&choose_button, 2, 2, 1, 1);
+
+    content_area.append(&grid);
+    content_area.append(&import_button);
+
+    // The chosen path is shared between the chooser and the import button.
+    let chosen_path: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+
+    // A different source may need a folder instead of a file, so the choice is reset.
+    let path_clone = Rc::clone(&chosen_path);
+    let path_label_clone = path_label.clone();
+    source_dropdown.connect_selected_notify(move |_| {
+        *path_clone.borrow_mut() = None;
+        path_label_clone.set_text(NO_IMPORT_PATH);
+    });
+
+    let path_clone = Rc::clone(&chosen_path);
+    let source_clone = source_dropdown.clone();
+    let dialog_clone = dialog.clone();
+    choose_button.connect_clicked(move |_| {
+        let source = ImportSource::ALL[(source_clone.selected() as usize).min(ImportSource::ALL.len() - 1)];
+        choose_import_path(&dialog_clone, source, &path_clone, &path_label);
+    });
+
+    dialog.show();
+
+    let bbox_clone = Rc::clone(buttons_box_ref);
+    let note_view_clone = Rc::clone(note_view_ref);
+    let app_ref_clone = Rc::clone(app_ref);
+    import_button.connect_clicked(move |_| {
+        let path = match chosen_path.borrow().clone() {
+            Some(path) => path,
+            None => {
+                report_error("import_button_click_event", NO_IMPORT_PATH);
+                return;
+            }
+        };
+
+        let source = ImportSource::ALL[(source_dropdown.selected() as usize).min(ImportSource::ALL.len() - 1)];
+        let policy = CollisionPolicy::ALL[(policy_dropdown.selected() as usize).min(CollisionPolicy::ALL.len() - 1)];
+
+        let report = match source.parse(&path) {
+            Ok(imported) => import::import_notes(imported, policy),
+            Err(e) => import::ImportReport { errors: vec![e], ..Default::default() },
+        };
+        info!("Import from {path:?}: {}", report.summary());
+
+        for note_title in report.new_titles() {
+            append_note_button(&note_title, &bbox_clone, &note_view_clone);
+        }
+        for note_title in &report.overwritten {
+            refresh_note_button(&bbox_clone, note_title);
+            if note_view_clone.current_note().as_deref() == Some(note_title.as_str()) {
+                note_view_clone.refresh();
+            }
+        }
+
+        dialog.close();
+        dialog.destroy();
+        show_import_report(&app_ref_clone, &report);
+    });
+}
+
+
+/**
+Ask for the file or folder to import from.
+# Parameters:
+* `import_dialog`: The import dialog, parent of the chooser.
+* `source`: The chosen import source.
+* `chosen_path`: Where to keep the chosen path.
+* `path_label`: Label showing the chosen path.
+ */
+fn choose_import_path(
+    import_dialog: &gtk::Dialog,
+    source: ImportSource,
+    chosen_path: &Rc<RefCell<Option<PathBuf>>>,
+    path_label: &gtk::Label
+) {
+    let action = if source.expects_folder() {
+        gtk::FileChooserAction::SelectFolder
+    } else {
+        gtk::FileChooserAction::Open
+    };
+
+    let chooser = gtk::FileChooserDialog::new(
+        Some(IMPORT_SOURCE_DIAG),
+        Some(import_dialog),
+        action,
+        &[("Cancel", gtk::ResponseType::Cancel), ("Choose", gtk::ResponseType::Accept)]
+    );
+    chooser.set_modal(true);
+
+    let path_clone = Rc::clone(chosen_path);
+    let path_label_clone = path_label.clone();
+    chooser.connect_response(move |chooser, response| {
+        if response == gtk::ResponseType::Accept {
+            if let Some(path) = chooser.file().and_then(|file| file.path()) {
+                path_label_clone.set_text(&path.to_string_lossy());
+                *path_clone.borrow_mut() = Some(path);
+            }
+        }
+
+        chooser.destroy();
+    });
+
+    chooser.show();
+}
+
+
+/**
+Show the outcome of an import on a dialog.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `report`: The import report.
+ */
+fn show_import_report(app_ref: &Rc<gtk::Application>, report: &import::ImportReport) {
+    let dialog = create_dialog(app_ref, IMPORT_REPORT_DIAG);
+    let content_area = dialog.content_area();
+
+    let mut details: Vec<String> = Vec::new();
+    details.extend(report.imported.iter().map(|title| format!("Imported \"{title}\"")));
+    details.extend(report.renamed.iter().map(|(old, new)| format!("Imported \"{old}\" as \"{new}\"")));
+    details.extend(report.overwritten.iter().map(|title| format!("Overwrote \"{title}\"")));
+    details.extend(report.skipped.iter().map(|title| format!("Skipped \"{title}\", it already exists")));
+    details.extend(report.errors.iter().map(|error| format!("Error: {error}")));
+
+    let summary_label = gtk::Label::builder()
+        .label(report.summary())
+        .css_classes([DIAG_TITLE_CLASS])
+        .build();
+    let details_label = gtk::Label::builder()
+        .label(details.join("\n"))
+        .xalign(0.0)
+        .selectable(true)
+        .css_classes([DIAG_CONTENTS_CLASS])
+        .build();
+    let scrolled_window = gtk::ScrolledWindow::builder()
+        .min_content_height(200)
+        .min_content_width(400)
+        .child(&details_label)
+        .build();
+
+    content_area.append(&summary_label);
+    content_area.append(&scrolled_window);
+    dialog.show();
+}
+
+
+/**
+Show a small window that appends a timestamped line to a note, the inbox note by default.
+It works on its own, the main window doesn't need to be open.
+
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn show_quick_capture(app_ref: &gtk::Application) {
+    let capture_window = gtk::Window::builder()
+        .application(app_ref)
+        .title(QUICK_CAPTURE_DIAG)
+        .default_width(QUICK_CAPTURE_WIDTH)
+        .css_name(DIALOG_BOX)
+        .build();
+
+    let content_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .build();
+
+    // The inbox comes first even if it doesn't exist yet, followed by every other note.
+    let mut note_titles = notes::list_note_titles();
+    note_titles.retain(|title| title != quick_capture::INBOX_NOTE);
+    note_titles.sort();
+    note_titles.insert(0, quick_capture::INBOX_NOTE.to_string());
+
+    let note_options: Vec<&str> = note_titles.iter().map(String::as_str).collect();
+    let note_dropdown = gtk::DropDown::from_strings(&note_options);
+    note_dropdown.style_context().add_class(DIAG_TITLE_CLASS);
+
+    let text_entry = gtk::Entry::builder()
+        .placeholder_text(CAPTURE_PLACEHOLDER)
+        .css_classes([DIAG_CONTENTS_CLASS])
+        .build();
+
+    let capture_button = gtk::Button::with_label("Capture");
+    capture_button.style_context().add_class(DIAG_BUTTON_CLASS);
+
+    let text_validation = error_reporting::validation_label(&text_entry);
+
+    content_box.append(&note_dropdown);
+    content_box.append(&text_entry);
+    content_box.append(&text_validation);
+    content_box.append(&capture_button);
+    capture_window.set_child(Some(&error_reporting::toast_overlay(&content_box)));
+
+    // Pressing enter on the entry captures too.
+    let capture_button_clone = capture_button.clone();
+    text_entry.connect_activate(move |_| {
+        capture_button_clone.emit_clicked();
+    });
+
+    // Create clones to use inside Fn enclosure
+    let capture_window_clone = capture_window.clone();
+    capture_button.connect_clicked(move |_| {
+        let text = text_entry.text();
+        if text.trim().is_empty() {
+            show_validation_error(&text_entry, &text_validation, CAPTURE_CANT_BE_EMPTY);
+            return;
+        }
+
+        let note_title = note_titles.get(note_dropdown.selected() as usize).map(String::as_str);
+        if quick_capture::capture(note_title, &text).is_none() {
+            report_error("show_quick_capture", FAILED_TO_CAPTURE);
+            return;
+        }
+        capture_window_clone.close();
+    });
+
+    capture_window.present();
+}
+
+
+/**
+Click event handler for the "today" button.
+Opens today's daily note, creating it if it doesn't exist, and displays its contents.
+
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+* `calendar_ref`: A reference to the daily notes calendar.
+ */
+pub fn today_button_click_event(
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>,
+    calendar_ref: &Rc<gtk::Calendar>
+) {
+    let today = daily_notes::today();
+    let title = match daily_notes::open_or_create_daily_note(today) {
+        Some(title) => title,
+        None => return,
+    };
+
+    select_note(&title, buttons_box_ref, note_view_ref);
+
+    select_calendar_date(calendar_ref, today);
+    mark_daily_notes(calendar_ref);
+}
+
+
+/**
+Display a note, adding a button for it if it doesn't have one yet and there is room for it.
+# Parameters:
+* `note_title`: The title of the note to display.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+# Return:
+Boolean stating if the note exists and is now displayed.
+ */
+pub fn select_note(note_title: &str, buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) -> bool {
+    if !notes::note_exists(note_title) {
+        report_error("select_note", &format!("{NOTE_NOT_FOUND} \"{note_title}\""));
+        return false;
+    }
+
+    // The note may be new or may just not have a button yet.
+    let has_button = get_hbox_childs(buttons_box_ref)
+        .iter()
+        .any(|child| note_button_title(child).as_deref() == Some(note_title));
+    if !has_button {
+        append_note_button(note_title, buttons_box_ref, note_view_ref);
+    }
+
+    note_view_ref.show_note(note_title);
+    true
+}
+
+
+/**
+Rebuild every note button, e.g. after the notes directory or the sort order changed.
+The displayed note is shown again, or cleared if it isn't there anymore.
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+ */
+pub fn reload_notes(buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) {
+    // The editor may have been changed in the preferences, so the menus look it up again.
+    EDIT_LABEL.with(|label| label.borrow_mut().take());
+
+    for child in get_hbox_childs(buttons_box_ref) {
+        buttons_box_ref.remove(&child);
+    }
+
+    for note_title in notes::load_notes() {
+        append_note_button(&note_title, buttons_box_ref, note_view_ref);
+    }
+
+    match note_view_ref.current_note() {
+        Some(note_title) if notes::note_exists(&note_title) => note_view_ref.refresh(),
+        Some(_) => note_view_ref.show_text(NO_NOTE_SELECTED),
+        None => {}
+    }
+}
+
+
+/**
+Bring a note's button and the note view up to date after the note changed on disk.
+# Parameters:
+* `note_title`: The title of the note that changed.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+ */
+pub fn sync_note(note_title: &str, buttons_box_ref: &Rc<gtk::Box>, note_view_ref: &Rc<NoteView>) {
+    let exists = notes::note_exists(note_title);
+    let hchilds = get_hbox_childs(buttons_box_ref);
+    let note_btn = hchilds.iter().find(|child| note_button_title(child).as_deref() == Some(note_title));
+
+    match note_btn {
+        Some(note_btn) if !exists => buttons_box_ref.remove(note_btn),
+        Some(_) => refresh_note_button(buttons_box_ref, note_title),
+        None if exists => {
+            append_note_button(note_title, buttons_box_ref, note_view_ref);
+        }
+        None => {}
+    }
+
+    if note_view_ref.current_note().as_deref() == Some(note_title) {
+        if exists {
+            note_view_ref.refresh();
+        } else {
+            note_view_ref.show_text(NOTE_DELETED);
+        }
+    }
+}
+
+
+/**
+Event handler for a day being selected on the daily notes calendar.
+Displays the daily note for that day if there is one.
+
+# Parameters:
+* `calendar_ref`: A reference to the daily notes calendar.
+* `note_view_ref`: A reference to the view that displays notes contents.
+ */
+pub fn calendar_day_selected_event(calendar_ref: &gtk::Calendar, note_view_ref: &Rc<NoteView>) {
+    let date = match calendar_date(calendar_ref) {
+        Some(date) => date,
+        None => return,
+    };
+
+    let title = daily_notes::daily_note_title(date);
+    if notes::note_exists(&title) {
+        note_view_ref.show_note(&title);
+    }
+}
+
+
+/**
+Mark the days of the calendar's current month that have a daily note.
+# Parameters:
+* `calendar_ref`: A reference to the daily notes calendar.
+ */
+pub fn mark_daily_notes(calendar_ref: &gtk::Calendar) {
+    calendar_ref.clear_marks();
+
+    let shown = calendar_ref.date();
+    for date in daily_notes::daily_note_dates() {
+        if date.year() == shown.year() && date.month() as i32 == shown.month() {
+            calendar_ref.mark_day(date.day());
+        }
+    }
+}
+
+
+/**
+Add a button for a note to the buttons box, displaying the note's contents when clicked.
+# Parameters:
+* `note_title`: The title of the note.
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_view_ref`: A reference to the view that displays notes contents.
+# Return:
+The new note button.
+ */
+pub fn append_note_button(
+    note_title: &str,
+    buttons_box_ref: &Rc<gtk::Box>,
+    note_view_ref: &Rc<NoteView>
+) -> gtk::Button {
+    // The button shows the title with a task completion badge, a snippet of the note and when it
+    // was modified, so the title is kept as its name.
+    let title_row = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .build();
+    title_row.append(&gtk::Label::builder()
+        .label(note_title)
+        .xalign(0.0)
+        .hexpand(true)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes([NOTE_TITLE_CLASS])
+        .build());
+    title_row.append(&gtk::Label::builder().css_classes([TASK_BADGE_CLASS]).build());
+
+    let button_content = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(2)
+        .build();
+    button_content.append(&title_row);
+    button_content.append(&gtk::Label::builder()
+        .xalign(0.0)
+        .ellipsize(gtk::pango::EllipsizeMode::End)
+        .css_classes([NOTE_SNIPPET_CLASS])
+        .build());
+    button_content.append(&gtk::Label::builder()
+        .xalign(0.0)
+        .css_classes([NOTE_DATE_CLASS])
+        .build());
+
+    let note_btn = gtk::Button::builder()
+        .name(note_title)
+        .child(&button_content)
+        .build();
+    note_btn.style_context().add_class(NOTE_BUTTON_CLASS);
+    update_note_button(&note_btn, note_title);
+
+    // Create a reference clone for the button click event
+    let note_view_clone = Rc::clone(note_view_ref);
+    let title = note_title.to_string();
+
+    note_btn.connect_clicked(move |_| {
+        note_view_clone.show_note(&title);
+    });
+
+    // Double clicking the button opens the note in a window of its own. The gesture runs before the
+    // button's own, so a single click still shows the note here.
+    let double_click = gtk::GestureClick::builder()
+        .button(gdk::BUTTON_PRIMARY)
+        .propagation_phase(gtk::PropagationPhase::Capture)
+        .build();
+    let title = note_title.to_string();
+    double_click.connect_pressed(move |gesture, n_press, _, _| {
+        if n_press != 2 {
+            return;
+        }
+        let detailed_action = format!("{}.{}", shortcuts::WINDOW_SCOPE, shortcuts::NOTE_OPEN_WINDOW_ACTION);
+        if let Some(widget) = gesture.widget() {
+            if let Err(e) = widget.activate_action(&detailed_action, Some(&title.to_variant())) {
+                error!("Error opening note {title} in a new window: {e}");
+            }
+        }
+    });
+    note_btn.add_controller(double_click);
+
+    // Right clicking the button opens the note's context menu.
+    let context_menu = gtk::PopoverMenu::from_model(Some(&note_context_menu(note_title)));
+    context_menu.set_parent(&note_btn);
+    context_menu.set_has_arrow(false);
+
+    let right_click = gtk::GestureClick::builder().button(gdk::BUTTON_SECONDARY).build();
+    let context_menu_clone = context_menu.clone();
+    right_click.connect_pressed(move |_, _, x, y| {
+        context_menu_clone.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        context_menu_clone.popup();
+    });
+    note_btn.add_controller(right_click);
+
+    // The button doesn't know about the popover, so it has to be unparented by hand.
+    note_btn.connect_destroy(move |_| {
+        context_menu.unparent();
+    });
+
+    buttons_box_ref.append(&note_btn);
+    note_btn
+}
+
+
+/**
+Build the context menu of a note's button, its items act on that note.
+# Parameters:
+* `note_title`: The title of the note.
+# Return:
+The menu model.
+ */
+fn note_context_menu(note_title: &str) -> gio::Menu {
+    let edit_label = EDIT_LABEL.with(|label| {
+        label
+            .borrow_mut()
+            .get_or_insert_with(|| match external_editor::resolve_editor() {
+                Some(editor) => format!("Edit in {}", editor.name()),
+                None => "Edit".to_string(),
+            })
+            .clone()
+    });
+    let items = [
+        ("Open", shortcuts::NOTE_OPEN_ACTION),
+        ("Open in new window", shortcuts::NOTE_OPEN_WINDOW_ACTION),
+        (edit_label.as_str(), shortcuts::NOTE_EDIT_ACTION),
+        ("Rename…", shortcuts::NOTE_RENAME_ACTION),
+        ("Move to trash", shortcuts::NOTE_TRASH_ACTION),
+    ];
+
+    let menu = gio::Menu::new();
+    for (label, action) in items {
+        let item = gio::MenuItem::new(Some(label), None);
+        let detailed_action = format!("{}.{action}", shortcuts::WINDOW_SCOPE);
+        item.set_action_and_target_value(Some(&detailed_action), Some(&note_title.to_variant()));
+        menu.append_item(&item);
+    }
+
+    menu
+}
+
+
+/**
+Refresh the task completion badge, snippet and modified date of a note's button.
+# Parameters:
+* `buttons_box_ref`: A reference to the buttons box.
+* `note_title`: The title of the note that changed.
+ */
+pub fn refresh_note_button(buttons_box_ref: &gtk::Box, note_title: &str) {
+    for child in get_hbox_childs(buttons_box_ref) {
+        if note_button_title(&child).as_deref() != Some(note_title) {
+            continue;
+        }
+
+        if let Some(btn) = child.downcast_ref::<gtk::Button>() {
+            update_note_button(btn, note_title);
+        }
+    }
+}
+
+
+/**
+Set the task completion badge, snippet and modified date of a note button.
+The badge is hidden if the note has no tasks.
+# Parameters:
+* `note_btn`: A note button created by `append_note_button`.
+* `note_title`: The title of the note.
+ */
+fn update_note_button(note_btn: &gtk::Button, note_title: &str) {
+    let button_content = match note_btn.child() {
+        Some(widget) => widget,
+        None => return,
+    };
+    let label_at = |widget: Option<gtk::Widget>| widget.and_then(|widget| widget.downcast::<gtk::Label>().ok());
+
+    // The badge closes the title row, the snippet and the date follow it.
+    let title_row = button_content.first_child();
+    let snippet = title_row.as_ref().and_then(|row| row.next_sibling());
+    let date = snippet.as_ref().and_then(|snippet| snippet.next_sibling());
+
+    if let Some(badge) = label_at(title_row.and_then(|row| row.last_child())) {
+        match checklists::task_progress(note_title) {
+            Some((done, total)) => {
+                badge.set_text(&format!("{done}/{total}"));
+                badge.set_visible(true);
+            }
+            None => badge.set_visible(false),
+        }
+    }
+
+    if let Some(snippet) = label_at(snippet) {
+        let text = notes::Note::load(note_title).map(|note| note.snippet()).unwrap_or_default();
+        snippet.set_visible(!text.is_empty());
+        snippet.set_text(&text);
+    }
+
+    if let Some(date) = label_at(date) {
+        let modified = notes::note_modified(note_title).map(DateTime::<Local>::from);
+        match modified {
+            Some(modified) if modified.date_naive() == Local::now().date_naive() => {
+                date.set_text(&modified.format(NOTE_TIME_FORMAT).to_string());
+                date.set_visible(true);
+            }
+            Some(modified) => {
+                date.set_text(&modified.format(NOTE_DATE_FORMAT).to_string());
+                date.set_visible(true);
+            }
+            None => date.set_visible(false),
+        }
+    }
+}
+
+
+/**
+Get the note title from a note button in the buttons box.
+# Parameters:
+* `child`: A child widget of the buttons box.
+# Return:
+The note title kept as the button's name, or `None` if the widget is not a button.
+ */
+fn note_button_title(child: &gtk::Widget) -> Option<String> {
+    let btn = child.downcast_ref::<gtk::Button>()?;
+    Some(btn.widget_name().trim().to_string())
+}
+
+
+/**
+Get the task chosen on a reminder target dropdown.
+# Parameters:
+* `dropdown`: The dropdown, whose first option is the whole note.
+* `task_texts`: The text of each task, in the same order as the dropdown options.
+# Return:
+The task text or `None` for the whole note.
+ */
+fn selected_task<'a>(dropdown: &gtk::DropDown, task_texts: &'a [String]) -> Option<&'a str> {
+    (dropdown.selected() as usize)
+        .checked_sub(1)
+        .and_then(|index| task_texts.get(index))
+        .map(String::as_str)
+}
+
+
+/**
+Show the due date of an existing reminder on the reminder dialog inputs,
+or an hour from now if there is no reminder.
+# Parameters:
+* `note_title`: The title of the note.
+* `task`: The text of the task, or `None` for the whole note.
+* `calendar`: The due date calendar.
+* `hour_spin`: The due hour input.
+* `minute_spin`: The due minute input.
+ */
+fn show_reminder_due(
+    note_title: &str,
+    task: Option<&str>,
+    calendar: &gtk::Calendar,
+    hour_spin: &gtk::SpinButton,
+    minute_spin: &gtk::SpinButton
+) {
+    let due: NaiveDateTime = reminders::get_reminder(note_title, task)
+        .unwrap_or_else(|| Local::now().naive_local() + Duration::hours(1));
+
+    select_calendar_date(calendar, due.date());
+    hour_spin.set_value(due.hour() as f64);
+    minute_spin.set_value(due.minute() as f64);
+}
+
+
+/**
+Select a date on a calendar.
+# Parameters:
+* `calendar_ref`: A reference to the calendar.
+* `date`: The date to select.
+ */
+fn select_calendar_date(calendar_ref: &gtk::Calendar, date: NaiveDate) {
+    match glib::DateTime::from_local(date.year(), date.month() as i32, date.day() as i32, 0, 0, 0.0) {
+        Ok(date) => calendar_ref.select_day(&date),
+        Err(e) => error!("Failed to build calendar date for {date}: {e}"),
+    }
+}
+
+
+/**
+Get the date selected on a calendar.
+# Parameters:
+* `calendar_ref`: A reference to the calendar.
+# Return:
+The selected date or `None` if it is not a valid date.
+ */
+fn calendar_date(calendar_ref: &gtk::Calendar) -> Option<NaiveDate> {
+    let date = calendar_ref.date();
+    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day_of_month() as u32)
+}
+
+
+/**
+Get all childs from a gtk box.
+# Parameters:
+* `hbox`: A reference to a gtk box to get their childs.
+# Return:
+A vector of gtk widgets. 
+ */
+fn get_hbox_childs(hbox: &gtk::Box) -> Vec<gtk::Widget> {
+    let mut children: Vec<gtk::Widget> = Vec::new();
+    let mut sibling = hbox.first_child();
+
+    while let Some(child) = sibling {
+        children.push(child.clone());
+        sibling = child.next_sibling();
+    }
+
+    children
+}
+
+
+/**
+Create a gtk dialog.
+It's kept over the focused window, or any window of the app, and stands on its own if there is none.
+
+# Parameters
+`app_ref`: A reference to a pointer of the Application
+`diag_msg`: The message to be displayed on the dialog.
+
+# Return:
+The gtk dialog widget.
+ */
+fn create_dialog(app_ref: &Rc<gtk::Application>, diag_msg: &str) -> gtk::Dialog {
+    let dialog = gtk::Dialog::builder()
+        .title(diag_msg)
+        .destroy_with_parent(true)
+        .modal(true)
+        .css_name(DIALOG_BOX)
+        .build();
+    dialog.set_transient_for(parent_window(app_ref).as_ref());
+    dialog
+}
+
+
+/**
+Get the window dialogs should be kept over.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+# Return:
+The focused window, another window of the app if none is focused, or `None` if the app has no window.
+ */
+pub fn parent_window(app_ref: &gtk::Application) -> Option<gtk::Window> {
+    app_ref.active_window().or_else(|| app_ref.windows().into_iter().next())
 }
@@ -1,34 +1,421 @@
 use std::rc::Rc;
-use std::path::PathBuf;
-use std::str::FromStr;
-use gtk::{self, prelude::*};
+use std::cell::RefCell;
+use gtk::{self, gio, glib, prelude::*};
+use notes_core::{notes, reminders};
+use notes_core::preferences::Preferences;
+use notes_core::window_state::WindowState;
+use notes_core::{warn, info};
 
-mod notes;
 mod gtk_handlers;
+mod note_view;
+mod reminder_notifications;
+mod pdf_export;
+mod cli;
+mod dbus_service;
+mod search_provider;
+mod external_editor;
+mod error_reporting;
+mod glib_logging;
+mod shortcuts;
+mod preferences_window;
+mod theme;
+mod stylesheet;
+mod note_windows;
+mod note_tabs;
+mod window_registry;
 
 
 const APP_ID: &str = "org.zeke-desktop-app";
 const APP_NAME: &str = "My Notes";
-const CSS_PATH: &str = "css/style.css";
-const DEFAULT_WIDTH: i32 = 800;
+/// The note list can't be squeezed narrower than this.
+const SIDEBAR_MIN_WIDTH: i32 = 150;
 
-const ADD_NOTE_LABEL: &str = "Add new note";
-const REMOVE_NOTE_LABEL: &str = "Remove a note";
-const EDIT_NOTE_LABEL: &str = "Edit a note";
 const TEXT_BOX_LABEL: &str = "** Your note contents will show here **";
 
+// Header bar buttons
+const NEW_NOTE_ICON: &str = "document-new-symbolic";
+const NEW_NOTE_TOOLTIP: &str = "New note";
+const TODAY_NOTE_ICON: &str = "x-office-calendar-symbolic";
+const TODAY_NOTE_TOOLTIP: &str = "Today's note";
+const SEARCH_ICON: &str = "system-search-symbolic";
+const SEARCH_TOOLTIP: &str = "Search notes";
+const MENU_ICON: &str = "open-menu-symbolic";
+const MENU_TOOLTIP: &str = "Main menu";
+
+// Command line options handled by the running instance
+const NOTE_OPTION: &str = "note";
+const NOTE_OPTION_DESCRIPTION: &str = "Open the note with the given title";
+const NOTE_OPTION_ARG: &str = "TITLE";
+const NEW_OPTION: &str = "new";
+const NEW_OPTION_DESCRIPTION: &str = "Open the dialog to create a new note";
+const CAPTURE_OPTION: &str = "capture";
+const CAPTURE_OPTION_DESCRIPTION: &str = "Open the quick capture window only";
+
+// Command line options handled before anything else, also by subcommands
+const VERBOSE_OPTION: &str = "verbose";
+const VERBOSE_SHORT_OPTION: u8 = b'v';
+const VERBOSE_OPTION_DESCRIPTION: &str = "Log debug messages and show info and debug messages on stderr";
+
+// Application actions
+const QUICK_CAPTURE_ACTION: &str = "quick-capture";
+
+
+/**
+The parts of the main window needed once it's built, e.g. to open a note requested from the command line.
+ */
+struct MainWindow {
+    window: gtk::ApplicationWindow,
+    /// Divides the note list from the note contents.
+    paned: gtk::Paned,
+    /// Scrolls the note contents.
+    note_scroll: gtk::ScrolledWindow,
+    buttons_box: Rc<gtk::Box>,
+    note_view: Rc<note_view::NoteView>,
+    note_tabs: Rc<note_tabs::NoteTabs>,
+    calendar: Rc<gtk::Calendar>,
+    search_bar: gtk::SearchBar,
+}
+
 
 fn main() {
+    // Logging is set up first so every message ends up in the log file.
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let verbose_flags = [format!("--{VERBOSE_OPTION}"), format!("-{}", VERBOSE_SHORT_OPTION as char)];
+    let verbose = args.iter().any(|arg| verbose_flags.contains(arg));
+    args.retain(|arg| !verbose_flags.contains(arg));
+    glib_logging::init(verbose);
+
+    // Subcommands are handled before gtk is initialized, so they work without a display.
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
+
+    // Launching the app again while it runs forwards the command line and files to the running instance.
     let app = gtk::Application::builder()
         .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::HANDLES_OPEN | gio::ApplicationFlags::HANDLES_COMMAND_LINE)
         .build();
 
-    app.connect_startup(|_| load_css());
-    app.connect_activate(create_app_structure);
+    app.add_main_option(
+        NOTE_OPTION,
+        glib::Char::from(b'n'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::String,
+        NOTE_OPTION_DESCRIPTION,
+        Some(NOTE_OPTION_ARG),
+    );
+    app.add_main_option(
+        NEW_OPTION,
+        glib::Char::from(b'N'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        NEW_OPTION_DESCRIPTION,
+        None,
+    );
+    // Already handled above, it's only declared so gtk accepts it.
+    app.add_main_option(
+        VERBOSE_OPTION,
+        glib::Char::from(VERBOSE_SHORT_OPTION),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        VERBOSE_OPTION_DESCRIPTION,
+        None,
+    );
+    app.add_main_option(
+        CAPTURE_OPTION,
+        glib::Char::from(b'c'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        CAPTURE_OPTION_DESCRIPTION,
+        None,
+    );
+
+    // The window is built on the first activation and reused by the next ones.
+    let main_window: Rc<RefCell<Option<Rc<MainWindow>>>> = Rc::new(RefCell::new(None));
+
+    let main_window_clone = Rc::clone(&main_window);
+    app.connect_startup(move |app| {
+        // The palette goes first, the stylesheet uses its colors.
+        let preferences = Preferences::load();
+        theme::init(app, preferences.theme);
+        preferences_window::apply_note_font(&preferences.font);
+        stylesheet::init(&preferences.user_theme);
+        reminder_notifications::watch_reminders(app, Rc::new(reminders::SystemClock));
+        export_notes_service(app);
+        export_search_provider(app, &main_window_clone);
+
+        // The monitor stops when dropped, so the app holds it until it shuts down.
+        let notes_monitor = Rc::new(RefCell::new(watch_notes_dir(app, &main_window_clone)));
+        let notes_monitor_clone = Rc::clone(&notes_monitor);
+        app.connect_shutdown(move |_| {
+            if let Some(monitor) = notes_monitor_clone.borrow().as_ref() {
+                monitor.cancel();
+            }
+        });
+
+        let preferences_action = gio::SimpleAction::new(shortcuts::PREFERENCES_ACTION, None);
+        let app_clone = app.clone();
+        let preferences_main_window = Rc::clone(&main_window_clone);
+        preferences_action.connect_activate(move |_, _| {
+            // Create clones to use inside Fn enclosure
+            let app_ref = app_clone.clone();
+            let main_window = Rc::clone(&preferences_main_window);
+            let notes_monitor = Rc::clone(&notes_monitor);
+            preferences_window::show_preferences(&app_clone, move |change| {
+                preferences_changed(&app_ref, change, &main_window, &notes_monitor);
+            });
+        });
+        app.add_action(&preferences_action);
+
+        // Also reachable from the desktop file, without opening the main window.
+        let quick_capture_action = gio::SimpleAction::new(QUICK_CAPTURE_ACTION, None);
+        let app_clone = app.clone();
+        quick_capture_action.connect_activate(move |_, _| {
+            gtk_handlers::show_quick_capture(&app_clone);
+        });
+        app.add_action(&quick_capture_action);
+
+        let quit_action = gio::SimpleAction::new(shortcuts::QUIT_ACTION, None);
+        let app_clone = app.clone();
+        let quit_main_window = Rc::clone(&main_window_clone);
+        quit_action.connect_activate(move |_, _| {
+            // Quitting doesn't close the window, so its state is saved here unless it was closed already.
+            if let Some(main_window) = quit_main_window.borrow().as_ref() {
+                if main_window.window.application().is_some() {
+                    save_window_state(main_window);
+                }
+            }
+            app_clone.quit();
+        });
+        app.add_action(&quit_action);
+
+        let about_action = gio::SimpleAction::new(shortcuts::ABOUT_ACTION, None);
+        let app_clone = app.clone();
+        about_action.connect_activate(move |_, _| {
+            gtk_handlers::about_event(&app_clone, APP_NAME);
+        });
+        app.add_action(&about_action);
+
+        shortcuts::set_accels(app);
+    });
+
+    let main_window_clone = Rc::clone(&main_window);
+    app.connect_activate(move |app| {
+        present_main_window(app, &main_window_clone);
+    });
+
+    let main_window_clone = Rc::clone(&main_window);
+    app.connect_open(move |app, files, _| {
+        let main_window = present_main_window(app, &main_window_clone);
+        for file in files {
+            open_note_file(file, &main_window);
+        }
+    });
 
-    println!("Application started...");
+    app.connect_command_line(move |app, command_line| {
+        handle_command_line(app, command_line, &main_window)
+    });
+
+    info!("Application started");
     app.run();
-    println!("Application terminated.");
+    info!("Application terminated");
+}
+
+
+/**
+Export the notes D-Bus interface.
+# Parameters:
+* `app_ref`: A reference to the gtk application, already registered on the session bus.
+ */
+fn export_notes_service(app_ref: &gtk::Application) {
+    if let (Some(connection), Some(object_path)) = (app_ref.dbus_connection(), app_ref.dbus_object_path()) {
+        dbus_service::register_notes_service(&connection, &object_path);
+    }
+}
+
+
+/**
+Watch the notes directory, keeping every window and the bus informed of changes to the notes.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `main_window`: The main window, if it was already built.
+# Return:
+The monitor, which has to be kept alive, or `None` if the directory can't be watched.
+ */
+fn watch_notes_dir(
+    app_ref: &gtk::Application,
+    main_window: &Rc<RefCell<Option<Rc<MainWindow>>>>
+) -> Option<gio::FileMonitor> {
+    let connection = app_ref.dbus_connection();
+    let object_path = app_ref.dbus_object_path();
+
+    // Makes sure the notes directory exists before watching it.
+    notes::list_note_titles();
+
+    // Create clones to use inside Fn enclosure
+    let main_window_clone = Rc::clone(main_window);
+    dbus_service::watch_notes(move |note_title, change| {
+        if let (Some(connection), Some(object_path)) = (&connection, &object_path) {
+            dbus_service::emit_note_changed(connection, object_path, note_title, change);
+        }
+        if let Some(window) = main_window_clone.borrow().as_ref() {
+            gtk_handlers::sync_note(note_title, &window.buttons_box, &window.note_view);
+        }
+        note_tabs::sync_note(note_title);
+        note_windows::sync_note(note_title);
+    })
+}
+
+
+/**
+Bring the main window up to date after a change in the preferences window.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `change`: What changed.
+* `main_window`: The main window, if it was already built.
+* `notes_monitor`: The monitor of the notes directory, replaced when the directory changes.
+ */
+fn preferences_changed(
+    app_ref: &gtk::Application,
+    change: preferences_window::PreferenceChange,
+    main_window: &Rc<RefCell<Option<Rc<MainWindow>>>>,
+    notes_monitor: &RefCell<Option<gio::FileMonitor>>
+) {
+    if change == preferences_window::PreferenceChange::NotesDirectory {
+        info!("Notes directory changed to {}", notes::notes_dir().display());
+        if let Some(monitor) = notes_monitor.borrow_mut().take() {
+            monitor.cancel();
+        }
+        *notes_monitor.borrow_mut() = watch_notes_dir(app_ref, main_window);
+    }
+
+    if let Some(window) = main_window.borrow().as_ref() {
+        gtk_handlers::reload_notes(&window.buttons_box, &window.note_view);
+        window.note_tabs.close_missing_tabs();
+        gtk_handlers::mark_daily_notes(&window.calendar);
+    }
+}
+
+
+/**
+Export the desktop search provider, opening notes chosen in the overview in the main window
+and searches launched from it in the main window's search bar.
+# Parameters:
+* `app_ref`: A reference to the gtk application, already registered on the session bus.
+* `main_window`: The main window, if it was already built.
+ */
+fn export_search_provider(app_ref: &gtk::Application, main_window: &Rc<RefCell<Option<Rc<MainWindow>>>>) {
+    let connection = match app_ref.dbus_connection() {
+        Some(connection) => connection,
+        None => return,
+    };
+
+    // Create clones to use inside Fn enclosure
+    let app_ref_clone = app_ref.clone();
+    let main_window_clone = Rc::clone(main_window);
+    search_provider::register_search_provider(&connection, move |activation| {
+        let window = present_main_window(&app_ref_clone, &main_window_clone);
+        match activation {
+            search_provider::SearchActivation::Note(note_title) => {
+                gtk_handlers::select_note(&note_title, &window.buttons_box, &window.note_view);
+            }
+            search_provider::SearchActivation::Search(terms) => {
+                gtk_handlers::start_search(&window.search_bar, &terms.join(" "));
+            }
+        }
+    });
+}
+
+
+/**
+Show the main window, building it if this is the first time.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `main_window`: The main window, if it was already built.
+# Return:
+The main window.
+ */
+fn present_main_window(app_ref: &gtk::Application, main_window: &RefCell<Option<Rc<MainWindow>>>) -> Rc<MainWindow> {
+    // Once closed the main window is gone, even if note windows kept the app running.
+    let existing = main_window.borrow().clone().filter(|window| window.window.application().is_some());
+    let window = match existing {
+        Some(window) => window,
+        None => {
+            let window = create_app_structure(app_ref);
+            *main_window.borrow_mut() = Some(Rc::clone(&window));
+            window
+        }
+    };
+
+    window.window.present();
+    window
+}
+
+
+/**
+Handle the command line of this or of a new instance of the app, which gets forwarded here.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `command_line`: The invocation, with its options already parsed.
+* `main_window`: The main window, if it was already built.
+# Return:
+The exit code for the invocation.
+ */
+fn handle_command_line(
+    app_ref: &gtk::Application,
+    command_line: &gio::ApplicationCommandLine,
+    main_window: &RefCell<Option<Rc<MainWindow>>>
+) -> i32 {
+    let options = command_line.options_dict();
+    if options.contains(CAPTURE_OPTION) {
+        gtk_handlers::show_quick_capture(app_ref);
+        return 0;
+    }
+
+    let window = present_main_window(app_ref, main_window);
+    let mut exit_code = 0;
+
+    if let Ok(Some(note_title)) = options.lookup::<String>(NOTE_OPTION) {
+        if !gtk_handlers::select_note(&note_title, &window.buttons_box, &window.note_view) {
+            exit_code = 1;
+        }
+    }
+
+    if options.contains(NEW_OPTION) {
+        gtk_handlers::add_button_click_event(&window.buttons_box, &window.note_view, &Rc::new(app_ref.clone()));
+    }
+
+    // What's left besides the program name are note files, opened through the open signal.
+    let files: Vec<gio::File> = command_line
+        .arguments()
+        .iter()
+        .skip(1)
+        .map(|arg| command_line.create_file_for_arg(arg))
+        .collect();
+    if !files.is_empty() {
+        app_ref.open(&files, "");
+    }
+
+    exit_code
+}
+
+
+/**
+Display the note stored in a file.
+# Parameters:
+* `file`: The note's txt file, which must be on the notes path.
+* `main_window`: The window to display the note in.
+ */
+fn open_note_file(file: &gio::File, main_window: &MainWindow) {
+    let note_title = match file.path().as_deref().and_then(notes::note_title_from_path) {
+        Some(title) => title,
+        None => {
+            warn!("{} is not a note in {}", file.parse_name(), notes::notes_dir().display());
+            return;
+        }
+    };
+
+    gtk_handlers::select_note(&note_title, &main_window.buttons_box, &main_window.note_view);
 }
 
 
@@ -36,130 +423,535 @@ fn main() {
 Creates general app structure with buttons functionalities
 # Parameters:
 * `app_ref`: A reference to the gtk application.
+# Return:
+The main window, already shown.
  */
-fn create_app_structure(app_ref: &gtk::Application) {
+fn create_app_structure(app_ref: &gtk::Application) -> Rc<MainWindow> {
     // We need to use multiple clones of the application, so we'll use a
     // smart reference-counted pointer, this will be done with multiple elements.
     // Sometimes an element has to be dereferenced before referencing. e: Rc<T> => *e: T => &*e: &T 
     let app_ref = Rc::new(app_ref.clone());
 
-    // ** Main container for app elements **
+    // ** Root container, the note list on the left and the note contents on the right **
+    let window_state = WindowState::load();
+    let paned = gtk::Paned::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .position(window_state.sidebar_width)
+        .resize_start_child(false)
+        .shrink_start_child(false)
+        .shrink_end_child(false)
+        .wide_handle(true)
+        .build();
+
+    // ** Content container, the note area on the left and the calendar on the right **
+    let content_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .css_name(gtk_handlers::MAIN_CONTAINER)
+        .build();
+
+    // ** Note area with the tabs over the note contents **
     let vertical_box = gtk::Box::builder()
         .orientation(gtk::Orientation::Vertical)
         .spacing(5)
-        .css_name(gtk_handlers::MAIN_CONTAINER)
+        .hexpand(true)
         .build();
 
     // ** Create base level app elements **
     // The buttons box will also have multiple clones, so we use Rc.
     let buttons_box = Rc::new(gtk::Box::builder()
-        .orientation(gtk::Orientation::Horizontal)
+        .orientation(gtk::Orientation::Vertical)
         .spacing(5)
-        .homogeneous(true)
         .css_name(gtk_handlers::BUTTON_BOX)
         .build());
-    
-    let add_button = gtk::Button::with_label(ADD_NOTE_LABEL);
-    let remove_button = gtk::Button::with_label(REMOVE_NOTE_LABEL);
-    let edit_button = gtk::Button::with_label(&format!("{EDIT_NOTE_LABEL} ({})", gtk_handlers::TEXT_EDITOR));
-
-    add_button.style_context().add_class(gtk_handlers::INTERACT_BUTTON_CLASS);
-    remove_button.style_context().add_class(gtk_handlers::INTERACT_BUTTON_CLASS);
-    edit_button.style_context().add_class(gtk_handlers::INTERACT_BUTTON_CLASS);
-
-    // We'll also use Rc for the text_box so we can create reference counted pointers.
-    let text_box = Rc::new(gtk::Label::builder()
-        .label(TEXT_BOX_LABEL)
-        .margin_start(12)
-        .margin_end(12)
-        .margin_bottom(12)
-        .margin_top(12)
-        .height_request(100)
-        .use_markup(true)
-        .css_name(gtk_handlers::CONTENT_BOX)
-        .build());
-    
-    // Arrange main vertical box for display
-    vertical_box.append(&*buttons_box);
-    vertical_box.append(&*text_box);
-    vertical_box.append(&add_button);
-    vertical_box.append(&remove_button);
-    vertical_box.append(&edit_button);
 
-    let note_titles = notes::load_notes();
 
-    // Create a button for each note and append it to the horizontal buttons box.
-    for note_title in note_titles {
-        let note_btn = gtk::Button::with_label(&note_title);
-        note_btn.style_context().add_class(gtk_handlers::NOTE_BUTTON_CLASS);
+    // The note view is already reference counted, so clones of it are cheap.
+    let note_view = note_view::NoteView::new(TEXT_BOX_LABEL);
+    // Every note the view displays gets a tab above it.
+    let note_tabs = note_tabs::NoteTabs::new(&note_view, TEXT_BOX_LABEL);
+
+    // Hidden until the search is toggled from the header bar or typing starts.
+    let search_bar = gtk_handlers::create_search_bar(&buttons_box, &note_view);
 
-        buttons_box.append(&note_btn);
+    // ** Sidebar with the search bar over the note list **
+    let sidebar = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .width_request(SIDEBAR_MIN_WIDTH)
+        .css_name(gtk_handlers::SIDEBAR)
+        .build();
 
-        // Create a reference clone for each button click event
-        let text_box_clone = Rc::clone(&text_box);
+    let note_list = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(&*buttons_box)
+        .build();
 
-        note_btn.connect_clicked(move |_| {
-            notes::display_file_contents(&note_title, &text_box_clone);
-        });
+    sidebar.append(&search_bar);
+    sidebar.append(&note_list);
+
+    // Long notes scroll inside the note area, keeping the tabs above it in place.
+    let note_scroll = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(note_view.widget())
+        .build();
+
+    // Arrange note area for display
+    vertical_box.append(note_tabs.widget());
+    vertical_box.append(&note_scroll);
+
+    // ** Side panel with a calendar marking the days that have a daily note **
+    let side_panel = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .spacing(5)
+        .css_name(gtk_handlers::SIDE_PANEL)
+        .build();
+
+    let calendar = Rc::new(gtk::Calendar::new());
+    side_panel.append(&*calendar);
+
+    content_box.append(&vertical_box);
+    content_box.append(&side_panel);
+
+    paned.set_start_child(Some(&sidebar));
+    paned.set_end_child(Some(&content_box));
+
+    let note_titles = notes::load_notes();
+
+    // Create a button for each note and append it to the note list.
+    for note_title in note_titles {
+        gtk_handlers::append_note_button(&note_title, &buttons_box, &note_view);
     }
 
-    // Create a reference clone for the add button
-    let mut buttons_box_clone = Rc::clone(&buttons_box);
-    let mut app_ref_clone = Rc::clone(&app_ref);
-    add_button.connect_clicked(move |_| {
-        gtk_handlers::add_button_click_event(&buttons_box_clone, &text_box, &app_ref_clone);
+    // Keep the completion badges in sync with the checkboxes of the note view.
+    let buttons_box_clone = Rc::clone(&buttons_box);
+    note_view.connect_tasks_changed(move |note_title| {
+        gtk_handlers::refresh_note_button(&buttons_box_clone, note_title);
     });
 
-    // Another reference clone for the remove button
-    buttons_box_clone = Rc::clone(&buttons_box);
-    app_ref_clone = Rc::clone(&app_ref);
-    remove_button.connect_clicked(move |_| {
-        gtk_handlers::rm_button_click_event(&buttons_box_clone, &app_ref_clone);
-    });
+    // Marks are per month, so they are refreshed whenever the shown month changes.
+    gtk_handlers::mark_daily_notes(&calendar);
+    calendar.connect_month_notify(gtk_handlers::mark_daily_notes);
+    calendar.connect_year_notify(gtk_handlers::mark_daily_notes);
 
-    // Another reference clone for the edit button
-    buttons_box_clone = Rc::clone(&buttons_box);
-    app_ref_clone = Rc::clone(&app_ref);
-    edit_button.connect_clicked(move |_| {
-        gtk_handlers::edit_button_click_event(&buttons_box_clone, &app_ref_clone);
+    let note_view_clone = Rc::clone(&note_view);
+    calendar.connect_day_selected(move |calendar| {
+        gtk_handlers::calendar_day_selected_event(calendar, &note_view_clone);
     });
 
-    // Create window and display it.
+    // Create window and display it, errors show up as toasts over its contents.
     let window = gtk::ApplicationWindow::builder()
         .application(&*app_ref)
-        .default_width(DEFAULT_WIDTH)
+        .default_width(window_state.width)
+        .default_height(window_state.height)
+        .maximized(window_state.maximized)
         .title(APP_NAME)
-        .child(&vertical_box)
+        .child(&error_reporting::toast_overlay(&paned))
         .build();
+    window.set_titlebar(Some(&create_header_bar(&search_bar)));
+    search_bar.set_key_capture_widget(Some(&window));
+
+    add_window_actions(&window, &buttons_box, &note_view, &note_tabs, &calendar, &search_bar, &app_ref);
+    shortcuts::add_shortcuts_window(&window);
 
     window.show();
+
+    let main_window = Rc::new(MainWindow {
+        window,
+        paned,
+        note_scroll,
+        buttons_box,
+        note_view,
+        note_tabs,
+        calendar,
+        search_bar,
+    });
+    restore_note(&main_window, &window_state);
+
+    // A weak reference, the window holding its own state alive would never free it.
+    let main_window_weak = Rc::downgrade(&main_window);
+    main_window.window.connect_close_request(move |_| {
+        if let Some(main_window) = main_window_weak.upgrade() {
+            save_window_state(&main_window);
+        }
+        glib::Propagation::Proceed
+    });
+
+    main_window
 }
 
 
 /**
-Load css styles from a path, or don't load anything on error.
+Remember the size and layout of the main window and the note it shows, for the next session.
+# Parameters:
+* `main_window`: The main window.
  */
-fn load_css() {
-    let css_path = match PathBuf::from_str(CSS_PATH) {
-        Ok(path) => path,
-        Err(e) => {
-            eprintln!("load_css: Failed to get file path for {CSS_PATH}: {e}");
-            return;
-        }
+fn save_window_state(main_window: &MainWindow) {
+    // The default size follows the window as it's resized, leaving out maximizing it.
+    let (width, height) = main_window.window.default_size();
+    let selected_note = main_window.note_view.current_note();
+
+    let window_state = WindowState {
+        width,
+        height,
+        maximized: main_window.window.is_maximized(),
+        sidebar_width: main_window.paned.position(),
+        cursor: selected_note.as_ref().and_then(|_| main_window.note_view.cursor_position()),
+        scroll: if selected_note.is_some() { main_window.note_scroll.vadjustment().value() } else { 0.0 },
+        selected_note: selected_note.unwrap_or_default(),
     };
+    if !window_state.save() {
+        warn!("The window state could not be saved");
+    }
+}
+
 
-    if !css_path.exists() {
-        eprintln!("load_css: css file at path {CSS_PATH} does not exist");
+/**
+Show the note that was open when the app was last closed, scrolled to where it was and with its cursor back in place.
+# Parameters:
+* `main_window`: The main window.
+* `window_state`: The state saved by the last session.
+ */
+fn restore_note(main_window: &MainWindow, window_state: &WindowState) {
+    let note_title = window_state.selected_note.as_str();
+    // The note may have been deleted or renamed since.
+    if note_title.is_empty() || !notes::note_exists(note_title) {
         return;
     }
-    
-    let provider = gtk::CssProvider::new();
-    provider.load_from_path(css_path);
-
-    // Set style context for default display with a high priority
-    gtk::style_context_add_provider_for_display(
-        &gtk::gdk::Display::default().expect("Could not load CSS file"),
-        &provider,
-        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION
-    );
+
+    if !gtk_handlers::select_note(note_title, &main_window.buttons_box, &main_window.note_view) {
+        return;
+    }
+    if let Some(cursor) = window_state.cursor {
+        main_window.note_view.set_cursor_position(cursor);
+    }
+
+    // The note isn't laid out yet, so scrolling waits for the first change of its size.
+    let scroll = window_state.scroll;
+    if scroll <= 0.0 {
+        return;
+    }
+    let adjustment = main_window.note_scroll.vadjustment();
+    let handler_id: Rc<RefCell<Option<glib::SignalHandlerId>>> = Rc::new(RefCell::new(None));
+
+    // Create clones to use inside Fn enclosure
+    let handler_id_clone = Rc::clone(&handler_id);
+    let id = adjustment.connect_changed(move |adjustment| {
+        adjustment.set_value(scroll);
+        if let Some(id) = handler_id_clone.borrow_mut().take() {
+            adjustment.disconnect(id);
+        }
+    });
+    *handler_id.borrow_mut() = Some(id);
+
+    // Showing another note before the first change must not scroll it to the restored position.
+    let restored_title = note_title.to_string();
+    main_window.note_view.connect_note_shown(move |shown_title| {
+        if shown_title == Some(restored_title.as_str()) {
+            return;
+        }
+        if let Some(id) = handler_id.borrow_mut().take() {
+            adjustment.disconnect(id);
+        }
+    });
+}
+
+
+/**
+Add the main window's actions, bound to keyboard shortcuts, the header bar and the menus.
+# Parameters:
+* `window`: The main window.
+* `buttons_box`: The note buttons box.
+* `note_view`: The view that displays notes contents.
+* `note_tabs`: The tabs of the notes opened in the view.
+* `calendar`: The calendar marking the days with a daily note.
+* `search_bar`: The search bar under the header bar.
+* `app_ref`: A reference to the gtk application.
+ */
+fn add_window_actions(
+    window: &gtk::ApplicationWindow,
+    buttons_box: &Rc<gtk::Box>,
+    note_view: &Rc<note_view::NoteView>,
+    note_tabs: &Rc<note_tabs::NoteTabs>,
+    calendar: &Rc<gtk::Calendar>,
+    search_bar: &gtk::SearchBar,
+    app_ref: &Rc<gtk::Application>
+) {
+    let new_note_action = gio::SimpleAction::new(shortcuts::NEW_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    new_note_action.connect_activate(move |_, _| {
+        gtk_handlers::add_button_click_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let delete_note_action = gio::SimpleAction::new(shortcuts::DELETE_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    delete_note_action.connect_activate(move |_, _| {
+        gtk_handlers::rm_button_click_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let edit_note_action = gio::SimpleAction::new(shortcuts::EDIT_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    edit_note_action.connect_activate(move |_, _| {
+        gtk_handlers::edit_button_click_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let rename_note_action = gio::SimpleAction::new(shortcuts::RENAME_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    rename_note_action.connect_activate(move |_, _| {
+        gtk_handlers::rename_note_event(None, &buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let search_action = gio::SimpleAction::new(shortcuts::SEARCH_ACTION, None);
+    let search_bar_clone = search_bar.clone();
+    search_action.connect_activate(move |_, _| {
+        search_bar_clone.set_search_mode(true);
+    });
+
+    let next_note_action = gio::SimpleAction::new(shortcuts::NEXT_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    next_note_action.connect_activate(move |_, _| {
+        gtk_handlers::cycle_note_event(&buttons_box_clone, &note_view_clone, true);
+    });
+
+    let previous_note_action = gio::SimpleAction::new(shortcuts::PREVIOUS_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    previous_note_action.connect_activate(move |_, _| {
+        gtk_handlers::cycle_note_event(&buttons_box_clone, &note_view_clone, false);
+    });
+
+    // Header bar and primary menu actions
+    let today_note_action = gio::SimpleAction::new(shortcuts::TODAY_NOTE_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let calendar_clone = Rc::clone(calendar);
+    today_note_action.connect_activate(move |_, _| {
+        gtk_handlers::today_button_click_event(&buttons_box_clone, &note_view_clone, &calendar_clone);
+    });
+
+    let open_tasks_action = gio::SimpleAction::new(shortcuts::OPEN_TASKS_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    open_tasks_action.connect_activate(move |_, _| {
+        gtk_handlers::open_tasks_button_click_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let set_reminder_action = gio::SimpleAction::new(shortcuts::SET_REMINDER_ACTION, None);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    set_reminder_action.connect_activate(move |_, _| {
+        gtk_handlers::reminder_button_click_event(&note_view_clone, &app_ref_clone);
+    });
+
+    let attach_files_action = gio::SimpleAction::new(shortcuts::ATTACH_FILES_ACTION, None);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    attach_files_action.connect_activate(move |_, _| {
+        gtk_handlers::attach_button_click_event(&note_view_clone, &app_ref_clone);
+    });
+
+    let import_action = gio::SimpleAction::new(shortcuts::IMPORT_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    import_action.connect_activate(move |_, _| {
+        gtk_handlers::import_button_click_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let export_action = gio::SimpleAction::new(shortcuts::EXPORT_ACTION, None);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    export_action.connect_activate(move |_, _| {
+        gtk_handlers::export_button_click_event(&note_view_clone, &app_ref_clone);
+    });
+
+    let trash_action = gio::SimpleAction::new(shortcuts::TRASH_ACTION, None);
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    trash_action.connect_activate(move |_, _| {
+        gtk_handlers::trash_event(&buttons_box_clone, &note_view_clone, &app_ref_clone);
+    });
+
+    let error_log_action = gio::SimpleAction::new(shortcuts::ERROR_LOG_ACTION, None);
+    let app_ref_clone = Rc::clone(app_ref);
+    error_log_action.connect_activate(move |_, _| {
+        gtk_handlers::error_log_button_click_event(&app_ref_clone);
+    });
+
+    let close_tab_action = gio::SimpleAction::new(shortcuts::CLOSE_TAB_ACTION, None);
+    let note_tabs_clone = Rc::clone(note_tabs);
+    close_tab_action.connect_activate(move |_, _| {
+        note_tabs_clone.close_current_tab();
+    });
+
+    let restore_tab_action = gio::SimpleAction::new(shortcuts::RESTORE_TAB_ACTION, None);
+    let note_tabs_clone = Rc::clone(note_tabs);
+    restore_tab_action.connect_activate(move |_, _| {
+        note_tabs_clone.restore_closed_tab();
+    });
+
+    // Note context menu actions, their parameter is the note's title.
+    let note_open_action = gio::SimpleAction::new(shortcuts::NOTE_OPEN_ACTION, Some(glib::VariantTy::STRING));
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    note_open_action.connect_activate(move |_, parameter| {
+        if let Some(note_title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            gtk_handlers::select_note(&note_title, &buttons_box_clone, &note_view_clone);
+        }
+    });
+
+    let note_open_window_action = gio::SimpleAction::new(shortcuts::NOTE_OPEN_WINDOW_ACTION, Some(glib::VariantTy::STRING));
+    let app_ref_clone = Rc::clone(app_ref);
+    note_open_window_action.connect_activate(move |_, parameter| {
+        if let Some(note_title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            note_windows::open_note_window(&app_ref_clone, &note_title);
+        }
+    });
+
+    let note_edit_action = gio::SimpleAction::new(shortcuts::NOTE_EDIT_ACTION, Some(glib::VariantTy::STRING));
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    note_edit_action.connect_activate(move |_, parameter| {
+        if let Some(note_title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            gtk_handlers::edit_note(&note_title, &buttons_box_clone, &note_view_clone);
+        }
+    });
+
+    let note_rename_action = gio::SimpleAction::new(shortcuts::NOTE_RENAME_ACTION, Some(glib::VariantTy::STRING));
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    note_rename_action.connect_activate(move |_, parameter| {
+        if let Some(note_title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            gtk_handlers::rename_note_event(Some(&note_title), &buttons_box_clone, &note_view_clone, &app_ref_clone);
+        }
+    });
+
+    let note_trash_action = gio::SimpleAction::new(shortcuts::NOTE_TRASH_ACTION, Some(glib::VariantTy::STRING));
+    let buttons_box_clone = Rc::clone(buttons_box);
+    let note_view_clone = Rc::clone(note_view);
+    let app_ref_clone = Rc::clone(app_ref);
+    note_trash_action.connect_activate(move |_, parameter| {
+        if let Some(note_title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+            gtk_handlers::trash_note_event(&note_title, &buttons_box_clone, &note_view_clone, &app_ref_clone);
+        }
+    });
+
+    window.add_action(&new_note_action);
+    window.add_action(&delete_note_action);
+    window.add_action(&edit_note_action);
+    window.add_action(&rename_note_action);
+    window.add_action(&search_action);
+    window.add_action(&next_note_action);
+    window.add_action(&previous_note_action);
+    window.add_action(&today_note_action);
+    window.add_action(&open_tasks_action);
+    window.add_action(&set_reminder_action);
+    window.add_action(&attach_files_action);
+    window.add_action(&import_action);
+    window.add_action(&export_action);
+    window.add_action(&trash_action);
+    window.add_action(&error_log_action);
+    window.add_action(&close_tab_action);
+    window.add_action(&restore_tab_action);
+    window.add_action(&note_open_action);
+    window.add_action(&note_open_window_action);
+    window.add_action(&note_edit_action);
+    window.add_action(&note_rename_action);
+    window.add_action(&note_trash_action);
+}
+
+
+/**
+Build the header bar with the new note and today's note buttons, the search toggle and the primary menu.
+# Parameters:
+* `search_bar`: The search bar the toggle shows and hides.
+# Return:
+The header bar, to be set as the window's titlebar.
+ */
+fn create_header_bar(search_bar: &gtk::SearchBar) -> gtk::HeaderBar {
+    let new_note_button = gtk::Button::builder()
+        .icon_name(NEW_NOTE_ICON)
+        .tooltip_text(NEW_NOTE_TOOLTIP)
+        .action_name(format!("{}.{}", shortcuts::WINDOW_SCOPE, shortcuts::NEW_NOTE_ACTION))
+        .build();
+
+    let today_note_button = gtk::Button::builder()
+        .icon_name(TODAY_NOTE_ICON)
+        .tooltip_text(TODAY_NOTE_TOOLTIP)
+        .action_name(format!("{}.{}", shortcuts::WINDOW_SCOPE, shortcuts::TODAY_NOTE_ACTION))
+        .build();
+
+    let search_button = gtk::ToggleButton::builder()
+        .icon_name(SEARCH_ICON)
+        .tooltip_text(SEARCH_TOOLTIP)
+        .build();
+    // The search bar can also be closed with escape, so the toggle follows it.
+    search_button
+        .bind_property("active", search_bar, "search-mode-enabled")
+        .bidirectional()
+        .sync_create()
+        .build();
+
+    let menu_button = gtk::MenuButton::builder()
+        .icon_name(MENU_ICON)
+        .tooltip_text(MENU_TOOLTIP)
+        .menu_model(&primary_menu())
+        .build();
+
+    let header_bar = gtk::HeaderBar::new();
+    header_bar.pack_start(&new_note_button);
+    header_bar.pack_start(&today_note_button);
+    header_bar.pack_start(&search_button);
+    header_bar.pack_end(&menu_button);
+
+    header_bar
+}
+
+
+/**
+Build the primary menu model, shown from the header bar's menu button.
+ */
+fn primary_menu() -> gio::Menu {
+    let window_action = |action: &str| format!("{}.{action}", shortcuts::WINDOW_SCOPE);
+    let app_action = |action: &str| format!("{}.{action}", shortcuts::APP_SCOPE);
+
+    let settings_section = gio::Menu::new();
+    settings_section.append(Some("Preferences"), Some(&app_action(shortcuts::PREFERENCES_ACTION)));
+
+    // Acting on the displayed note
+    let note_section = gio::Menu::new();
+    note_section.append(Some("Set a reminder…"), Some(&window_action(shortcuts::SET_REMINDER_ACTION)));
+    note_section.append(Some("Attach files…"), Some(&window_action(shortcuts::ATTACH_FILES_ACTION)));
+
+    let notes_section = gio::Menu::new();
+    notes_section.append(Some("Open tasks"), Some(&window_action(shortcuts::OPEN_TASKS_ACTION)));
+    notes_section.append(Some("Import notes…"), Some(&window_action(shortcuts::IMPORT_ACTION)));
+    notes_section.append(Some("Export notes…"), Some(&window_action(shortcuts::EXPORT_ACTION)));
+    notes_section.append(Some("Trash"), Some(&window_action(shortcuts::TRASH_ACTION)));
+
+    let help_section = gio::Menu::new();
+    help_section.append(Some("Error log"), Some(&window_action(shortcuts::ERROR_LOG_ACTION)));
+    help_section.append(Some("Keyboard shortcuts"), Some(&window_action(shortcuts::SHOW_SHORTCUTS_ACTION)));
+    help_section.append(Some(&format!("About {APP_NAME}")), Some(&app_action(shortcuts::ABOUT_ACTION)));
+
+    let menu = gio::Menu::new();
+    menu.append_section(None, &settings_section);
+    menu.append_section(None, &note_section);
+    menu.append_section(None, &notes_section);
+    menu.append_section(None, &help_section);
+
+    menu
 }
@@ -0,0 +1,300 @@
+use std::{cell::RefCell, rc::Rc};
+use gtk::{self, gdk, prelude::*};
+
+use notes_core::notes;
+use notes_core::debug;
+use crate::note_view::NoteView;
+use crate::window_registry::WindowRegistry;
+
+const CLOSE_TAB_ICON: &str = "window-close-symbolic";
+const CLOSE_TAB_TOOLTIP: &str = "Close tab";
+/// Longer titles are ellipsized on their tab.
+const TAB_TITLE_CHARS: i32 = 20;
+const MAX_CLOSED_TABS: usize = 20;
+
+// CSS Classes
+const NOTE_TABS_CLASS: &str = "note_tabs";
+
+
+/**
+A tab for each note opened in a note view, over the view. Only the selected tab's note is
+displayed, so switching tabs displays that note in the same view.
+ */
+pub struct NoteTabs {
+    notebook: gtk::Notebook,
+    note_view: Rc<NoteView>,
+    /// Text displayed once every tab is closed.
+    placeholder: String,
+    /// Titles of the closed tabs, the last closed one at the end.
+    closed_tabs: RefCell<Vec<String>>,
+}
+
+
+// Kept so renames and deletions reach every window's tabs.
+thread_local! {
+    static NOTE_TABS: WindowRegistry<NoteTabs> = const { WindowRegistry::new() };
+}
+
+
+impl NoteTabs {
+    /**
+    Create the tabs of a note view, every note the view displays from now on gets a tab.
+    # Parameters:
+    * `note_view`: The view that displays the selected tab's note.
+    * `placeholder`: Text displayed in the view once every tab is closed.
+    # Return:
+    A reference counted pointer to the tabs, as their buttons need to reference them.
+     */
+    pub fn new(note_view: &Rc<NoteView>, placeholder: &str) -> Rc<Self> {
+        // The pages are empty, the notebook only shows the tabs.
+        let notebook = gtk::Notebook::builder()
+            .scrollable(true)
+            .show_border(false)
+            .visible(false)
+            .css_classes([NOTE_TABS_CLASS])
+            .build();
+
+        let tabs = Rc::new(Self {
+            notebook,
+            note_view: Rc::clone(note_view),
+            placeholder: placeholder.to_string(),
+            closed_tabs: RefCell::new(Vec::new()),
+        });
+
+        let view = Rc::downgrade(note_view);
+        tabs.notebook.connect_switch_page(move |_, page, _| {
+            let view = match view.upgrade() {
+                Some(view) => view,
+                None => return,
+            };
+            let note_title = page.widget_name();
+            if view.current_note().as_deref() != Some(note_title.as_str()) {
+                view.show_note(&note_title);
+            }
+        });
+
+        let tabs_weak = Rc::downgrade(&tabs);
+        note_view.connect_note_shown(move |note_title| {
+            if let (Some(tabs), Some(note_title)) = (tabs_weak.upgrade(), note_title) {
+                tabs.select_tab(note_title);
+            }
+        });
+
+        NOTE_TABS.with(|all_tabs| all_tabs.register(&tabs));
+        tabs
+    }
+
+    /**
+    Get the root widget of the tabs to add it to a container.
+     */
+    pub fn widget(&self) -> &gtk::Notebook {
+        &self.notebook
+    }
+
+    /**
+    Close the selected tab.
+     */
+    pub fn close_current_tab(&self) {
+        if let Some(page) = self.notebook.nth_page(self.notebook.current_page()) {
+            self.close_tab(&page, true);
+        }
+    }
+
+    /**
+    Open the last closed tab again, skipping the notes that don't exist anymore.
+    # Return:
+    Boolean stating if a tab was reopened.
+     */
+    pub fn restore_closed_tab(&self) -> bool {
+        loop {
+            let note_title = match self.closed_tabs.borrow_mut().pop() {
+                Some(note_title) => note_title,
+                None => return false,
+            };
+
+            if notes::note_exists(&note_title) {
+                debug!("Reopening the tab of note {note_title}");
+                self.note_view.show_note(&note_title);
+                return true;
+            }
+        }
+    }
+
+    /**
+    Close the tabs of notes that don't exist anymore, e.g. after the notes directory changed.
+    These can't be reopened.
+     */
+    pub fn close_missing_tabs(&self) {
+        let missing: Vec<gtk::Widget> = self.pages()
+            .into_iter()
+            .filter(|page| !notes::note_exists(&page.widget_name()))
+            .collect();
+
+        for page in missing {
+            self.close_tab(&page, false);
+        }
+    }
+
+    /**
+    Select the tab of a note, opening one after the selected tab if it doesn't have one yet.
+     */
+    fn select_tab(self: &Rc<Self>, note_title: &str) {
+        let page = match self.find_page(note_title) {
+            Some(page) => page,
+            None => self.open_tab(note_title),
+        };
+
+        if let Some(page_num) = self.notebook.page_num(&page) {
+            self.notebook.set_current_page(Some(page_num));
+        }
+    }
+
+    /**
+    Open a tab for a note, after the selected tab.
+    # Return:
+    The tab's page.
+     */
+    fn open_tab(self: &Rc<Self>, note_title: &str) -> gtk::Widget {
+        let page: gtk::Widget = gtk::Box::builder().name(note_title).build().upcast();
+
+        let title_label = gtk::Label::builder()
+            .label(note_title)
+            .ellipsize(gtk::pango::EllipsizeMode::End)
+            .max_width_chars(TAB_TITLE_CHARS)
+            .tooltip_text(note_title)
+            .build();
+
+        let close_button = gtk::Button::builder()
+            .icon_name(CLOSE_TAB_ICON)
+            .tooltip_text(CLOSE_TAB_TOOLTIP)
+            .has_frame(false)
+            .build();
+
+        let tab_label = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .build();
+        tab_label.append(&title_label);
+        tab_label.append(&close_button);
+
+        // The close button and middle clicking the tab close it, going through the page since the title may change.
+        let tabs = Rc::downgrade(self);
+        let page_clone = page.clone();
+        close_button.connect_clicked(move |_| {
+            if let Some(tabs) = tabs.upgrade() {
+                tabs.close_tab(&page_clone, true);
+            }
+        });
+
+        let middle_click = gtk::GestureClick::builder().button(gdk::BUTTON_MIDDLE).build();
+        let tabs = Rc::downgrade(self);
+        let page_clone = page.clone();
+        middle_click.connect_released(move |_, _, _, _| {
+            if let Some(tabs) = tabs.upgrade() {
+                tabs.close_tab(&page_clone, true);
+            }
+        });
+        tab_label.add_controller(middle_click);
+
+        let position = self.notebook.current_page().map(|current| current + 1);
+        self.notebook.insert_page(&page, Some(&tab_label), position);
+        self.notebook.set_tab_reorderable(&page, true);
+        self.notebook.set_visible(true);
+        page
+    }
+
+    /**
+    Close a tab, the notebook selects a neighbour if it was the selected one.
+    # Parameters:
+    * `page`: The tab's page.
+    * `remember`: Whether the tab can be reopened.
+     */
+    fn close_tab(&self, page: &gtk::Widget, remember: bool) {
+        let page_num = match self.notebook.page_num(page) {
+            Some(page_num) => page_num,
+            None => return,
+        };
+        let note_title = page.widget_name().to_string();
+
+        if remember {
+            let mut closed_tabs = self.closed_tabs.borrow_mut();
+            closed_tabs.retain(|closed| closed != &note_title);
+            closed_tabs.push(note_title.clone());
+            if closed_tabs.len() > MAX_CLOSED_TABS {
+                closed_tabs.remove(0);
+            }
+        }
+
+        self.notebook.remove_page(Some(page_num));
+
+        // A deleted note keeps its notice in the view instead of the placeholder.
+        if self.notebook.n_pages() == 0 {
+            self.notebook.set_visible(false);
+            if self.note_view.current_note().as_deref() == Some(note_title.as_str()) {
+                self.note_view.show_text(&self.placeholder);
+            }
+        }
+    }
+
+    /**
+    Show a renamed note's tab under its new title, it can also be reopened under it.
+     */
+    fn rename_tab(&self, note_title: &str, new_title: &str) {
+        for closed in self.closed_tabs.borrow_mut().iter_mut().filter(|closed| closed.as_str() == note_title) {
+            *closed = new_title.to_string();
+        }
+
+        let page = match self.find_page(note_title) {
+            Some(page) => page,
+            None => return,
+        };
+        page.set_widget_name(new_title);
+
+        let title_label = self.notebook
+            .tab_label(&page)
+            .and_then(|tab_label| tab_label.first_child())
+            .and_then(|title_label| title_label.downcast::<gtk::Label>().ok());
+        if let Some(title_label) = title_label {
+            title_label.set_text(new_title);
+            title_label.set_tooltip_text(Some(new_title));
+        }
+    }
+
+    fn find_page(&self, note_title: &str) -> Option<gtk::Widget> {
+        self.pages().into_iter().find(|page| page.widget_name() == note_title)
+    }
+
+    fn pages(&self) -> Vec<gtk::Widget> {
+        (0..self.notebook.n_pages()).filter_map(|page_num| self.notebook.nth_page(Some(page_num))).collect()
+    }
+}
+
+
+/**
+Close the tabs of a note that was deleted.
+# Parameters:
+* `note_title`: The title of the note that changed.
+ */
+pub fn sync_note(note_title: &str) {
+    if notes::note_exists(note_title) {
+        return;
+    }
+
+    for tabs in NOTE_TABS.with(WindowRegistry::entries) {
+        tabs.close_missing_tabs();
+    }
+}
+
+
+/**
+Show a renamed note under its new title in the tabs that had it open.
+Has to be called before the note view displays the new title, or it would get a second tab.
+# Parameters:
+* `note_title`: The old title of the note.
+* `new_title`: The new title of the note.
+ */
+pub fn note_renamed(note_title: &str, new_title: &str) {
+    for tabs in NOTE_TABS.with(WindowRegistry::entries) {
+        tabs.rename_tab(note_title, new_title);
+    }
+}
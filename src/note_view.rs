@@ -0,0 +1,384 @@
+use std::{cell::RefCell, rc::{Rc, Weak}, path::{Path, PathBuf}, time::Duration};
+use gtk::{self, gdk, gio, glib, prelude::*};
+
+use notes_core::Note;
+use notes_core::attachments;
+use notes_core::checklists::{self, TaskItem};
+use notes_core::preferences::Preferences;
+use notes_core::warn;
+use crate::gtk_handlers::CONTENT_BOX;
+use crate::error_reporting::report_error;
+
+const ERROR_LABEL_TEXT: &str = "<ERROR: Could not read note content>";
+
+// CSS Classes
+pub const NOTE_TEXT_CLASS: &str = "note_text";
+pub const TASK_CLASS: &str = "task_check";
+const ATTACHMENT_CLASS: &str = "attachment";
+
+// Attachments
+const THUMBNAIL_SIZE: i32 = 96;
+const FILE_ICON: &str = "text-x-generic";
+
+/// Callbacks run with the title of a note whose tasks were ticked in the view.
+type TasksChangedHandlers = RefCell<Vec<Box<dyn Fn(&str)>>>;
+/// Callbacks run with the title of the note the view now shows, `None` for plain text.
+type NoteShownHandlers = RefCell<Vec<Box<dyn Fn(Option<&str>)>>>;
+
+
+/**
+Displays a note's contents, with its task lines as checkboxes that are written back to the note
+and its attachments as thumbnails. Files dropped on the view are attached to the displayed note.
+ */
+pub struct NoteView {
+    container: gtk::Box,
+    text_view: gtk::TextView,
+    tasks_box: gtk::Box,
+    attachments_box: gtk::FlowBox,
+    current_note: RefCell<Option<String>>,
+    tasks_changed_handlers: TasksChangedHandlers,
+    note_shown_handlers: NoteShownHandlers,
+}
+
+
+impl NoteView {
+    /**
+    Create a new note view.
+    # Parameters:
+    * `placeholder`: Text shown until a note is displayed.
+    # Return:
+    A reference counted pointer to the view, as its checkboxes need to reference it.
+     */
+    pub fn new(placeholder: &str) -> Rc<Self> {
+        let container = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(5)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_bottom(12)
+            .margin_top(12)
+            .height_request(100)
+            .css_name(CONTENT_BOX)
+            .build();
+
+        // Read only, but with a visible cursor so its text can be copied and its position restored.
+        let text_view = gtk::TextView::builder()
+            .editable(false)
+            .cursor_visible(true)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .css_classes([NOTE_TEXT_CLASS])
+            .build();
+        text_view.buffer().set_text(placeholder);
+
+        let tasks_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(2)
+            .build();
+
+        let attachments_box = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .column_spacing(5)
+            .row_spacing(5)
+            .build();
+
+        container.append(&text_view);
+        container.append(&tasks_box);
+        container.append(&attachments_box);
+
+        let view = Rc::new(Self {
+            container,
+            text_view,
+            tasks_box,
+            attachments_box,
+            current_note: RefCell::new(None),
+            tasks_changed_handlers: RefCell::new(Vec::new()),
+            note_shown_handlers: RefCell::new(Vec::new()),
+        });
+
+        view.accept_dropped_files();
+        view
+    }
+
+    /**
+    Get the root widget of the view to add it to a container.
+     */
+    pub fn widget(&self) -> &gtk::Box {
+        &self.container
+    }
+
+    /**
+    Get the title of the note being displayed, if any.
+     */
+    pub fn current_note(&self) -> Option<String> {
+        self.current_note.borrow().clone()
+    }
+
+    /**
+    Get the character offset of the cursor in the displayed text.
+    # Return:
+    The offset, or `None` if no note is displayed.
+     */
+    pub fn cursor_position(&self) -> Option<i32> {
+        self.current_note.borrow().as_ref().map(|_| self.text_view.buffer().cursor_position())
+    }
+
+    /**
+    Place the cursor in the displayed text, e.g. to restore it from the last session.
+    # Parameters:
+    * `position`: Character offset of the cursor, past the end puts it at the end.
+     */
+    pub fn set_cursor_position(&self, position: i32) {
+        let buffer = self.text_view.buffer();
+        let position = position.clamp(0, buffer.char_count());
+        buffer.place_cursor(&buffer.iter_at_offset(position));
+    }
+
+    /**
+    Display a note from the default path.
+    # Parameters:
+    * `note_title`: The title of the note to display.
+     */
+    pub fn show_note(self: &Rc<Self>, note_title: &str) {
+        *self.current_note.borrow_mut() = Some(note_title.to_string());
+
+        match Note::load(note_title) {
+            Some(note) => {
+                self.text_view.buffer().set_text(&format!("{} ...", note.body()));
+                self.fill_tasks(note_title, &note.tasks);
+                self.fill_attachments(note_title);
+            }
+            None => {
+                self.text_view.buffer().set_text(ERROR_LABEL_TEXT);
+                self.clear_tasks();
+                self.clear_attachments();
+            }
+        }
+
+        self.note_shown(Some(note_title));
+    }
+
+    /**
+    Display the current note again, e.g. after it changed on disk.
+     */
+    pub fn refresh(self: &Rc<Self>) {
+        if let Some(note_title) = self.current_note() {
+            self.show_note(&note_title);
+        }
+    }
+
+    /**
+    Display a plain text instead of a note.
+    # Parameters:
+    * `text`: The text to display.
+     */
+    pub fn show_text(&self, text: &str) {
+        *self.current_note.borrow_mut() = None;
+        self.text_view.buffer().set_text(text);
+        self.clear_tasks();
+        self.clear_attachments();
+        self.note_shown(None);
+    }
+
+    /**
+    Attach files to the displayed note and show them.
+    # Parameters:
+    * `files`: The paths of the files to attach.
+    # Return:
+    Boolean stating if at least one file was attached.
+     */
+    pub fn attach_files(self: &Rc<Self>, files: &[PathBuf]) -> bool {
+        let note_title = match self.current_note() {
+            Some(title) => title,
+            None => return false,
+        };
+
+        let attached = files
+            .iter()
+            .filter(|file| attachments::add_attachment(&note_title, file).is_some())
+            .count();
+
+        self.fill_attachments(&note_title);
+        attached > 0
+    }
+
+    /**
+    Register a handler called with the note title whenever a task is toggled from this view.
+    # Parameters:
+    * `handler`: The function to call.
+     */
+    pub fn connect_tasks_changed<F: Fn(&str) + 'static>(&self, handler: F) {
+        self.tasks_changed_handlers.borrow_mut().push(Box::new(handler));
+    }
+
+    /**
+    Register a handler called whenever the view displays a note, with its title, or a plain text, with `None`.
+    # Parameters:
+    * `handler`: The function to call.
+     */
+    pub fn connect_note_shown<F: Fn(Option<&str>) + 'static>(&self, handler: F) {
+        self.note_shown_handlers.borrow_mut().push(Box::new(handler));
+    }
+
+    fn note_shown(&self, note_title: Option<&str>) {
+        for handler in self.note_shown_handlers.borrow().iter() {
+            handler(note_title);
+        }
+    }
+
+    fn accept_dropped_files(self: &Rc<Self>) {
+        let drop_target = gtk::DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+        let view = Rc::downgrade(self);
+
+        drop_target.connect_drop(move |_, value, _, _| {
+            let view = match view.upgrade() {
+                Some(view) => view,
+                None => return false,
+            };
+            let file_list = match value.get::<gdk::FileList>() {
+                Ok(file_list) => file_list,
+                Err(e) => {
+                    warn!("Dropped value is not a file list: {e}");
+                    return false;
+                }
+            };
+
+            let paths: Vec<PathBuf> = file_list.files().iter().filter_map(|file| file.path()).collect();
+            view.attach_files(&paths)
+        });
+
+        self.container.add_controller(drop_target);
+    }
+
+    fn clear_attachments(&self) {
+        while let Some(child) = self.attachments_box.first_child() {
+            self.attachments_box.remove(&child);
+        }
+    }
+
+    fn fill_attachments(self: &Rc<Self>, note_title: &str) {
+        self.clear_attachments();
+
+        for path in attachments::list_attachments(note_title) {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            // Images are shown inline, other files just get an icon.
+            let preview: gtk::Widget = if attachments::is_image(&path) {
+                let picture = gtk::Picture::for_filename(&path);
+                picture.set_can_shrink(true);
+                picture.set_size_request(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+                picture.upcast()
+            } else {
+                let icon = gtk::Image::from_icon_name(FILE_ICON);
+                icon.set_pixel_size(THUMBNAIL_SIZE / 2);
+                icon.upcast()
+            };
+
+            let open_button = gtk::Button::builder()
+                .child(&preview)
+                .tooltip_text(&file_name)
+                .build();
+            let remove_button = gtk::Button::with_label("Remove");
+
+            let tile = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(2)
+                .css_classes([ATTACHMENT_CLASS])
+                .build();
+            tile.append(&open_button);
+            tile.append(&gtk::Label::new(Some(&file_name)));
+            tile.append(&remove_button);
+
+            open_button.connect_clicked(move |_| open_attachment(&path));
+
+            let view = Rc::downgrade(self);
+            let title = note_title.to_string();
+            remove_button.connect_clicked(move |_| {
+                if !attachments::remove_attachment(&title, &file_name) {
+                    return;
+                }
+
+                if let Some(view) = view.upgrade() {
+                    view.fill_attachments(&title);
+                }
+            });
+
+            self.attachments_box.insert(&tile, -1);
+        }
+    }
+
+    fn clear_tasks(&self) {
+        while let Some(child) = self.tasks_box.first_child() {
+            self.tasks_box.remove(&child);
+        }
+    }
+
+    fn fill_tasks(self: &Rc<Self>, note_title: &str, tasks: &[TaskItem]) {
+        self.clear_tasks();
+
+        for task in tasks {
+            let check = gtk::CheckButton::with_label(&task.text);
+            check.set_active(task.done);
+            check.style_context().add_class(TASK_CLASS);
+
+            // A weak reference avoids a cycle between the view and its own checkboxes.
+            let view = Rc::downgrade(self);
+            let title = note_title.to_string();
+            let line = task.line;
+
+            check.connect_toggled(move |check| {
+                // The state is read when saving, so quick toggles end up saving the last one.
+                let view = view.clone();
+                let title = title.clone();
+                let check = check.clone();
+                let save = move || save_task(&view, &title, line, check.is_active());
+
+                match Preferences::load().autosave_delay {
+                    0 => save(),
+                    delay => {
+                        glib::timeout_add_local_once(Duration::from_millis(delay.into()), save);
+                    }
+                }
+            });
+
+            self.tasks_box.append(&check);
+        }
+    }
+}
+
+
+/**
+Write a toggled task back to its note and tell the view's handlers about it.
+# Parameters:
+* `view`: The view the task was toggled in, which may be gone by now.
+* `note_title`: The title of the note the task belongs to.
+* `line`: The line of the task in the note.
+* `done`: Whether the task is checked.
+ */
+fn save_task(view: &Weak<NoteView>, note_title: &str, line: usize, done: bool) {
+    if !checklists::set_task_done(note_title, line, done) {
+        return;
+    }
+
+    if let Some(view) = view.upgrade() {
+        for handler in view.tasks_changed_handlers.borrow().iter() {
+            handler(note_title);
+        }
+    }
+}
+
+
+/**
+Open an attachment with the desktop's default application for it.
+# Parameters:
+* `path`: The path of the attachment.
+ */
+fn open_attachment(path: &Path) {
+    let uri = gio::File::for_path(path).uri();
+    if let Err(e) = gio::AppInfo::launch_default_for_uri(&uri, None::<&gio::AppLaunchContext>) {
+        let file_name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+        report_error("open_attachment", &format!("Could not open {file_name}: {e}"));
+    }
+}
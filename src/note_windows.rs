@@ -0,0 +1,127 @@
+use std::rc::Rc;
+use gtk::{self, glib, prelude::*};
+
+use notes_core::notes;
+use notes_core::debug;
+use crate::note_view::NoteView;
+use crate::gtk_handlers::{NOTE_DELETED, NOTE_NOT_FOUND};
+use crate::error_reporting::{self, report_error};
+use crate::window_registry::WindowRegistry;
+
+const NOTE_WINDOW_WIDTH: i32 = 500;
+const NOTE_WINDOW_HEIGHT: i32 = 400;
+const DELETED_TITLE_SUFFIX: &str = " (deleted)";
+
+
+/**
+A window showing a single note, next to the main window.
+ */
+struct NoteWindow {
+    window: gtk::ApplicationWindow,
+    note_view: Rc<NoteView>,
+}
+
+
+// Every window reads and writes the notes directory, which is the one model they all share: changes
+// made from any window reach the others through the directory monitor, which calls `sync_note`.
+thread_local! {
+    static NOTE_WINDOWS: WindowRegistry<NoteWindow> = const { WindowRegistry::new() };
+}
+
+
+/**
+Open a note in a window of its own, or bring its window up if it already has one.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `note_title`: The title of the note to open.
+# Return:
+Boolean stating if the note exists and its window is shown.
+ */
+pub fn open_note_window(app_ref: &gtk::Application, note_title: &str) -> bool {
+    if !notes::note_exists(note_title) {
+        report_error("open_note_window", &format!("{NOTE_NOT_FOUND} \"{note_title}\""));
+        return false;
+    }
+
+    if let Some(note_window) = windows_showing(note_title).first() {
+        note_window.window.present();
+        return true;
+    }
+
+    let note_view = NoteView::new("");
+    note_view.show_note(note_title);
+
+    let note_scroll = gtk::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Never)
+        .vexpand(true)
+        .child(note_view.widget())
+        .build();
+
+    let window = gtk::ApplicationWindow::builder()
+        .application(app_ref)
+        .default_width(NOTE_WINDOW_WIDTH)
+        .default_height(NOTE_WINDOW_HEIGHT)
+        .title(note_title)
+        .child(&error_reporting::toast_overlay(&note_scroll))
+        .build();
+    window.set_titlebar(Some(&gtk::HeaderBar::new()));
+
+    let note_window = Rc::new(NoteWindow { window: window.clone(), note_view });
+    NOTE_WINDOWS.with(|windows| windows.register(&note_window));
+
+    // The window keeps its note window alive until it's destroyed, and it's forgotten as soon as
+    // it's closed, so the note can be opened in a new one again.
+    let note_window_clone = Rc::clone(&note_window);
+    window.connect_close_request(move |_| {
+        NOTE_WINDOWS.with(|windows| windows.unregister(&note_window_clone));
+        glib::Propagation::Proceed
+    });
+
+    debug!("Opening note {note_title} in a new window");
+    window.present();
+    true
+}
+
+
+/**
+Bring the windows showing a note up to date after the note changed on disk.
+A deleted note stays in its windows with a notice, so nothing disappears from under the user.
+# Parameters:
+* `note_title`: The title of the note that changed.
+ */
+pub fn sync_note(note_title: &str) {
+    let exists = notes::note_exists(note_title);
+    for note_window in windows_showing(note_title) {
+        if exists {
+            note_window.note_view.refresh();
+        } else {
+            note_window.note_view.show_text(NOTE_DELETED);
+            note_window.window.set_title(Some(&format!("{note_title}{DELETED_TITLE_SUFFIX}")));
+        }
+    }
+}
+
+
+/**
+Show a renamed note under its new title in the windows that had it open.
+# Parameters:
+* `note_title`: The old title of the note.
+* `new_title`: The new title of the note.
+ */
+pub fn note_renamed(note_title: &str, new_title: &str) {
+    for note_window in windows_showing(note_title) {
+        note_window.note_view.show_note(new_title);
+        note_window.window.set_title(Some(new_title));
+    }
+}
+
+
+/**
+Get the open note windows showing a note.
+ */
+fn windows_showing(note_title: &str) -> Vec<Rc<NoteWindow>> {
+    NOTE_WINDOWS.with(WindowRegistry::entries)
+        .into_iter()
+        .filter(|note_window| note_window.note_view.current_note().as_deref() == Some(note_title))
+        .collect()
+}
@@ -0,0 +1,146 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+use gtk::{self, cairo, prelude::*};
+
+use notes_core::notes;
+use notes_core::error;
+
+// Page layout, in points
+const PAGE_MARGIN: f64 = 36.0;
+const FONT_SIZE: f64 = 11.0;
+const TITLE_FONT_SIZE: f64 = 16.0;
+const LINE_HEIGHT: f64 = 15.0;
+const FONT_FAMILY: &str = "Sans";
+
+
+/**
+A line of the exported document, titles are drawn bigger and in bold.
+ */
+#[derive(Clone)]
+struct PrintLine {
+    text: String,
+    is_title: bool,
+}
+
+
+/**
+Export notes to a PDF file, one after another, using a gtk print operation.
+# Parameters:
+* `note_titles`: The titles of the notes to export.
+* `output`: Path of the PDF file to write.
+* `parent`: Window the operation belongs to, if any.
+# Return:
+Boolean stating if the PDF was written.
+ */
+pub fn export_pdf(note_titles: &[String], output: &Path, parent: Option<&gtk::Window>) -> bool {
+    let mut source_lines: Vec<PrintLine> = Vec::new();
+    for note_title in note_titles {
+        let contents = match notes::read_note(note_title) {
+            Some(contents) => contents,
+            None => {
+                error!("Could not export note {note_title}");
+                return false;
+            }
+        };
+
+        source_lines.push(PrintLine { text: note_title.clone(), is_title: true });
+        source_lines.extend(contents.lines().map(|line| PrintLine { text: line.to_string(), is_title: false }));
+        source_lines.push(PrintLine { text: String::new(), is_title: false });
+    }
+
+    let operation = gtk::PrintOperation::new();
+    operation.set_export_filename(output);
+    operation.set_unit(gtk::Unit::Points);
+
+    // Lines can only be wrapped once the page size is known, on begin-print.
+    let wrapped_lines: Rc<RefCell<Vec<PrintLine>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let wrapped_clone = Rc::clone(&wrapped_lines);
+    operation.connect_begin_print(move |operation, context| {
+        let cr = context.cairo_context();
+        let max_width = context.width() - 2.0 * PAGE_MARGIN;
+
+        let mut wrapped = wrapped_clone.borrow_mut();
+        wrapped.clear();
+        for line in &source_lines {
+            set_font(&cr, line.is_title);
+            wrapped.extend(wrap_line(&cr, &line.text, max_width).into_iter().map(|text| PrintLine {
+                text,
+                is_title: line.is_title,
+            }));
+        }
+
+        let lines_per_page = lines_per_page(context.height());
+        let pages = wrapped.len().div_ceil(lines_per_page).max(1);
+        operation.set_n_pages(pages as i32);
+    });
+
+    operation.connect_draw_page(move |_, context, page_number| {
+        let cr = context.cairo_context();
+        let lines_per_page = lines_per_page(context.height());
+        let wrapped = wrapped_lines.borrow();
+
+        let first_line = page_number as usize * lines_per_page;
+        for (index, line) in wrapped.iter().skip(first_line).take(lines_per_page).enumerate() {
+            set_font(&cr, line.is_title);
+            cr.move_to(PAGE_MARGIN, PAGE_MARGIN + LINE_HEIGHT * (index as f64 + 1.0));
+            if let Err(e) = cr.show_text(&line.text) {
+                error!("Error drawing line on page {page_number}: {e}");
+            }
+        }
+    });
+
+    match operation.run(gtk::PrintOperationAction::Export, parent) {
+        Ok(_) => true,
+        Err(e) => {
+            error!("Error exporting PDF at path {output:?}: {e}");
+            false
+        }
+    }
+}
+
+
+/**
+Select the font used for a line.
+ */
+fn set_font(cr: &cairo::Context, is_title: bool) {
+    if is_title {
+        cr.select_font_face(FONT_FAMILY, cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(TITLE_FONT_SIZE);
+    } else {
+        cr.select_font_face(FONT_FAMILY, cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(FONT_SIZE);
+    }
+}
+
+
+/**
+Get how many lines fit on a page of the given height.
+ */
+fn lines_per_page(page_height: f64) -> usize {
+    (((page_height - 2.0 * PAGE_MARGIN) / LINE_HEIGHT).floor() as usize).max(1)
+}
+
+
+/**
+Split a line into words and group them into lines no wider than `max_width`.
+Words wider than a whole line are kept on their own line.
+ */
+fn wrap_line(cr: &cairo::Context, text: &str, max_width: f64) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split(' ') {
+        let candidate = if current.is_empty() { word.to_string() } else { format!("{current} {word}") };
+        let width = cr.text_extents(&candidate).map(|extents| extents.x_advance()).unwrap_or(0.0);
+
+        if width > max_width && !current.is_empty() {
+            result.push(current);
+            current = word.to_string();
+        } else {
+            current = candidate;
+        }
+    }
+
+    result.push(current);
+    result
+}
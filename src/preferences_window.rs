@@ -0,0 +1,447 @@
+use std::{cell::RefCell, rc::Rc};
+use gtk::{self, gdk, gio, pango, prelude::*};
+
+use notes_core::notes::{self, SortOrder, NOTES_PATH};
+use notes_core::preferences::{Preferences, Theme, MAX_AUTOSAVE_DELAY};
+use notes_core::user_themes;
+use crate::note_view::{NOTE_TEXT_CLASS, TASK_CLASS};
+use crate::theme;
+use crate::stylesheet;
+use crate::gtk_handlers;
+use crate::error_reporting::{self, report_error, show_validation_error, clear_validation_error};
+
+// Window defaults
+const PREFERENCES_DIAG: &str = "Preferences";
+const CHOOSE_NOTES_DIR_DIAG: &str = "Choose the notes directory";
+const PREFERENCES_WINDOW_NAME: &str = "preferences_window";
+const PREFERENCES_WIDTH: i32 = 500;
+const EDITOR_PLACEHOLDER: &str = "$VISUAL, $EDITOR or the desktop's default";
+const AUTOSAVE_DELAY_STEP: f64 = 100.0;
+const NO_USER_THEME_OPTION: &str = "(None)";
+
+// Error messages
+const INVALID_NOTES_DIR: &str = "Notes can't be stored in this directory";
+const FAILED_TO_SAVE_PREFERENCES: &str = "Could not save the preferences";
+const FAILED_TO_OPEN_THEMES_DIR: &str = "Could not open the themes directory";
+
+
+/**
+What has to be brought up to date in the main window after the preferences changed.
+Theme and font changes are applied to every window right away.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum PreferenceChange {
+    /// Notes are now stored somewhere else.
+    NotesDirectory,
+    /// The note buttons have to be rebuilt, e.g. to follow a new sort order.
+    NoteList,
+}
+
+
+// The font provider is only touched from the gtk main thread.
+thread_local! {
+    static FONT_PROVIDER: RefCell<Option<gtk::CssProvider>> = const { RefCell::new(None) };
+}
+
+
+/**
+Show the preferences window, changes are saved and applied as soon as they're made.
+Only one preferences window is open at a time.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+* `on_change`: Called after a change that the main window has to follow.
+ */
+pub fn show_preferences(app_ref: &gtk::Application, on_change: impl Fn(PreferenceChange) + 'static) {
+    let existing = app_ref.windows().into_iter().find(|window| window.widget_name() == PREFERENCES_WINDOW_NAME);
+    if let Some(window) = existing {
+        window.present();
+        return;
+    }
+
+    let preferences = Rc::new(RefCell::new(Preferences::load()));
+    let on_change: Rc<dyn Fn(PreferenceChange)> = Rc::new(on_change);
+
+    let grid = gtk::Grid::builder()
+        .row_spacing(8)
+        .column_spacing(12)
+        .margin_start(12)
+        .margin_end(12)
+        .margin_top(12)
+        .margin_bottom(12)
+        .build();
+
+    let window = gtk::Window::builder()
+        .application(app_ref)
+        .title(PREFERENCES_DIAG)
+        .name(PREFERENCES_WINDOW_NAME)
+        .default_width(PREFERENCES_WIDTH)
+        .destroy_with_parent(true)
+        .child(&error_reporting::toast_overlay(&grid))
+        .build();
+    window.set_transient_for(gtk_handlers::parent_window(app_ref).as_ref());
+
+    // Notes directory, switched once the entry is left or a directory is chosen.
+    let notes_dir_entry = gtk::Entry::builder()
+        .text(&preferences.borrow().notes_dir)
+        .placeholder_text(NOTES_PATH)
+        .hexpand(true)
+        .build();
+    let notes_dir_validation = error_reporting::validation_label(&notes_dir_entry);
+    let choose_dir_button = gtk::Button::with_label("Choose…");
+    let notes_dir_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .build();
+    notes_dir_box.append(&notes_dir_entry);
+    notes_dir_box.append(&choose_dir_button);
+
+    // Editor command, empty picks one from the environment.
+    let editor_entry = gtk::Entry::builder()
+        .text(&preferences.borrow().editor)
+        .placeholder_text(EDITOR_PLACEHOLDER)
+        .hexpand(true)
+        .build();
+
+    let theme_labels: Vec<&str> = Theme::ALL.iter().map(Theme::label).collect();
+    let theme_dropdown = gtk::DropDown::from_strings(&theme_labels);
+    theme_dropdown.set_selected(position_of(&Theme::ALL, &preferences.borrow().theme));
+
+    // User themes, the one in use is listed even if its file is gone.
+    let mut user_theme_names = user_themes::list_user_themes();
+    let current_user_theme = preferences.borrow().user_theme.clone();
+    if !current_user_theme.is_empty() && !user_theme_names.contains(&current_user_theme) {
+        user_theme_names.push(current_user_theme.clone());
+    }
+    let mut user_theme_options = vec![NO_USER_THEME_OPTION];
+    user_theme_options.extend(user_theme_names.iter().map(String::as_str));
+    let user_theme_dropdown = gtk::DropDown::from_strings(&user_theme_options);
+    let selected_user_theme = user_theme_names.iter().position(|name| *name == current_user_theme);
+    user_theme_dropdown.set_selected(selected_user_theme.map_or(0, |position| position as u32 + 1));
+    let themes_dir_button = gtk::Button::with_label("Open folder");
+    let user_theme_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .build();
+    user_theme_box.append(&user_theme_dropdown);
+    user_theme_box.append(&themes_dir_button);
+
+    let font_button = gtk::FontButton::new();
+    if !preferences.borrow().font.is_empty() {
+        font_button.set_font(&preferences.borrow().font);
+    }
+    let default_font_button = gtk::Button::with_label("Default");
+    default_font_button.set_sensitive(!preferences.borrow().font.is_empty());
+    let font_box = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(5)
+        .build();
+    font_box.append(&font_button);
+    font_box.append(&default_font_button);
+
+    let autosave_spin = gtk::SpinButton::with_range(0.0, MAX_AUTOSAVE_DELAY as f64, AUTOSAVE_DELAY_STEP);
+    autosave_spin.set_value(preferences.borrow().autosave_delay as f64);
+    autosave_spin.set_halign(gtk::Align::Start);
+
+    let sort_labels: Vec<&str> = SortOrder::ALL.iter().map(SortOrder::label).collect();
+    let sort_dropdown = gtk::DropDown::from_strings(&sort_labels);
+    sort_dropdown.set_selected(position_of(&SortOrder::ALL, &preferences.borrow().sort_order));
+
+    let confirm_switch = gtk::Switch::builder()
+        .active(preferences.borrow().confirm_delete)
+        .halign(gtk::Align::Start)
+        .build();
+
+    let rows: [(&str, gtk::Widget); 8] = [
+        ("Notes directory", notes_dir_box.upcast()),
+        ("Editor command", editor_entry.clone().upcast()),
+        ("Theme", theme_dropdown.clone().upcast()),
+        ("Custom stylesheet", user_theme_box.upcast()),
+        ("Note font", font_box.upcast()),
+        ("Autosave delay (ms)", autosave_spin.clone().upcast()),
+        ("Sort notes by", sort_dropdown.clone().upcast()),
+        ("Ask before deleting", confirm_switch.clone().upcast()),
+    ];
+    let mut row = 0;
+    for (label, widget) in rows {
+        grid.attach(&gtk::Label::builder().label(label).xalign(0.0).build(), 0, row, 1, 1);
+        grid.attach(&widget, 1, row, 1, 1);
+        row += 1;
+
+        // The validation message goes right under the notes directory.
+        if row == 1 {
+            grid.attach(&notes_dir_validation, 1, row, 1, 1);
+            row += 1;
+        }
+    }
+
+    // Create clones to use inside Fn enclosures
+    let preferences_clone = Rc::clone(&preferences);
+    let on_change_clone = Rc::clone(&on_change);
+    let validation_clone = notes_dir_validation.clone();
+    connect_entry_committed(&notes_dir_entry, move |entry| {
+        set_notes_dir(entry, &validation_clone, &preferences_clone, &on_change_clone);
+    });
+
+    let notes_dir_entry_clone = notes_dir_entry.clone();
+    let window_clone = window.clone();
+    let preferences_clone = Rc::clone(&preferences);
+    let on_change_clone = Rc::clone(&on_change);
+    choose_dir_button.connect_clicked(move |_| {
+        let chooser = gtk::FileChooserDialog::new(
+            Some(CHOOSE_NOTES_DIR_DIAG),
+            Some(&window_clone),
+            gtk::FileChooserAction::SelectFolder,
+            &[("Cancel", gtk::ResponseType::Cancel), ("Choose", gtk::ResponseType::Accept)]
+        );
+        chooser.set_modal(true);
+
+        let notes_dir_entry = notes_dir_entry_clone.clone();
+        let validation = notes_dir_validation.clone();
+        let preferences = Rc::clone(&preferences_clone);
+        let on_change = Rc::clone(&on_change_clone);
+        chooser.connect_response(move |chooser, response| {
+            let dir = chooser.file().and_then(|file| file.path());
+            if let (gtk::ResponseType::Accept, Some(dir)) = (response, dir) {
+                notes_dir_entry.set_text(&dir.to_string_lossy());
+                set_notes_dir(&notes_dir_entry, &validation, &preferences, &on_change);
+            }
+
+            chooser.close();
+            chooser.destroy();
+        });
+
+        chooser.show();
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    let on_change_clone = Rc::clone(&on_change);
+    connect_entry_committed(&editor_entry, move |entry| {
+        let editor = entry.text().trim().to_string();
+        if preferences_clone.borrow().editor == editor {
+            return;
+        }
+        // The note menus name the editor, so they're rebuilt.
+        if change_preferences(&preferences_clone, |preferences| preferences.editor = editor) {
+            on_change_clone(PreferenceChange::NoteList);
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    theme_dropdown.connect_selected_notify(move |dropdown| {
+        let theme = Theme::ALL.get(dropdown.selected() as usize).copied().unwrap_or_default();
+        if change_preferences(&preferences_clone, |preferences| preferences.theme = theme) {
+            theme::set_theme(theme);
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    user_theme_dropdown.connect_selected_notify(move |dropdown| {
+        // The first option is no user theme at all.
+        let user_theme = match dropdown.selected() {
+            0 => String::new(),
+            selected => user_theme_names.get(selected as usize - 1).cloned().unwrap_or_default(),
+        };
+        let user_theme_clone = user_theme.clone();
+        if change_preferences(&preferences_clone, |preferences| preferences.user_theme = user_theme_clone) {
+            stylesheet::set_user_theme(&user_theme);
+        }
+    });
+
+    themes_dir_button.connect_clicked(|_| {
+        let opened = user_themes::create_themes_dir().map(|dir| {
+            gio::AppInfo::launch_default_for_uri(&gio::File::for_path(dir).uri(), None::<&gio::AppLaunchContext>)
+        });
+        match opened {
+            Some(Ok(())) => {}
+            Some(Err(e)) => report_error("show_preferences", &format!("{FAILED_TO_OPEN_THEMES_DIR}: {e}")),
+            None => report_error("show_preferences", FAILED_TO_OPEN_THEMES_DIR),
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    let default_font_button_clone = default_font_button.clone();
+    font_button.connect_font_set(move |font_button| {
+        let font = font_button.font_desc().map(|font_desc| font_desc.to_string()).unwrap_or_default();
+        if change_preferences(&preferences_clone, |preferences| preferences.font = font) {
+            default_font_button_clone.set_sensitive(true);
+            apply_note_font(&preferences_clone.borrow().font);
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    default_font_button.connect_clicked(move |default_font_button| {
+        if change_preferences(&preferences_clone, |preferences| preferences.font.clear()) {
+            default_font_button.set_sensitive(false);
+            apply_note_font("");
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    autosave_spin.connect_value_changed(move |spin| {
+        let delay = spin.value_as_int().max(0) as u32;
+        change_preferences(&preferences_clone, |preferences| preferences.autosave_delay = delay);
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    let on_change_clone = Rc::clone(&on_change);
+    sort_dropdown.connect_selected_notify(move |dropdown| {
+        let sort_order = SortOrder::ALL.get(dropdown.selected() as usize).copied().unwrap_or_default();
+        if change_preferences(&preferences_clone, |preferences| preferences.sort_order = sort_order) {
+            on_change_clone(PreferenceChange::NoteList);
+        }
+    });
+
+    let preferences_clone = Rc::clone(&preferences);
+    confirm_switch.connect_active_notify(move |switch| {
+        let confirm = switch.is_active();
+        change_preferences(&preferences_clone, |preferences| preferences.confirm_delete = confirm);
+    });
+
+    window.present();
+}
+
+
+/**
+Set the font of the note contents in every window.
+# Parameters:
+* `font`: A Pango font description, empty for the theme's font.
+ */
+pub fn apply_note_font(font: &str) {
+    let display = match gdk::Display::default() {
+        Some(display) => display,
+        None => return,
+    };
+
+    FONT_PROVIDER.with(|font_provider| {
+        let mut font_provider = font_provider.borrow_mut();
+        let provider = font_provider.get_or_insert_with(|| {
+            let provider = gtk::CssProvider::new();
+            // Above the app's stylesheet, so the font preference wins.
+            gtk::style_context_add_provider_for_display(&display, &provider, gtk::STYLE_PROVIDER_PRIORITY_USER);
+            provider
+        });
+        provider.load_from_data(&font_css(font));
+    });
+}
+
+
+/**
+Build the stylesheet that sets the font of the note contents.
+# Parameters:
+* `font`: A Pango font description, empty for the theme's font.
+# Return:
+The stylesheet, empty if no font is set.
+ */
+fn font_css(font: &str) -> String {
+    if font.trim().is_empty() {
+        return String::new();
+    }
+
+    let font_desc = pango::FontDescription::from_string(font);
+    let mut declarations = Vec::new();
+    if let Some(family) = font_desc.family() {
+        declarations.push(format!("font-family: \"{}\";", family.replace('"', "\\\"")));
+    }
+    if font_desc.size() > 0 {
+        let size = font_desc.size() as f64 / pango::SCALE as f64;
+        let unit = if font_desc.is_size_absolute() { "px" } else { "pt" };
+        declarations.push(format!("font-size: {size}{unit};"));
+    }
+    let style = match font_desc.style() {
+        pango::Style::Italic => "italic",
+        pango::Style::Oblique => "oblique",
+        _ => "normal",
+    };
+    declarations.push(format!("font-style: {style};"));
+    declarations.push(format!("font-weight: {};", font_weight(font_desc.weight())));
+
+    format!(".{NOTE_TEXT_CLASS}, .{TASK_CLASS} {{ {} }}", declarations.join(" "))
+}
+
+
+/**
+Get the CSS weight of a Pango weight.
+ */
+fn font_weight(weight: pango::Weight) -> i32 {
+    match weight {
+        pango::Weight::Thin => 100,
+        pango::Weight::Ultralight => 200,
+        pango::Weight::Light | pango::Weight::Semilight => 300,
+        pango::Weight::Book | pango::Weight::Normal => 400,
+        pango::Weight::Medium => 500,
+        pango::Weight::Semibold => 600,
+        pango::Weight::Bold => 700,
+        pango::Weight::Ultrabold => 800,
+        pango::Weight::Heavy | pango::Weight::Ultraheavy => 900,
+        _ => 400,
+    }
+}
+
+
+/**
+Switch the notes directory to the one in the entry, pointing out directories that can't be used.
+ */
+fn set_notes_dir(
+    entry: &gtk::Entry,
+    validation: &gtk::Label,
+    preferences: &RefCell<Preferences>,
+    on_change: &Rc<dyn Fn(PreferenceChange)>
+) {
+    let notes_dir = entry.text().trim().to_string();
+    if preferences.borrow().notes_dir == notes_dir {
+        clear_validation_error(entry, validation);
+        return;
+    }
+
+    let dir = if notes_dir.is_empty() { NOTES_PATH } else { notes_dir.as_str() };
+    if !notes::prepare_notes_dir(dir) {
+        show_validation_error(entry, validation, INVALID_NOTES_DIR);
+        return;
+    }
+    clear_validation_error(entry, validation);
+
+    if change_preferences(preferences, |preferences| preferences.notes_dir = notes_dir) {
+        on_change(PreferenceChange::NotesDirectory);
+    }
+}
+
+
+/**
+Change the preferences and save them, reporting it if they could not be written.
+# Return:
+Boolean stating if the preferences were saved.
+ */
+fn change_preferences(preferences: &RefCell<Preferences>, change: impl FnOnce(&mut Preferences)) -> bool {
+    let mut preferences = preferences.borrow_mut();
+    change(&mut preferences);
+
+    if !preferences.save() {
+        report_error("change_preferences", FAILED_TO_SAVE_PREFERENCES);
+        return false;
+    }
+    true
+}
+
+
+/**
+Call a handler once an entry's text is done being edited: on enter or when the focus leaves it.
+ */
+fn connect_entry_committed(entry: &gtk::Entry, handler: impl Fn(&gtk::Entry) + 'static) {
+    let handler = Rc::new(handler);
+
+    let handler_clone = Rc::clone(&handler);
+    entry.connect_activate(move |entry| handler_clone(entry));
+
+    let focus_controller = gtk::EventControllerFocus::new();
+    let entry_clone = entry.clone();
+    focus_controller.connect_leave(move |_| handler(&entry_clone));
+    entry.add_controller(focus_controller);
+}
+
+
+/**
+Get the position of a value in a list of choices, for a dropdown.
+ */
+fn position_of<T: PartialEq>(choices: &[T], value: &T) -> u32 {
+    choices.iter().position(|choice| choice == value).unwrap_or(0) as u32
+}
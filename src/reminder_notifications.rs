@@ -0,0 +1,64 @@
+use std::rc::Rc;
+use gtk::{self, gio, glib, prelude::*};
+
+use notes_core::reminders::{self, Clock, Reminder};
+use notes_core::{info, warn};
+
+// Longest wait between checks, so reminders added meanwhile are not missed.
+const MAX_CHECK_SECONDS: u32 = 60;
+const NOTIFICATION_TITLE: &str = "Note reminder";
+
+
+/**
+Send notifications for due reminders, including the ones missed while the app was closed,
+and keep checking until the application quits.
+
+# Parameters:
+* `app`: A reference to the gtk application that sends the notifications.
+* `clock`: The clock deciding when reminders are due.
+ */
+pub fn watch_reminders(app: &gtk::Application, clock: Rc<dyn Clock>) {
+    reminders::take_due_reminders(&*clock, |reminder| send_reminder_notification(app, reminder));
+
+    // Wake up right when the next reminder is due, but never sleep too long.
+    let delay = reminders::time_until_next(&reminders::list_reminders(), &*clock)
+        .and_then(|left| u32::try_from(left.num_seconds()).ok())
+        .map(|seconds| seconds.clamp(1, MAX_CHECK_SECONDS))
+        .unwrap_or(MAX_CHECK_SECONDS);
+
+    let app_clone = app.clone();
+    glib::timeout_add_seconds_local_once(delay, move || {
+        watch_reminders(&app_clone, clock);
+    });
+}
+
+
+/**
+Send a desktop notification for a reminder.
+# Parameters:
+* `app`: A reference to the gtk application.
+* `reminder`: The reminder that fired.
+# Return:
+Boolean stating if the notification was sent, which needs the application to be registered.
+ */
+fn send_reminder_notification(app: &gtk::Application, reminder: &Reminder) -> bool {
+    if !app.is_registered() {
+        warn!("Application is not registered, can't send the reminder for note {}", reminder.note_title);
+        return false;
+    }
+
+    let notification = gio::Notification::new(NOTIFICATION_TITLE);
+
+    let body = match reminder.task {
+        Some(_) => format!("{} ({})", reminder.description(), reminder.note_title),
+        None => reminder.description(),
+    };
+    notification.set_body(Some(&body));
+
+    let task_id = reminder.task.as_deref().unwrap_or_default();
+    let notification_id = format!("reminder-{}-{task_id}", reminder.note_title);
+
+    info!("Sending reminder for note {}", reminder.note_title);
+    app.send_notification(Some(&notification_id), &notification);
+    true
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use gtk::{gio, glib, prelude::*};
+
+use notes_core::search;
+use notes_core::Note;
+use notes_core::{error, debug};
+
+
+// Must match the object path in the search provider's ini file
+const SEARCH_PROVIDER_PATH: &str = "/org/zeke_desktop_app/SearchProvider";
+const SEARCH_PROVIDER_INTERFACE: &str = "org.gnome.Shell.SearchProvider2";
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg type="as" name="previous_results" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg type="as" name="identifiers" direction="in"/>
+      <arg type="aa{sv}" name="metas" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg type="s" name="identifier" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+// D-Bus error names
+const INVALID_ARGS_ERROR: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const UNKNOWN_METHOD_ERROR: &str = "org.freedesktop.DBus.Error.UnknownMethod";
+
+// Amount of results shown in the overview
+const MAX_RESULTS: usize = 10;
+
+
+/**
+What the user picked in the shell's overview.
+ */
+pub enum SearchActivation {
+    /// A result, with the title of its note.
+    Note(String),
+    /// The app's own search, with the terms typed in the overview.
+    Search(Vec<String>),
+}
+
+
+/**
+Export the desktop search provider interface, so notes show up in the shell's overview search.
+# Parameters:
+* `connection`: The connection to export the interface on.
+* `on_activate`: Called with what the user picked in the overview.
+# Return:
+The registration id or `None` if the interface could not be exported.
+ */
+pub fn register_search_provider(
+    connection: &gio::DBusConnection,
+    on_activate: impl Fn(SearchActivation) + 'static
+) -> Option<gio::RegistrationId> {
+    let node_info = match gio::DBusNodeInfo::for_xml(INTERFACE_XML) {
+        Ok(node_info) => node_info,
+        Err(e) => {
+            error!("Error parsing interface description: {e}");
+            return None;
+        }
+    };
+    let interface_info = node_info.lookup_interface(SEARCH_PROVIDER_INTERFACE)?;
+
+    let registration = connection
+        .register_object(SEARCH_PROVIDER_PATH, &interface_info)
+        .method_call(move |_, _, _, _, method_name, parameters, invocation| {
+            debug!("Search provider {method_name}{parameters}");
+            handle_method_call(method_name, &parameters, invocation, &on_activate);
+        })
+        .build();
+
+    match registration {
+        Ok(id) => Some(id),
+        Err(e) => {
+            error!("Error exporting object at path {SEARCH_PROVIDER_PATH}: {e}");
+            None
+        }
+    }
+}
+
+
+/**
+Run a method of the search provider interface and send its reply.
+ */
+fn handle_method_call(
+    method_name: &str,
+    parameters: &glib::Variant,
+    invocation: gio::DBusMethodInvocation,
+    on_activate: &dyn Fn(SearchActivation)
+) {
+    match method_name {
+        "GetInitialResultSet" => match parameters.get::<(Vec<String>,)>() {
+            Some((terms,)) => invocation.return_value(Some(&(search_results(&terms, None),).to_variant())),
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, "Expected search terms"),
+        },
+        "GetSubsearchResultSet" => match parameters.get::<(Vec<String>, Vec<String>)>() {
+            Some((previous_results, terms)) => {
+                let results = search_results(&terms, Some(previous_results.as_slice()));
+                invocation.return_value(Some(&(results,).to_variant()));
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, "Expected previous results and search terms"),
+        },
+        "GetResultMetas" => match parameters.get::<(Vec<String>,)>() {
+            Some((identifiers,)) => {
+                let metas: Vec<HashMap<String, glib::Variant>> =
+                    identifiers.iter().filter_map(|note_title| result_meta(note_title)).collect();
+                invocation.return_value(Some(&(metas,).to_variant()));
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, "Expected result identifiers"),
+        },
+        "ActivateResult" => match parameters.get::<(String, Vec<String>, u32)>() {
+            Some((note_title, _, _)) => {
+                on_activate(SearchActivation::Note(note_title));
+                invocation.return_value(None);
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, "Expected a result identifier"),
+        },
+        "LaunchSearch" => match parameters.get::<(Vec<String>, u32)>() {
+            Some((terms, _)) => {
+                on_activate(SearchActivation::Search(terms));
+                invocation.return_value(None);
+            }
+            None => invocation.return_dbus_error(INVALID_ARGS_ERROR, "Expected search terms"),
+        },
+        _ => invocation.return_dbus_error(UNKNOWN_METHOD_ERROR, &format!("Unknown method {method_name}")),
+    }
+}
+
+
+/**
+Search the notes for the shell, results are identified by note title.
+# Parameters:
+* `terms`: The terms typed in the overview.
+* `previous_results`: The results of the previous search, when the terms were only refined.
+# Return:
+The titles of the matching notes, best matches first.
+ */
+fn search_results(terms: &[String], previous_results: Option<&[String]>) -> Vec<String> {
+    let terms = search::query_terms(&terms.join(" "));
+
+    search::search_notes(&terms)
+        .into_iter()
+        .map(|found| found.note_title)
+        .filter(|note_title| previous_results.is_none_or(|previous| previous.contains(note_title)))
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+
+/**
+Describe a result for the shell, with the note's first line of text as description.
+ */
+fn result_meta(note_title: &str) -> Option<HashMap<String, glib::Variant>> {
+    let note = Note::load(note_title)?;
+    let body = note.body();
+    let description = body.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+
+    let mut meta: HashMap<String, glib::Variant> = HashMap::new();
+    meta.insert("id".to_string(), note.title.to_variant());
+    meta.insert("name".to_string(), note.title.to_variant());
+    meta.insert("description".to_string(), description.to_variant());
+    Some(meta)
+}
@@ -0,0 +1,200 @@
+use gtk::{self, glib, prelude::*};
+
+use notes_core::config;
+use notes_core::warn;
+
+// Actions, window ones are added to the main window and app ones to the application
+pub const NEW_NOTE_ACTION: &str = "new-note";
+pub const DELETE_NOTE_ACTION: &str = "delete-note";
+pub const EDIT_NOTE_ACTION: &str = "edit-note";
+pub const RENAME_NOTE_ACTION: &str = "rename-note";
+pub const SEARCH_ACTION: &str = "search";
+pub const NEXT_NOTE_ACTION: &str = "next-note";
+pub const PREVIOUS_NOTE_ACTION: &str = "previous-note";
+pub const CLOSE_TAB_ACTION: &str = "close-tab";
+pub const RESTORE_TAB_ACTION: &str = "restore-closed-tab";
+pub const QUIT_ACTION: &str = "quit";
+// Actions without a default shortcut, reached from the header bar and the primary menu
+pub const TODAY_NOTE_ACTION: &str = "today-note";
+pub const OPEN_TASKS_ACTION: &str = "open-tasks";
+pub const SET_REMINDER_ACTION: &str = "set-reminder";
+pub const ATTACH_FILES_ACTION: &str = "attach-files";
+pub const PREFERENCES_ACTION: &str = "preferences";
+pub const IMPORT_ACTION: &str = "import";
+pub const EXPORT_ACTION: &str = "export";
+pub const TRASH_ACTION: &str = "trash";
+pub const ERROR_LOG_ACTION: &str = "error-log";
+pub const ABOUT_ACTION: &str = "about";
+// Actions taking the title of the note they act on, used by the note context menus
+pub const NOTE_OPEN_ACTION: &str = "note-open";
+pub const NOTE_OPEN_WINDOW_ACTION: &str = "note-open-window";
+pub const NOTE_EDIT_ACTION: &str = "note-edit";
+pub const NOTE_RENAME_ACTION: &str = "note-rename";
+pub const NOTE_TRASH_ACTION: &str = "note-trash";
+// Added by gtk once the window has a help overlay
+pub const SHOW_SHORTCUTS_ACTION: &str = "show-help-overlay";
+
+// Action scopes
+pub const WINDOW_SCOPE: &str = "win";
+pub const APP_SCOPE: &str = "app";
+
+/// Settings named `shortcut.<detailed action>` replace the default accelerators, e.g.
+/// `shortcut.win.search=<Primary>k`. Several accelerators are separated by spaces.
+pub const SHORTCUT_SETTING_PREFIX: &str = "shortcut.";
+/// Setting value that removes every accelerator of an action.
+const NO_SHORTCUT: &str = "none";
+
+const SHORTCUTS_WINDOW_ID: &str = "shortcuts_window";
+
+
+/**
+An action with keyboard accelerators, as listed in the shortcuts window.
+ */
+pub struct Shortcut {
+    /// Where the action is added, `win` or `app`.
+    pub scope: &'static str,
+    pub action: &'static str,
+    pub title: &'static str,
+    /// The shortcuts window group the action is listed under.
+    pub group: &'static str,
+    default_accels: &'static [&'static str],
+}
+
+
+/// Every action with accelerators, in the order they're listed.
+pub const SHORTCUTS: [Shortcut; 11] = [
+    Shortcut { scope: WINDOW_SCOPE, action: NEW_NOTE_ACTION, title: "New note", group: "Notes", default_accels: &["<Primary>n"] },
+    Shortcut { scope: WINDOW_SCOPE, action: EDIT_NOTE_ACTION, title: "Edit a note", group: "Notes", default_accels: &["<Primary>e"] },
+    Shortcut { scope: WINDOW_SCOPE, action: RENAME_NOTE_ACTION, title: "Rename a note", group: "Notes", default_accels: &["F2"] },
+    Shortcut { scope: WINDOW_SCOPE, action: DELETE_NOTE_ACTION, title: "Delete a note", group: "Notes", default_accels: &["<Primary>Delete"] },
+    Shortcut { scope: WINDOW_SCOPE, action: SEARCH_ACTION, title: "Search notes", group: "Navigation", default_accels: &["<Primary>f"] },
+    Shortcut { scope: WINDOW_SCOPE, action: NEXT_NOTE_ACTION, title: "Next note", group: "Navigation", default_accels: &["<Primary>Page_Down"] },
+    Shortcut { scope: WINDOW_SCOPE, action: PREVIOUS_NOTE_ACTION, title: "Previous note", group: "Navigation", default_accels: &["<Primary>Page_Up"] },
+    Shortcut { scope: WINDOW_SCOPE, action: CLOSE_TAB_ACTION, title: "Close tab", group: "Tabs", default_accels: &["<Primary>w"] },
+    Shortcut { scope: WINDOW_SCOPE, action: RESTORE_TAB_ACTION, title: "Reopen closed tab", group: "Tabs", default_accels: &["<Primary><Shift>t"] },
+    Shortcut { scope: WINDOW_SCOPE, action: SHOW_SHORTCUTS_ACTION, title: "Keyboard shortcuts", group: "General", default_accels: &["<Primary>question"] },
+    Shortcut { scope: APP_SCOPE, action: QUIT_ACTION, title: "Quit", group: "General", default_accels: &["<Primary>q"] },
+];
+
+
+impl Shortcut {
+    /**
+    Get the action's name with its scope, e.g. `win.new-note`.
+     */
+    pub fn detailed_action(&self) -> String {
+        format!("{}.{}", self.scope, self.action)
+    }
+
+    /**
+    Get the accelerators of the action, from the settings if it was remapped.
+    Accelerators gtk can't parse are ignored, falling back to the defaults if none is left.
+    # Return:
+    The accelerators, empty if the shortcut was turned off.
+     */
+    pub fn accels(&self) -> Vec<String> {
+        let setting = match config::get_setting(&format!("{SHORTCUT_SETTING_PREFIX}{}", self.detailed_action())) {
+            Some(setting) => setting,
+            None => return self.default_accels.iter().map(|accel| accel.to_string()).collect(),
+        };
+        if setting.trim() == NO_SHORTCUT {
+            return Vec::new();
+        }
+
+        let accels: Vec<String> = setting
+            .split_whitespace()
+            .filter(|accel| {
+                let valid = gtk::accelerator_parse(*accel).is_some();
+                if !valid {
+                    warn!("Ignoring invalid accelerator {accel} for {}", self.detailed_action());
+                }
+                valid
+            })
+            .map(str::to_string)
+            .collect();
+
+        if accels.is_empty() {
+            return self.default_accels.iter().map(|accel| accel.to_string()).collect();
+        }
+        accels
+    }
+}
+
+
+/**
+Bind the accelerators of every action, the actions themselves may be added later.
+# Parameters:
+* `app_ref`: A reference to the gtk application.
+ */
+pub fn set_accels(app_ref: &gtk::Application) {
+    for shortcut in SHORTCUTS.iter() {
+        let accels = shortcut.accels();
+        let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+        app_ref.set_accels_for_action(&shortcut.detailed_action(), &accels);
+    }
+}
+
+
+/**
+Give a window a shortcuts window listing every action, opened with the "win.show-help-overlay" action.
+# Parameters:
+* `window_ref`: A reference to the main window.
+ */
+pub fn add_shortcuts_window(window_ref: &gtk::ApplicationWindow) {
+    // Sections and groups can only be added through a builder before gtk 4.14.
+    let builder = gtk::Builder::from_string(&shortcuts_window_ui());
+    match builder.object::<gtk::ShortcutsWindow>(SHORTCUTS_WINDOW_ID) {
+        Some(shortcuts_window) => window_ref.set_help_overlay(Some(&shortcuts_window)),
+        None => warn!("The shortcuts window could not be built"),
+    }
+}
+
+
+/**
+Build the builder description of the shortcuts window, with the current accelerators.
+ */
+fn shortcuts_window_ui() -> String {
+    let mut groups = String::new();
+    let mut current_group: Option<&str> = None;
+
+    for shortcut in SHORTCUTS.iter() {
+        let accels = shortcut.accels();
+        if accels.is_empty() {
+            continue;
+        }
+
+        if current_group != Some(shortcut.group) {
+            if current_group.is_some() {
+                groups.push_str("</object></child>");
+            }
+            groups.push_str(&format!(
+                "<child><object class=\"GtkShortcutsGroup\"><property name=\"title\">{}</property>",
+                glib::markup_escape_text(shortcut.group)
+            ));
+            current_group = Some(shortcut.group);
+        }
+
+        groups.push_str(&format!(
+            "<child><object class=\"GtkShortcutsShortcut\">\
+            <property name=\"title\">{}</property>\
+            <property name=\"accelerator\">{}</property>\
+            </object></child>",
+            glib::markup_escape_text(shortcut.title),
+            glib::markup_escape_text(&accels.join(" "))
+        ));
+    }
+    if current_group.is_some() {
+        groups.push_str("</object></child>");
+    }
+
+    format!(
+        "<interface>\
+        <object class=\"GtkShortcutsWindow\" id=\"{SHORTCUTS_WINDOW_ID}\">\
+        <property name=\"modal\">1</property>\
+        <child><object class=\"GtkShortcutsSection\">\
+        <property name=\"section-name\">shortcuts</property>\
+        {groups}\
+        </object></child>\
+        </object>\
+        </interface>"
+    )
+}
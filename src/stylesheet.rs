@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+use std::path::Path;
+use gtk::{self, gdk, gio, prelude::*};
+
+use notes_core::user_themes;
+use notes_core::{debug, info, warn};
+use crate::error_reporting::report_error;
+use crate::theme;
+
+// The app's stylesheet and palettes, all in the same directory
+const CSS_DIR: &str = "css";
+pub const CSS_PATH: &str = "css/style.css";
+const STYLESHEET_EXTENSION: &str = "css";
+/// User themes go over the app's stylesheet and palettes, and below the font preference.
+const USER_THEME_PRIORITY: u32 = gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1;
+
+
+/**
+The loaded stylesheets and the monitors that reload them, kept for as long as the app runs.
+ */
+struct Stylesheets {
+    app_provider: gtk::CssProvider,
+    user_theme_provider: gtk::CssProvider,
+    /// The user theme in use, none if empty.
+    user_theme: String,
+    /// Only held so the monitors keep running.
+    _monitors: Vec<gio::FileMonitor>,
+}
+
+
+// Stylesheets are only touched from the gtk main thread.
+thread_local! {
+    static STYLESHEETS: RefCell<Option<Stylesheets>> = const { RefCell::new(None) };
+}
+
+
+/**
+Load the app's stylesheet and a user theme, and reload them whenever they change on disk.
+# Parameters:
+* `user_theme`: The name of the user theme to load, none if empty.
+ */
+pub fn init(user_theme: &str) {
+    let display = match gdk::Display::default() {
+        Some(display) => display,
+        None => {
+            warn!("There is no display to load the stylesheets for");
+            return;
+        }
+    };
+
+    let app_provider = gtk::CssProvider::new();
+    let user_theme_provider = gtk::CssProvider::new();
+    report_parsing_errors(&app_provider);
+    report_parsing_errors(&user_theme_provider);
+    gtk::style_context_add_provider_for_display(&display, &app_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+    gtk::style_context_add_provider_for_display(&display, &user_theme_provider, USER_THEME_PRIORITY);
+
+    let mut monitors = Vec::new();
+    monitors.extend(watch_dir(Path::new(CSS_DIR), app_stylesheet_changed));
+    if let Some(themes_dir) = user_themes::themes_dir() {
+        monitors.extend(watch_dir(&themes_dir, user_theme_changed));
+    }
+
+    STYLESHEETS.with(|stylesheets| {
+        *stylesheets.borrow_mut() = Some(Stylesheets {
+            app_provider,
+            user_theme_provider,
+            user_theme: user_theme.trim().to_string(),
+            _monitors: monitors,
+        });
+    });
+
+    load_app_stylesheet();
+    load_user_theme();
+}
+
+
+/**
+Switch to another user theme, or back to the app's own look.
+# Parameters:
+* `user_theme`: The name of the user theme, none if empty.
+ */
+pub fn set_user_theme(user_theme: &str) {
+    STYLESHEETS.with(|stylesheets| {
+        if let Some(stylesheets) = stylesheets.borrow_mut().as_mut() {
+            stylesheets.user_theme = user_theme.trim().to_string();
+        }
+    });
+    load_user_theme();
+}
+
+
+/**
+Show the errors found while parsing a stylesheet to the user, with the file and line they're in.
+# Parameters:
+* `provider`: The provider that loads the stylesheet.
+ */
+pub fn report_parsing_errors(provider: &gtk::CssProvider) {
+    provider.connect_parsing_error(|_, section, error| {
+        report_error("stylesheet", &format!("{section}: {error}"));
+    });
+}
+
+
+/**
+Load the app's stylesheet from `CSS_PATH`.
+ */
+fn load_app_stylesheet() {
+    if !Path::new(CSS_PATH).exists() {
+        warn!("css file at path {CSS_PATH} does not exist");
+        return;
+    }
+
+    STYLESHEETS.with(|stylesheets| {
+        if let Some(stylesheets) = stylesheets.borrow().as_ref() {
+            stylesheets.app_provider.load_from_path(CSS_PATH);
+        }
+    });
+}
+
+
+/**
+Load the user theme in use, or clear the provider if there is none or its file is gone.
+ */
+fn load_user_theme() {
+    STYLESHEETS.with(|stylesheets| {
+        let stylesheets = stylesheets.borrow();
+        let stylesheets = match stylesheets.as_ref() {
+            Some(stylesheets) => stylesheets,
+            None => return,
+        };
+
+        match user_themes::user_theme_path(&stylesheets.user_theme) {
+            Some(path) if path.exists() => {
+                info!("Loading user theme {}", stylesheets.user_theme);
+                stylesheets.user_theme_provider.load_from_path(path);
+            }
+            Some(path) => {
+                warn!("User theme at path {path:?} does not exist");
+                stylesheets.user_theme_provider.load_from_data("");
+            }
+            None => stylesheets.user_theme_provider.load_from_data(""),
+        }
+    });
+}
+
+
+/**
+Reload what changed in the app's stylesheet directory.
+ */
+fn app_stylesheet_changed(file_name: &str) {
+    if Path::new(CSS_PATH).file_name().is_some_and(|name| name == file_name) {
+        debug!("Reloading {CSS_PATH}");
+        load_app_stylesheet();
+    } else {
+        // Anything else in there is a palette.
+        theme::reload_palette();
+    }
+}
+
+
+/**
+Reload the user theme if its file is the one that changed.
+ */
+fn user_theme_changed(file_name: &str) {
+    let user_theme = STYLESHEETS.with(|stylesheets| {
+        stylesheets.borrow().as_ref().map(|stylesheets| stylesheets.user_theme.clone()).unwrap_or_default()
+    });
+
+    let path = user_themes::user_theme_path(&user_theme);
+    if path.as_deref().and_then(Path::file_name).is_some_and(|name| name == file_name) {
+        load_user_theme();
+    }
+}
+
+
+/**
+Watch a directory for stylesheets being written, created or deleted.
+# Parameters:
+* `dir`: The directory to watch, which is created if missing so it can be watched.
+* `on_change`: Called with the name of the stylesheet that changed.
+# Return:
+The monitor, which has to be kept alive, or `None` if the directory can't be watched.
+ */
+fn watch_dir(dir: &Path, on_change: fn(&str)) -> Option<gio::FileMonitor> {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        warn!("Error creating directory {dir:?}, changes in it won't be reloaded: {e}");
+        return None;
+    }
+
+    let monitor = match gio::File::for_path(dir).monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            warn!("Error watching directory {dir:?}, changes in it won't be reloaded: {e}");
+            return None;
+        }
+    };
+
+    monitor.connect_changed(move |_, file, _, event| {
+        // Editors that save by replacing the file show up as a new one.
+        if !matches!(
+            event,
+            gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted
+        ) {
+            return;
+        }
+
+        let path = match file.path() {
+            Some(path) => path,
+            None => return,
+        };
+        if path.extension().is_none_or(|extension| extension != STYLESHEET_EXTENSION) {
+            return;
+        }
+        if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+            on_change(file_name);
+        }
+    });
+
+    Some(monitor)
+}
@@ -0,0 +1,242 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use gtk::{self, gdk, gio, glib, prelude::*};
+
+use notes_core::preferences::Theme;
+use notes_core::{debug, warn};
+use crate::stylesheet;
+
+// Palettes, each one defines every `@notes_*` color used by css/style.css
+const LIGHT_PALETTE_PATH: &str = "css/palette-light.css";
+const DARK_PALETTE_PATH: &str = "css/palette-dark.css";
+
+// gtk settings that tell whether the desktop uses a dark theme
+const PREFER_DARK_THEME_PROPERTY: &str = "gtk-application-prefer-dark-theme";
+const THEME_NAME_PROPERTY: &str = "gtk-theme-name";
+const DARK_THEME_SUFFIXES: [&str; 2] = ["-dark", ":dark"];
+
+// The desktop portal's color scheme, followed when the theme is set to follow the system
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_SETTINGS_INTERFACE: &str = "org.freedesktop.portal.Settings";
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+const COLOR_SCHEME_KEY: &str = "color-scheme";
+const PREFER_DARK_COLOR_SCHEME: u32 = 1;
+
+
+/**
+Where the palette colors come from, shared by every window.
+ */
+struct ThemeState {
+    /// The theme picked in the preferences.
+    theme: Cell<Theme>,
+    /// Whether the desktop portal asks for dark colors.
+    portal_prefers_dark: Cell<bool>,
+    palette_provider: RefCell<Option<gtk::CssProvider>>,
+    /// The palette loaded in the provider, to skip reloading the same one.
+    dark_palette_loaded: Cell<Option<bool>>,
+}
+
+
+// The theme is only touched from the gtk main thread.
+thread_local! {
+    static THEME_STATE: ThemeState = const {
+        ThemeState {
+            theme: Cell::new(Theme::System),
+            portal_prefers_dark: Cell::new(false),
+            palette_provider: RefCell::new(None),
+            dark_palette_loaded: Cell::new(None),
+        }
+    };
+}
+
+
+/**
+Load the palette of a theme and keep following the desktop's color scheme from now on.
+# Parameters:
+* `app_ref`: A reference to the gtk application, already registered on the session bus.
+* `theme`: The theme picked in the preferences.
+ */
+pub fn init(app_ref: &gtk::Application, theme: Theme) {
+    if let Some(settings) = gtk::Settings::default() {
+        settings.connect_notify_local(Some(PREFER_DARK_THEME_PROPERTY), |_, _| update_palette());
+        settings.connect_notify_local(Some(THEME_NAME_PROPERTY), |_, _| update_palette());
+    }
+
+    if let Some(connection) = app_ref.dbus_connection() {
+        watch_portal_color_scheme(&connection);
+    }
+
+    set_theme(theme);
+}
+
+
+/**
+Switch to another theme, the palette is swapped in every window right away.
+# Parameters:
+* `theme`: The theme to use.
+ */
+pub fn set_theme(theme: Theme) {
+    THEME_STATE.with(|state| state.theme.set(theme));
+    update_gtk_theme();
+    update_palette();
+}
+
+
+/**
+Load the current palette again, e.g. after it was edited.
+ */
+pub fn reload_palette() {
+    THEME_STATE.with(|state| state.dark_palette_loaded.set(None));
+    update_palette();
+}
+
+
+/**
+Check if the dark palette should be used.
+ */
+fn is_dark() -> bool {
+    THEME_STATE.with(|state| match state.theme.get() {
+        Theme::Light => false,
+        Theme::Dark => true,
+        Theme::System => system_prefers_dark(state),
+    })
+}
+
+
+/**
+Tell gtk's own theme which variant to use, so the widgets match the palette.
+ */
+fn update_gtk_theme() {
+    let settings = match gtk::Settings::default() {
+        Some(settings) => settings,
+        None => return,
+    };
+
+    let (theme, portal_prefers_dark) = THEME_STATE.with(|state| (state.theme.get(), state.portal_prefers_dark.get()));
+    match theme {
+        Theme::Light => settings.set_gtk_application_prefer_dark_theme(false),
+        Theme::Dark => settings.set_gtk_application_prefer_dark_theme(true),
+        // Without the portal saying otherwise, the desktop's own setting is used.
+        Theme::System if portal_prefers_dark => settings.set_gtk_application_prefer_dark_theme(true),
+        Theme::System => settings.reset_property(PREFER_DARK_THEME_PROPERTY),
+    }
+}
+
+
+/**
+Load the palette matching the current theme, unless it's already loaded.
+Named colors are looked up across providers, so the stylesheet picks up the new palette.
+ */
+fn update_palette() {
+    let dark = is_dark();
+
+    THEME_STATE.with(|state| {
+        if state.dark_palette_loaded.get() == Some(dark) {
+            return;
+        }
+
+        let display = match gdk::Display::default() {
+            Some(display) => display,
+            None => return,
+        };
+
+        let mut palette_provider = state.palette_provider.borrow_mut();
+        let provider = palette_provider.get_or_insert_with(|| {
+            let provider = gtk::CssProvider::new();
+            stylesheet::report_parsing_errors(&provider);
+            gtk::style_context_add_provider_for_display(&display, &provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+            provider
+        });
+
+        let palette_path = if dark { DARK_PALETTE_PATH } else { LIGHT_PALETTE_PATH };
+        if !Path::new(palette_path).exists() {
+            warn!("Palette at path {palette_path} does not exist");
+            return;
+        }
+
+        debug!("Loading palette {palette_path}");
+        provider.load_from_path(palette_path);
+        state.dark_palette_loaded.set(Some(dark));
+    });
+}
+
+
+/**
+Check if the desktop uses dark colors, through the portal, gtk's settings or a dark gtk theme.
+ */
+fn system_prefers_dark(state: &ThemeState) -> bool {
+    if state.portal_prefers_dark.get() {
+        return true;
+    }
+
+    let settings = match gtk::Settings::default() {
+        Some(settings) => settings,
+        None => return false,
+    };
+    let theme_name = settings.gtk_theme_name().map(|name| name.to_lowercase()).unwrap_or_default();
+
+    settings.is_gtk_application_prefer_dark_theme()
+        || DARK_THEME_SUFFIXES.iter().any(|suffix| theme_name.ends_with(suffix))
+}
+
+
+/**
+Read the desktop portal's color scheme and follow its changes.
+Desktops without the portal simply never ask for dark colors this way.
+ */
+fn watch_portal_color_scheme(connection: &gio::DBusConnection) {
+    connection.call(
+        Some(PORTAL_BUS_NAME),
+        PORTAL_OBJECT_PATH,
+        PORTAL_SETTINGS_INTERFACE,
+        "Read",
+        Some(&(APPEARANCE_NAMESPACE, COLOR_SCHEME_KEY).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+        |result| match result {
+            Ok(reply) => set_portal_color_scheme(&reply.child_value(0)),
+            Err(e) => debug!("The desktop portal's color scheme could not be read: {e}"),
+        },
+    );
+
+    connection.signal_subscribe(
+        Some(PORTAL_BUS_NAME),
+        Some(PORTAL_SETTINGS_INTERFACE),
+        Some("SettingChanged"),
+        Some(PORTAL_OBJECT_PATH),
+        Some(APPEARANCE_NAMESPACE),
+        gio::DBusSignalFlags::NONE,
+        |_, _, _, _, _, parameters| {
+            if let Some((_, key, value)) = parameters.get::<(String, String, glib::Variant)>() {
+                if key == COLOR_SCHEME_KEY {
+                    set_portal_color_scheme(&value);
+                }
+            }
+        },
+    );
+}
+
+
+/**
+Store the portal's color scheme and update the theme if it changed.
+# Parameters:
+* `value`: The color scheme, possibly wrapped in variants.
+ */
+fn set_portal_color_scheme(value: &glib::Variant) {
+    // Older portals wrap the value twice.
+    let mut value = value.clone();
+    while let Some(inner) = value.as_variant() {
+        value = inner;
+    }
+
+    let prefers_dark = value.get::<u32>() == Some(PREFER_DARK_COLOR_SCHEME);
+    let changed = THEME_STATE.with(|state| state.portal_prefers_dark.replace(prefers_dark) != prefers_dark);
+    if changed {
+        debug!("The desktop portal now {} dark colors", if prefers_dark { "prefers" } else { "doesn't prefer" });
+        update_gtk_theme();
+        update_palette();
+    }
+}
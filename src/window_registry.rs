@@ -0,0 +1,48 @@
+use std::{cell::RefCell, rc::{Rc, Weak}};
+
+
+/**
+Keeps track of a part of every open window, like its note tabs, so changes to the notes reach all of them.
+Only weak references are kept, so a part is forgotten once its window drops it.
+Meant to be stored in a `thread_local!`, windows are only touched from the gtk main thread.
+ */
+pub struct WindowRegistry<T> {
+    entries: RefCell<Vec<Weak<T>>>,
+}
+
+
+impl<T> Default for WindowRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+impl<T> WindowRegistry<T> {
+    pub const fn new() -> Self {
+        WindowRegistry { entries: RefCell::new(Vec::new()) }
+    }
+
+    /**
+    Start keeping track of a window's part.
+     */
+    pub fn register(&self, entry: &Rc<T>) {
+        self.entries.borrow_mut().push(Rc::downgrade(entry));
+    }
+
+    /**
+    Forget a window's part before it's dropped, e.g. as soon as its window is closed.
+     */
+    pub fn unregister(&self, entry: &Rc<T>) {
+        self.entries.borrow_mut().retain(|registered| !std::ptr::eq(registered.as_ptr(), Rc::as_ptr(entry)));
+    }
+
+    /**
+    Get the parts of every window that is still open, so they can be changed without holding the registry.
+     */
+    pub fn entries(&self) -> Vec<Rc<T>> {
+        let mut entries = self.entries.borrow_mut();
+        entries.retain(|entry| entry.strong_count() > 0);
+        entries.iter().filter_map(Weak::upgrade).collect()
+    }
+}